@@ -0,0 +1,97 @@
+use std::str::FromStr;
+
+use colored::*;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+
+use crate::{
+    args::AccountArgs,
+    error::OreError,
+    output::print_json,
+    program,
+    schema::{AccountDocument, BusRewards},
+    utils::{
+        amount_u64_to_string, calculate_multiplier, format_timestamp, get_mining_state_with_proof,
+        MiningState,
+    },
+    Miner,
+};
+
+impl Miner {
+    pub async fn account(&self, args: AccountArgs) -> Result<(), OreError> {
+        let address = if let Some(address) = args.address {
+            Pubkey::from_str(&address).map_err(|_| OreError::InvalidAddress(address))?
+        } else {
+            self.signer()?.pubkey()
+        };
+
+        // Fetch accounts
+        let (state, proof) = get_mining_state_with_proof(&self.rpc_client, address).await?;
+        let MiningState { config, busses, .. } = state;
+        let sol_balance = self.rpc_client.get_balance(&address).await.unwrap_or(0);
+        let token_account_address =
+            spl_associated_token_account::get_associated_token_address(&address, &program::mint());
+        let (token_balance, token_amount) = if let Ok(Some(token_account)) = self
+            .rpc_client
+            .get_token_account(&token_account_address)
+            .await
+        {
+            (
+                token_account.token_amount.ui_amount_string,
+                u64::from_str(&token_account.token_amount.amount).unwrap_or(0),
+            )
+        } else {
+            ("0".to_string(), 0)
+        };
+
+        // Print as json
+        if self.is_json() {
+            print_json(&AccountDocument {
+                address: address.to_string(),
+                balance: token_amount,
+                sol_balance,
+                stake: proof.balance,
+                multiplier: calculate_multiplier(proof.balance, config.top_balance),
+                last_hash_at: proof.last_hash_at,
+                total_rewards: proof.total_rewards,
+                busses: busses
+                    .iter()
+                    .map(|bus| BusRewards {
+                        id: bus.id,
+                        rewards: bus.rewards,
+                    })
+                    .collect(),
+            });
+            return Ok(());
+        }
+
+        // Wallet
+        println!("{}", "Wallet".bold());
+        println!("  Address: {}", address);
+        println!("  Balance: {} ORE", token_balance);
+        println!("  Balance: {} SOL", lamports_to_sol(sol_balance));
+
+        // Proof
+        println!("\n{}", "Proof".bold());
+        println!("  Stake: {} ORE", amount_u64_to_string(proof.balance));
+        println!(
+            "  Multiplier: {:.12}x",
+            calculate_multiplier(proof.balance, config.top_balance)
+        );
+        println!("  Last mined at: {}", format_timestamp(proof.last_hash_at));
+        println!(
+            "  Total rewards: {} ORE",
+            amount_u64_to_string(proof.total_rewards)
+        );
+
+        // Busses
+        println!("\n{}", "Busses".bold());
+        for bus in busses {
+            println!(
+                "  Bus {}: {} ORE",
+                bus.id,
+                amount_u64_to_string(bus.rewards)
+            );
+        }
+        Ok(())
+    }
+}
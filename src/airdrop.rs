@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use colored::*;
+use solana_program::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+};
+use tracing::warn;
+
+use crate::{args::AirdropArgs, error::OreError, timeout, Miner};
+
+/// The genesis hash of mainnet-beta, which has no faucet.
+const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+/// How many times to retry an airdrop the faucet turned down.
+const MAX_RETRIES: u32 = 5;
+
+/// The delay before the first retry. It doubles with each retry.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+impl Miner {
+    pub async fn airdrop(&self, args: AirdropArgs) -> Result<(), OreError> {
+        // Refuse to run against mainnet
+        let genesis_hash = self.rpc_client.get_genesis_hash().await?;
+        if genesis_hash.to_string().eq(MAINNET_GENESIS_HASH) {
+            return Err(OreError::Airdrop(
+                "Airdrops are only available on devnet, testnet, and local validators".to_string(),
+            ));
+        }
+
+        // Fund the signer and the fee payer
+        let mut recipients = vec![self.signer()?.pubkey()];
+        let fee_payer = self.fee_payer()?.pubkey();
+        if !recipients.contains(&fee_payer) {
+            recipients.push(fee_payer);
+        }
+        let lamports = sol_to_lamports(args.amount);
+        for recipient in recipients {
+            self.request_airdrop(recipient, lamports).await?;
+            let balance = self.rpc_client.get_balance(&recipient).await?;
+            println!(
+                "{} Airdropped {} SOL to {}. Balance: {} SOL",
+                "OK".bold().green(),
+                args.amount,
+                recipient,
+                lamports_to_sol(balance)
+            );
+        }
+        Ok(())
+    }
+
+    /// Requests an airdrop and waits for it to land, backing off while the faucet is rate
+    /// limited.
+    pub(crate) async fn request_airdrop(
+        &self,
+        pubkey: Pubkey,
+        lamports: u64,
+    ) -> Result<(), OreError> {
+        let mut delay = RETRY_DELAY;
+        let mut retries = 0;
+        let sig = loop {
+            match self.rpc_client.request_airdrop(&pubkey, lamports).await {
+                Ok(sig) => break sig,
+                Err(err) if retries.lt(&MAX_RETRIES) => {
+                    warn!("Airdrop failed, retrying in {}s: {}", delay.as_secs(), err);
+                    self.sleep(delay).await?;
+                    delay *= 2;
+                    retries += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        let started_at = Instant::now();
+        while !self
+            .cancellable(self.rpc_client.confirm_transaction(&sig))
+            .await??
+        {
+            if started_at.elapsed().gt(&timeout::confirm_timeout()) {
+                return Err(OreError::TransactionExpired);
+            }
+            self.sleep(Duration::from_millis(500)).await?;
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,62 @@
-use clap::{arg, Parser};
+use std::path::PathBuf;
+
+use clap::{arg, Parser, Subcommand};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    dca::parse_interval,
+    mine::BusStrategy,
+    notify::EventKind,
+    swap::parse_token,
+    top::TopOrder,
+    utils::{parse_duration, parse_hex},
+};
 
 #[derive(Parser, Debug)]
-pub struct BalanceArgs {
+pub struct AccountArgs {
     #[arg(
         long,
         value_name = "ADDRESS",
-        help = "The address of the account to fetch the balance of"
+        help = "The address of the account to summarize. Defaults to the signer."
+    )]
+    pub address: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct AirdropArgs {
+    #[arg(
+        value_name = "SOL",
+        help = "The amount of SOL to request for the signer and the fee payer."
+    )]
+    pub amount: f64,
+}
+
+#[derive(Parser, Debug)]
+pub struct AuditArgs {
+    #[arg(
+        value_name = "ADDRESS",
+        help = "The authority to audit. Defaults to the signer."
     )]
     pub address: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "The maximum number of recent transactions to replay",
+        default_value = "200"
+    )]
+    pub limit: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct BalanceArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "The address of the account to fetch the balance of. Can be repeated."
+    )]
+    pub address: Vec<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -20,10 +69,189 @@ pub struct BenchmarkArgs {
         default_value = "1"
     )]
     pub threads: u64,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "How long to run each benchmark, in seconds or with a unit suffix (e.g. 30s, 30m, 2h)",
+        default_value = "30",
+        value_parser = parse_duration
+    )]
+    pub duration: u64,
+
+    #[arg(
+        long,
+        value_name = "RANGE",
+        help = "Benchmark every thread count in an inclusive range, e.g. 1..32"
+    )]
+    pub sweep_threads: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Write the benchmark results to a CSV file"
+    )]
+    pub csv: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Save the benchmark results as a named baseline"
+    )]
+    pub save: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Compare the benchmark results against a saved baseline"
+    )]
+    pub compare: Option<String>,
+
+    #[arg(
+        long,
+        help = "Benchmark each physical core in isolation and flag slow cores",
+        conflicts_with = "sweep_threads"
+    )]
+    pub per_core: bool,
+
+    #[arg(
+        long,
+        help = "Sustain full load for the duration while logging CPU temperature, clock speed, and throttling",
+        conflicts_with_all = ["sweep_threads", "per_core"]
+    )]
+    pub stress: bool,
+
+    #[arg(
+        long,
+        value_name = "SEED",
+        help = "Seed for the benchmark challenge and nonces, to reproduce a run across machines. Defaults to a random seed."
+    )]
+    pub seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "WATTS",
+        help = "Power draw of the machine while mining, to estimate profitability",
+        requires = "kwh_price"
+    )]
+    pub watts: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "Electricity price per kWh in USD",
+        requires = "watts"
+    )]
+    pub kwh_price: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "ORE price in USD for profitability estimates. Defaults to the current market price."
+    )]
+    pub ore_price: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "SOL price in USD for profitability estimates. Defaults to the current market price."
+    )]
+    pub sol_price: Option<f64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct BreakevenArgs {
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SEC",
+        help = "Your hashrate. Defaults to measuring it with a short benchmark."
+    )]
+    pub hashrate: Option<u64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of threads to measure the hashrate with",
+        default_value = "1",
+        conflicts_with = "hashrate"
+    )]
+    pub threads: u64,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "How long to measure the hashrate, in seconds or with a unit suffix (e.g. 30s, 2m)",
+        default_value = "10",
+        value_parser = parse_duration,
+        conflicts_with = "hashrate"
+    )]
+    pub duration: u64,
+
+    #[arg(
+        long,
+        value_name = "WATTS",
+        help = "Power draw of the machine while mining",
+        default_value = "0"
+    )]
+    pub watts: f64,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "Electricity price per kWh in USD",
+        default_value = "0"
+    )]
+    pub kwh_price: f64,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "ORE price in USD. Defaults to the current market price."
+    )]
+    pub ore_price: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "SOL price in USD. Defaults to the current market price."
+    )]
+    pub sol_price: Option<f64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ChallengeArgs {
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The number of seconds before the cutoff to stop hashing, subtracted from the cutoff time",
+        default_value = "0"
+    )]
+    pub buffer_time: u64,
 }
 
 #[derive(Parser, Debug)]
-pub struct BussesArgs {}
+pub struct BussesArgs {
+    #[arg(long, help = "Continuously refresh the bus balances")]
+    pub watch: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The number of seconds between refreshes in watch mode",
+        default_value = "2",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub interval: u64,
+
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        help = "Strategy used to highlight the bus a miner would select. Must be one of 'random', 'max-balance', 'round-robin', or 'fixed:<id>'.",
+        default_value = "random"
+    )]
+    pub bus_strategy: BusStrategy,
+}
 
 #[derive(Parser, Debug)]
 pub struct ClaimArgs {
@@ -40,76 +268,1114 @@ pub struct ClaimArgs {
         help = "Wallet to receive claimed tokens."
     )]
     pub to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIRECTORY",
+        help = "Claim all rewards of every keypair file in this directory, each to its own wallet.",
+        conflicts_with_all = ["amount", "to"]
+    )]
+    pub keypair_dir: Option<String>,
+
+    #[arg(
+        long,
+        help = "Retry the claims left over from a --keypair-dir batch that did not finish",
+        conflicts_with_all = ["amount", "to", "keypair_dir", "auto_sell_pct"]
+    )]
+    pub resume: bool,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Sell this share of the claimed ORE through Jupiter",
+        conflicts_with_all = ["to", "keypair_dir"],
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub auto_sell_pct: Option<u8>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "The token to sell claimed ORE for: USDC, USDT, SOL, or a mint address",
+        default_value = "USDC",
+        value_parser = parse_token
+    )]
+    pub auto_sell_to: Pubkey,
+
+    #[arg(
+        long,
+        value_name = "BPS",
+        help = "The maximum slippage of the sale, in basis points, up to 1000 unless --allow-high-slippage is given",
+        default_value = "50",
+        value_parser = clap::value_parser!(u16).range(1..=10000)
+    )]
+    pub slippage_bps: u16,
+
+    #[arg(long, help = "Allow a slippage above 1000 basis points")]
+    pub allow_high_slippage: bool,
 }
 
 #[derive(Parser, Debug)]
-pub struct CloseArgs {}
+pub struct CloseArgs {
+    #[arg(
+        long,
+        help = "Claim any unclaimed rewards and close the account anyway"
+    )]
+    pub force: bool,
+}
 
 #[derive(Parser, Debug)]
-pub struct ConfigArgs {}
+pub struct ConfigArgs {
+    #[arg(long, help = "Print the program config, treasury, and mint as JSON")]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Option<ConfigCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    #[command(
+        about = "Check the ore-cli config file for mistakes and print the effective settings"
+    )]
+    Validate(ConfigValidateArgs),
+}
 
-#[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
-pub struct PauseArgs {}
+pub struct ConfigValidateArgs {}
 
-#[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
-pub struct InitializeArgs {}
+pub struct DcaArgs {
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "The amount of ORE to sell each time"
+    )]
+    pub amount: f64,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "How often to sell, e.g. 30m, 12h, or 1d. At least a minute.",
+        value_parser = parse_interval
+    )]
+    pub every: u64,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "The token to buy: USDC, USDT, SOL, or a mint address",
+        default_value = "USDC",
+        value_parser = parse_token
+    )]
+    pub to: Pubkey,
+
+    #[arg(
+        long,
+        value_name = "BPS",
+        help = "The maximum slippage of each sale, in basis points, up to 1000 unless --allow-high-slippage is given",
+        default_value = "50",
+        value_parser = clap::value_parser!(u16).range(1..=10000)
+    )]
+    pub slippage_bps: u16,
+
+    #[arg(long, help = "Allow a slippage above 1000 basis points")]
+    pub allow_high_slippage: bool,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Where to keep the schedule's progress. Defaults to ~/.config/ore/dca.json."
+    )]
+    pub state_file: Option<PathBuf>,
+}
 
 #[derive(Parser, Debug)]
-pub struct MineArgs {
-    // #[cfg(not(feature = "gpu"))]
+pub struct ExportArgs {
     #[arg(
         long,
-        short,
-        value_name = "THREAD_COUNT",
-        help = "The number of CPU threads to allocate to mining",
-        default_value = "1"
+        value_name = "FORMAT",
+        help = "The report format. Must be one of 'csv' or 'koinly'. The koinly format omits claims, which move already-earned rewards into your wallet.",
+        default_value = "csv"
     )]
-    pub threads: u64,
+    pub format: String,
 
     #[arg(
         long,
-        short,
-        value_name = "SECONDS",
-        help = "The number seconds before the deadline to stop mining and start submitting",
-        default_value = "5"
+        value_name = "ADDRESS",
+        help = "The authority of the proof account to export. Defaults to the signer."
     )]
-    pub buffer_time: u64,
+    pub address: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "The maximum number of transactions to scan",
+        default_value = "1000"
+    )]
+    pub limit: usize,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Write the report to this file instead of stdout"
+    )]
+    pub out_file: Option<String>,
+
+    #[arg(long, help = "Include the daily USD price of ORE for each transaction")]
+    pub usd: bool,
+
+    #[arg(
+        long,
+        help = "Export transactions from the local database instead of scanning the RPC"
+    )]
+    pub local: bool,
 }
 
 #[derive(Parser, Debug)]
-pub struct RewardsArgs {}
+pub struct GlobalArgs {}
 
 #[derive(Parser, Debug)]
-pub struct StakeArgs {
+pub struct HistoryArgs {
     #[arg(
         long,
-        value_name = "AMOUNT",
-        help = "The amount of Ore to stake. Defaults to max."
+        value_name = "COUNT",
+        help = "The number of recent transactions to show",
+        default_value = "20"
     )]
-    pub amount: Option<f64>,
+    pub limit: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct KeyArgs {
+    #[command(subcommand)]
+    pub command: KeyCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeyCommand {
+    #[command(about = "Encrypt a keypair file with a passphrase")]
+    Encrypt(KeyEncryptArgs),
+
+    #[command(about = "Store a keypair in the OS keyring, for use as --keypair keyring:NAME")]
+    Store(KeyStoreArgs),
+
+    #[command(about = "Remove a keypair from the OS keyring")]
+    Remove(KeyRemoveArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct KeyEncryptArgs {
+    #[arg(value_name = "PATH", help = "The keypair file to encrypt")]
+    pub path: String,
 
     #[arg(
         long,
-        value_name = "TOKEN_ACCOUNT_ADDRESS",
-        help = "Token account to send Ore from."
+        value_name = "PATH",
+        help = "Write the encrypted keypair here and keep the original. Defaults to replacing the original."
     )]
-    pub sender: Option<String>,
+    pub out_file: Option<String>,
 }
 
-#[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
-pub struct UpdateAdminArgs {
-    pub new_admin: String,
+pub struct KeyStoreArgs {
+    #[arg(value_name = "NAME", help = "The name to store the keypair under")]
+    pub name: String,
+
+    #[arg(
+        value_name = "KEYPAIR",
+        help = "The keypair to store. Accepts the same sources as --keypair."
+    )]
+    pub source: String,
 }
 
 #[derive(Parser, Debug)]
-pub struct UpgradeArgs {
+pub struct KeyRemoveArgs {
+    #[arg(value_name = "NAME", help = "The name of the keypair to remove")]
+    pub name: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct KeygenArgs {
     #[arg(
         long,
-        value_name = "AMOUNT",
-        help = "The amount of Ore to upgrade from v1 to v2. Defaults to max."
+        value_name = "PATH",
+        help = "Filepath to write the new keypair to",
+        default_value = "id.json"
     )]
-    pub amount: Option<f64>,
+    pub outfile: String,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        help = "Grind for a keypair whose address starts with this prefix"
+    )]
+    pub prefix: Option<String>,
+
+    #[arg(
+        long,
+        help = "Match the prefix regardless of case",
+        requires = "prefix"
+    )]
+    pub ignore_case: bool,
+
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of threads to grind with. Defaults to the number of CPUs.",
+        requires = "prefix"
+    )]
+    pub threads: Option<u64>,
+
+    #[arg(long, help = "Overwrite the file if it already exists")]
+    pub force: bool,
+
+    #[arg(long, help = "Open a proof account for the new keypair")]
+    pub open: bool,
+}
+
+#[cfg(feature = "admin")]
+#[derive(Parser, Debug)]
+pub struct PauseArgs {}
+
+#[cfg(feature = "admin")]
+#[derive(Parser, Debug)]
+pub struct InitializeArgs {}
+
+#[cfg(feature = "dev")]
+#[derive(Parser, Debug)]
+pub struct LocalnetArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Compiled ORE program to preload. It must accept --keypair as its initializer."
+    )]
+    pub program: String,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Compiled Metaplex token metadata program to preload. Defaults to cloning it from mainnet."
+    )]
+    pub metadata_program: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Ledger directory of the validator. Defaults to ~/.config/ore/localnet/ledger."
+    )]
+    pub ledger: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Port of the validator's RPC server",
+        default_value = "8899"
+    )]
+    pub rpc_port: u16,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "SOL to airdrop to the initializer and the test keypair",
+        default_value = "100"
+    )]
+    pub airdrop: f64,
+
+    #[arg(long, help = "Start from an empty ledger")]
+    pub reset: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct MineArgs {
+    // #[cfg(not(feature = "gpu"))]
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of CPU threads to allocate to mining",
+        default_value = "1"
+    )]
+    pub threads: u64,
+
+    #[arg(
+        long,
+        value_name = "NONCE",
+        help = "Start hashing at this nonce. Defaults to an offset derived from the machine ID, so machines mining the same proof hash different nonces."
+    )]
+    pub nonce_offset: Option<u64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "The number seconds before the deadline to stop mining and start submitting",
+        default_value = "5"
+    )]
+    pub buffer_time: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Hold submissions from this many seconds before an epoch reset until this many seconds after it, when transactions often fail. 0 submits regardless.",
+        default_value = "5"
+    )]
+    pub reset_guard: u64,
+
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        help = "Strategy for picking a bus. Must be one of 'random', 'max-balance', 'round-robin', or 'fixed:<id>'.",
+        default_value = "random"
+    )]
+    pub bus_strategy: BusStrategy,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Serve Prometheus metrics on this address, e.g. 0.0.0.0:9090"
+    )]
+    pub metrics_addr: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Touch this file after each round that lands, for container liveness probes"
+    )]
+    pub health_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "10m",
+        value_parser = parse_duration,
+        help = "How long without a landed round before /healthz on the metrics address reports the miner as stalled"
+    )]
+    pub health_max_age: u64,
+
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        help = "Notify when no mine transaction has landed for the signer in this many minutes"
+    )]
+    pub stall_alarm: Option<u64>,
+
+    #[arg(
+        long,
+        requires = "stall_alarm",
+        help = "Also restart the mining loop when the stall alarm fires"
+    )]
+    pub stall_restart: bool,
+
+    #[arg(
+        long,
+        help = "Subscribe to slot updates and send each mine transaction at the start of one of the first slots of a leader's window"
+    )]
+    pub slot_timing: bool,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        requires = "slot_timing",
+        help = "The websocket URL for slot updates. Defaults to the one of the RPC URL."
+    )]
+    pub ws_url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Push per-round metrics to this InfluxDB line-protocol write endpoint, e.g. http://localhost:8086/api/v2/write?org=ore&bucket=mining"
+    )]
+    pub influx_url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        requires = "influx_url",
+        help = "API token sent with InfluxDB writes"
+    )]
+    pub influx_token: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Emit per-round StatsD metrics over UDP to this address, e.g. localhost:8125"
+    )]
+    pub statsd_addr: Option<String>,
+
+    #[arg(
+        long,
+        requires = "statsd_addr",
+        help = "Tag StatsD metrics with the miner authority using the DogStatsD format"
+    )]
+    pub dogstatsd: bool,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Append a row to this CSV file for every submitted mine transaction"
+    )]
+    pub csv_log: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Stop mining after this many rounds"
+    )]
+    pub rounds: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Write the end-of-session summary to this JSON file"
+    )]
+    pub summary_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        allow_negative_numbers = true,
+        help = "Pause mining while the expected reward of a round, valued at the current ORE price, leaves less than this share after fees"
+    )]
+    pub min_profit_margin: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "ROUNDS",
+        default_value = "3",
+        requires = "min_profit_margin",
+        help = "How many consecutive rounds must fall below --min-profit-margin before mining pauses"
+    )]
+    pub unprofitable_rounds: u64,
+
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Raise the priority fee by this much while mine transactions expire without landing, and lower it by as much after each round that lands, up to --dynamic-fee-max"
+    )]
+    pub fee_escalation_step: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "ROUNDS",
+        default_value = "1",
+        requires = "fee_escalation_step",
+        help = "How many consecutive rounds must expire before each raise of the priority fee"
+    )]
+    pub fee_escalation_rounds: u64,
+
+    #[arg(
+        long,
+        help = "Show a live dashboard instead of the scrolling output. Console logs are hidden; use --log-file to keep them."
+    )]
+    pub tui: bool,
+
+    #[arg(
+        long,
+        help = "Detach from the terminal and mine in the background. Logs go to --log-file, or ~/.config/ore/ore.log. Stop it with `ore stop`.",
+        conflicts_with = "tui"
+    )]
+    pub daemon: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the pid of the miner to this file while it runs. Defaults to ~/.config/ore/ore.pid with --daemon."
+    )]
+    pub pid_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Mine even if another ore process is already mining the same proof"
+    )]
+    pub force: bool,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Serve a live web dashboard on this address, e.g. 127.0.0.1:8080"
+    )]
+    pub dashboard_addr: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        requires = "api_token",
+        help = "Serve the control API on this address, e.g. 127.0.0.1:8081"
+    )]
+    pub api_addr: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "Bearer token required by every control API request"
+    )]
+    pub api_token: Option<String>,
+
+    #[command(flatten)]
+    pub notify: NotifyArgs,
+}
+
+#[derive(Parser, Debug)]
+pub struct NotifyArgs {
+    #[arg(
+        long,
+        value_name = "WEBHOOK_URL",
+        help = "Send notifications to this Discord webhook"
+    )]
+    pub notify_discord: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        requires = "telegram_chat_id",
+        help = "Send notifications through this Telegram bot"
+    )]
+    pub telegram_token: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CHAT_ID",
+        requires = "telegram_token",
+        help = "Telegram chat to send notifications to"
+    )]
+    pub telegram_chat_id: Option<String>,
+
+    #[arg(
+        long,
+        requires = "telegram_token",
+        help = "Answer /status and /claim commands sent to the Telegram bot from the chat"
+    )]
+    pub telegram_commands: bool,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "POST a JSON payload for each event to this URL. Can be repeated."
+    )]
+    pub notify_url: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        requires = "notify_url",
+        help = "JSON payload template for --notify-url, or @FILEPATH to read it from a file. {{field}} placeholders are replaced with event fields."
+    )]
+    pub notify_template: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "HOST",
+        requires_all = ["smtp_from", "smtp_to"],
+        help = "Email critical alerts (low SOL, stalled, or stopped miner) through this SMTP server"
+    )]
+    pub smtp_host: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        default_value = "587",
+        help = "SMTP server port. Port 465 uses implicit TLS; other ports use STARTTLS."
+    )]
+    pub smtp_port: u16,
+
+    #[arg(long, value_name = "USERNAME", help = "SMTP username")]
+    pub smtp_username: Option<String>,
+
+    #[arg(long, value_name = "PASSWORD", help = "SMTP password")]
+    pub smtp_password: Option<String>,
+
+    #[arg(long, value_name = "ADDRESS", help = "Sender address for email alerts")]
+    pub smtp_from: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Recipient address for email alerts. Can be repeated."
+    )]
+    pub smtp_to: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "EVENTS",
+        value_delimiter = ',',
+        default_value = "landed,claim,low-sol,rpc-down,stalled,stopped,unprofitable,price",
+        help = "Comma separated events to send notifications for"
+    )]
+    pub notify_events: Vec<EventKind>,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        default_value = "0",
+        help = "Only notify about landed transactions with at least this difficulty"
+    )]
+    pub notify_min_difficulty: u32,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        default_value = "0.1",
+        help = "Notify when the fee payer balance drops below this amount"
+    )]
+    pub notify_low_sol: f64,
+
+    #[arg(
+        long,
+        value_name = "ROUNDS",
+        default_value = "5",
+        help = "Notify when this many consecutive rounds fail to land a transaction"
+    )]
+    pub notify_stall_rounds: u64,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "Notify when the ORE price rises above this"
+    )]
+    pub alert_price_above: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "Notify when the ORE price falls below this"
+    )]
+    pub alert_price_below: Option<f64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct OpenArgs {
+    #[arg(
+        long,
+        value_name = "MINER_ADDRESS",
+        help = "The address allowed to submit hashes for the proof. Defaults to the proof authority."
+    )]
+    pub miner: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIRECTORY",
+        help = "Open a proof for every keypair file in this directory, using each keypair as its own authority.",
+        conflicts_with = "miner"
+    )]
+    pub keypair_dir: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ProfileArgs {
+    #[command(subcommand)]
+    pub command: ProfileCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommand {
+    #[command(about = "List the profiles in the config file")]
+    List,
+
+    #[command(about = "Set the profile used when --profile is not given")]
+    SetDefault(ProfileSetDefaultArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ProfileSetDefaultArgs {
+    #[arg(value_name = "NAME", help = "The name of the profile")]
+    pub name: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct ProofArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "The authority of the proof account to fetch. Defaults to the signer."
+    )]
+    pub address: Option<String>,
+
+    #[arg(long, help = "Print the proof account as JSON")]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct ReportArgs {
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "How far back to report, in seconds or with a unit suffix (e.g. 12h, 7d)",
+        default_value = "7d",
+        value_parser = parse_duration
+    )]
+    pub since: u64,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "ORE price in USD. Defaults to the current market price."
+    )]
+    pub ore_price: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        help = "SOL price in USD. Defaults to the current market price."
+    )]
+    pub sol_price: Option<f64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct RewardsArgs {
+    #[arg(long, help = "Print the reward rates as JSON")]
+    pub json: bool,
+
+    #[arg(
+        long,
+        value_name = "FILEPATH",
+        help = "Export the full reward curve to a CSV file"
+    )]
+    pub csv: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct SelfUpdateArgs {
+    #[arg(long, help = "Only check whether a newer release is available")]
+    pub check_only: bool,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Require the release binary to be signed by this public key instead of the pinned release key"
+    )]
+    pub signer: Option<String>,
+
+    #[arg(long, help = "Reinstall the latest release even if it is not newer")]
+    pub force: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct ServiceArgs {
+    #[command(subcommand)]
+    pub command: ServiceCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ServiceCommand {
+    #[command(about = "Register a service that runs the miner with these arguments at boot")]
+    Install(ServiceInstallArgs),
+
+    #[command(about = "Stop and remove the service")]
+    Uninstall(ServiceNameArgs),
+
+    #[command(about = "Start the service")]
+    Start(ServiceNameArgs),
+
+    #[command(about = "Stop the service")]
+    Stop(ServiceNameArgs),
+
+    #[command(hide = true)]
+    Run(ServiceRunArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ServiceInstallArgs {
+    #[arg(
+        long,
+        value_name = "NAME",
+        default_value = "ore",
+        help = "The name of the service."
+    )]
+    pub name: String,
+
+    #[arg(long, help = "Start the service after installing it.")]
+    pub start: bool,
+
+    #[arg(
+        long,
+        value_name = "ACCOUNT",
+        help = "The account to run the service as, e.g. .\\miner or NT AUTHORITY\\LocalService. Asks for the password of a user account. Defaults to LocalSystem, which has full control of the machine."
+    )]
+    pub account: Option<String>,
+
+    #[arg(
+        value_name = "MINE_ARGS",
+        last = true,
+        help = "The arguments to run `mine` with, after --. For example: -- --threads 8"
+    )]
+    pub mine_args: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ServiceNameArgs {
+    #[arg(
+        long,
+        value_name = "NAME",
+        default_value = "ore",
+        help = "The name of the service."
+    )]
+    pub name: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct ServiceRunArgs {
+    #[arg(long, value_name = "NAME", default_value = "ore")]
+    pub name: String,
+
+    #[arg(long, value_name = "PATH")]
+    pub home: Option<PathBuf>,
+
+    #[arg(value_name = "COMMAND", last = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct SetupArgs {}
+
+#[derive(Parser, Debug)]
+pub struct ShellArgs {}
+
+#[derive(Parser, Debug)]
+pub struct StatsArgs {
+    #[arg(
+        long,
+        value_name = "DAYS",
+        help = "The number of recent days to summarize",
+        default_value = "7"
+    )]
+    pub days: usize,
+
+    #[arg(
+        long,
+        value_name = "PUBKEY",
+        help = "Only summarize the activity of this wallet"
+    )]
+    pub wallet: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct StakeArgs {
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "The amount of Ore to stake. Defaults to max."
+    )]
+    pub amount: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN_ACCOUNT_ADDRESS",
+        help = "Token account to send Ore from."
+    )]
+    pub sender: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "Simulate the multiplier for an additional stake of this amount without sending a transaction."
+    )]
+    pub simulate: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "The difficulty to assume per submission when simulating rewards. Defaults to the min difficulty.",
+        requires = "simulate"
+    )]
+    pub difficulty: Option<u32>,
+}
+
+#[derive(Parser, Debug)]
+pub struct StatusArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "The pid file of the miner. Defaults to ~/.config/ore/ore.pid."
+    )]
+    pub pid_file: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct StopArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "The pid file of the miner. Defaults to ~/.config/ore/ore.pid."
+    )]
+    pub pid_file: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct SystemdInstallArgs {
+    #[arg(
+        long,
+        value_name = "NAME",
+        default_value = "ore",
+        help = "The name of the service."
+    )]
+    pub name: String,
+
+    #[arg(
+        long,
+        help = "Install a user service in ~/.config/systemd/user instead of a system service."
+    )]
+    pub user: bool,
+
+    #[arg(long, help = "Print the unit file without installing it.")]
+    pub print: bool,
+
+    #[arg(
+        value_name = "MINE_ARGS",
+        last = true,
+        help = "The arguments to run `mine` with, after --. For example: -- --threads 8"
+    )]
+    pub mine_args: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct SubmitArgs {
+    #[arg(
+        value_name = "FILE",
+        help = "File holding the solution as JSON, with the hex encoded `digest` and the `nonce`, and optionally the `challenge` it solves. Reads stdin if omitted or -."
+    )]
+    pub file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Hold the submission from this many seconds before an epoch reset until this many seconds after it. 0 submits regardless.",
+        default_value = "5"
+    )]
+    pub reset_guard: u64,
+
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        help = "Strategy for picking a bus. Must be one of 'random', 'max-balance', 'round-robin', or 'fixed:<id>'.",
+        default_value = "random"
+    )]
+    pub bus_strategy: BusStrategy,
+}
+
+#[derive(Parser, Debug)]
+pub struct SwapArgs {
+    #[arg(value_name = "AMOUNT", help = "The amount of ORE to sell.")]
+    pub amount: f64,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "The token to buy: USDC, USDT, SOL, or a mint address",
+        default_value = "USDC",
+        value_parser = parse_token
+    )]
+    pub to: Pubkey,
+
+    #[arg(
+        long,
+        value_name = "BPS",
+        help = "The maximum slippage, in basis points, up to 1000 unless --allow-high-slippage is given",
+        default_value = "50",
+        value_parser = clap::value_parser!(u16).range(1..=10000)
+    )]
+    pub slippage_bps: u16,
+
+    #[arg(long, help = "Allow a slippage above 1000 basis points")]
+    pub allow_high_slippage: bool,
+
+    #[arg(long, help = "Print the quote without swapping")]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct TopArgs {
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "COUNT",
+        help = "The number of miners to list",
+        default_value = "10"
+    )]
+    pub limit: usize,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ORDER",
+        help = "What to rank miners by",
+        default_value = "rewards"
+    )]
+    pub by: TopOrder,
+
+    #[arg(long, help = "Print the leaderboard as JSON")]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct TransferArgs {
+    #[arg(value_name = "AMOUNT", help = "The amount of ORE to transfer.")]
+    pub amount: f64,
+
+    #[arg(
+        value_name = "RECIPIENT_ADDRESS",
+        help = "The wallet address of the recipient."
+    )]
+    pub to: String,
+}
+
+#[cfg(feature = "admin")]
+#[derive(Parser, Debug)]
+pub struct UpdateAdminArgs {
+    pub new_admin: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct UpgradeArgs {
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "The amount of Ore to upgrade from v1 to v2 from each token account. Defaults to max."
+    )]
+    pub amount: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN_ACCOUNT_ADDRESS",
+        help = "A v1 token account to upgrade. Can be repeated. Defaults to all v1 token accounts owned by the signer.",
+        num_args = 1..,
+    )]
+    pub accounts: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Preview the accounts and amounts to upgrade without sending any transactions"
+    )]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct VerifyArgs {
+    #[arg(
+        long,
+        value_name = "HEX",
+        help = "The challenge the solution was computed for",
+        value_parser = parse_hex::<32>
+    )]
+    pub challenge: [u8; 32],
+
+    #[arg(long, value_name = "NONCE", help = "The nonce of the solution")]
+    pub nonce: u64,
+
+    #[arg(
+        long,
+        value_name = "HEX",
+        help = "The equix digest of the solution",
+        value_parser = parse_hex::<16>
+    )]
+    pub digest: [u8; 16],
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Fail unless the solution reaches this difficulty",
+        default_value = "0"
+    )]
+    pub min_difficulty: u32,
 }
@@ -0,0 +1,179 @@
+use std::{collections::HashMap, str::FromStr};
+
+use colored::*;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+
+use crate::{
+    args::AuditArgs,
+    error::OreError,
+    export::HistoryKind,
+    ledger::{self, LedgerKind},
+    output::print_json,
+    schema::{AuditDocument, AuditIssueDocument},
+    utils::{amount_u64_to_string, format_timestamp, get_proof_with_authority},
+    Miner,
+};
+
+impl Miner {
+    pub async fn audit(&self, args: AuditArgs) -> Result<(), OreError> {
+        // Parse authority
+        let authority = match args.address {
+            Some(address) => {
+                Pubkey::from_str(&address).map_err(|_| OreError::InvalidAddress(address))?
+            }
+            None => self.signer()?.pubkey(),
+        };
+
+        // Replay the transaction history from the RPC
+        get_proof_with_authority(&self.rpc_client, authority).await?;
+        let history = self.fetch_history(authority, args.limit).await;
+        let on_chain: HashMap<String, _> = history
+            .iter()
+            .map(|entry| (entry.signature.to_string(), entry))
+            .collect();
+
+        // Only local entries within the scanned window can be reconciled
+        let since = history.first().map(|entry| entry.timestamp).unwrap_or(0);
+        let local: Vec<_> = ledger::load()
+            .into_iter()
+            .filter(|entry| entry.authority.eq(&authority.to_string()) && entry.timestamp >= since)
+            .collect();
+
+        // Reconcile
+        let mut issues = vec![];
+        for entry in local.iter() {
+            let Some(signature) = entry.signature.clone().filter(|_| entry.landed) else {
+                issues.push(AuditIssueDocument {
+                    issue: "never_landed".to_string(),
+                    kind: entry.kind.as_str().to_string(),
+                    timestamp: entry.timestamp,
+                    signature: entry.signature.clone(),
+                    amount: 0,
+                    recorded_amount: Some(entry.amount),
+                    fee: entry.fee,
+                });
+                continue;
+            };
+            match on_chain.get(&signature) {
+                None => issues.push(AuditIssueDocument {
+                    issue: "missing_on_chain".to_string(),
+                    kind: entry.kind.as_str().to_string(),
+                    timestamp: entry.timestamp,
+                    signature: Some(signature),
+                    amount: 0,
+                    recorded_amount: Some(entry.amount),
+                    fee: entry.fee,
+                }),
+                Some(tx) if tx.amount.ne(&entry.amount) => issues.push(AuditIssueDocument {
+                    issue: "amount_mismatch".to_string(),
+                    kind: entry.kind.as_str().to_string(),
+                    timestamp: tx.timestamp,
+                    signature: Some(signature),
+                    amount: tx.amount,
+                    recorded_amount: Some(entry.amount),
+                    fee: tx.fee,
+                }),
+                Some(_) => {}
+            }
+        }
+        for tx in history.iter() {
+            let signature = tx.signature.to_string();
+            if !local
+                .iter()
+                .any(|entry| entry.signature.as_ref().eq(&Some(&signature)))
+            {
+                issues.push(AuditIssueDocument {
+                    issue: "not_recorded".to_string(),
+                    kind: match tx.kind {
+                        HistoryKind::Mine => LedgerKind::Mine,
+                        HistoryKind::Claim => LedgerKind::Claim,
+                    }
+                    .as_str()
+                    .to_string(),
+                    timestamp: tx.timestamp,
+                    signature: Some(signature),
+                    amount: tx.amount,
+                    recorded_amount: None,
+                    fee: tx.fee,
+                });
+            }
+        }
+        issues.sort_by_key(|issue| issue.timestamp);
+
+        // Total rewards and fees
+        let sum = |kind: HistoryKind| {
+            history
+                .iter()
+                .filter(|tx| tx.kind.eq(&kind))
+                .map(|tx| tx.amount)
+                .sum::<u64>()
+        };
+        let document = AuditDocument {
+            authority: authority.to_string(),
+            transactions: history.len(),
+            recorded_transactions: local.len(),
+            rewards: sum(HistoryKind::Mine),
+            claimed: sum(HistoryKind::Claim),
+            fees: history.iter().map(|tx| tx.fee).sum(),
+            recorded_fees: local.iter().map(|entry| entry.fee).sum(),
+            issues,
+        };
+
+        // Print as json
+        if self.is_json() {
+            print_json(&document);
+            return Ok(());
+        }
+
+        // Print human readable
+        println!("{}: {}", "Authority".bold(), document.authority);
+        println!(
+            "{}: {} on-chain, {} in the local database",
+            "Transactions".bold(),
+            document.transactions,
+            document.recorded_transactions
+        );
+        println!(
+            "{}: {} ORE",
+            "Rewards".bold(),
+            amount_u64_to_string(document.rewards)
+        );
+        println!(
+            "{}: {} ORE",
+            "Claimed".bold(),
+            amount_u64_to_string(document.claimed)
+        );
+        println!(
+            "{}: {} SOL on-chain, {} SOL in the local database",
+            "Fees".bold(),
+            lamports_to_sol(document.fees),
+            lamports_to_sol(document.recorded_fees)
+        );
+        if document.issues.is_empty() {
+            println!(
+                "\n{} The local database matches the on-chain history",
+                "OK".bold().green()
+            );
+            return Ok(());
+        }
+        println!(
+            "\n{:<23} {:<17} {:<6} {:>14} {:>14}  Signature",
+            "Time", "Issue", "Kind", "Amount (ORE)", "Recorded (ORE)"
+        );
+        for issue in document.issues.iter() {
+            println!(
+                "{:<23} {:<17} {:<6} {:>14} {:>14}  {}",
+                format_timestamp(issue.timestamp),
+                issue.issue.yellow(),
+                issue.kind,
+                amount_u64_to_string(issue.amount),
+                issue
+                    .recorded_amount
+                    .map(amount_u64_to_string)
+                    .unwrap_or_default(),
+                issue.signature.clone().unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+}
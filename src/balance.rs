@@ -1,45 +1,110 @@
 use std::str::FromStr;
 
+use colored::*;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
 
 use crate::{
     args::BalanceArgs,
+    error::OreError,
+    output::{finish_batch, new_batch_progress, print_json},
+    program,
+    schema::BalanceDocument,
     utils::{amount_u64_to_string, get_proof_with_authority},
     Miner,
 };
 
 impl Miner {
-    pub async fn balance(&self, args: BalanceArgs) {
-        let signer = self.signer();
-        let address = if let Some(address) = args.address {
-            if let Ok(address) = Pubkey::from_str(&address) {
-                address
-            } else {
-                println!("Invalid address: {:?}", address);
-                return;
+    pub async fn balance(&self, args: BalanceArgs) -> Result<(), OreError> {
+        // Parse addresses
+        let mut addresses = vec![];
+        for address in args.address.iter() {
+            addresses.push(
+                Pubkey::from_str(address).map_err(|_| OreError::InvalidAddress(address.clone()))?,
+            );
+        }
+        if addresses.is_empty() {
+            addresses.push(self.signer()?.pubkey());
+        }
+
+        // Fetch a single balance
+        if let [address] = addresses[..] {
+            let (token_balance, stake) = self.fetch_balance(address).await?;
+            if self.is_json() {
+                print_json(&BalanceDocument {
+                    address: address.to_string(),
+                    balance: token_balance,
+                    stake,
+                });
+                return Ok(());
             }
-        } else {
-            signer.pubkey()
-        };
-        let proof = get_proof_with_authority(&self.rpc_client, address).await;
-        let token_account_address = spl_associated_token_account::get_associated_token_address(
-            &address,
-            &ore_api::consts::MINT_ADDRESS,
+            println!(
+                "Balance: {} ORE\nStake: {} ORE",
+                amount_u64_to_string(token_balance),
+                amount_u64_to_string(stake)
+            );
+            return Ok(());
+        }
+
+        // Fetch balances of every address
+        let mut balances = vec![];
+        let progress_bar = new_batch_progress(addresses.len());
+        for address in addresses {
+            progress_bar.set_message(address.to_string());
+            let (token_balance, stake) = self.fetch_balance(address).await?;
+            balances.push((address, token_balance, stake));
+            progress_bar.inc(1);
+        }
+        finish_batch(&progress_bar);
+        if self.is_json() {
+            for (address, token_balance, stake) in balances.iter() {
+                print_json(&BalanceDocument {
+                    address: address.to_string(),
+                    balance: *token_balance,
+                    stake: *stake,
+                });
+            }
+            return Ok(());
+        }
+        println!(
+            "{}",
+            format!(
+                "{:<44} {:>20} {:>20}",
+                "Address", "Balance (ORE)", "Stake (ORE)"
+            )
+            .bold()
         );
-        let token_balance = if let Ok(Some(token_account)) = self
+        for (address, token_balance, stake) in balances.iter() {
+            println!(
+                "{:<44} {:>20} {:>20}",
+                address.to_string(),
+                amount_u64_to_string(*token_balance),
+                amount_u64_to_string(*stake)
+            );
+        }
+        println!(
+            "{:<44} {:>20} {:>20}",
+            "Total",
+            amount_u64_to_string(balances.iter().map(|(_, balance, _)| balance).sum()),
+            amount_u64_to_string(balances.iter().map(|(_, _, stake)| stake).sum())
+        );
+        Ok(())
+    }
+
+    /// Returns the wallet token balance and the proof stake of an address.
+    async fn fetch_balance(&self, address: Pubkey) -> Result<(u64, u64), OreError> {
+        let proof = get_proof_with_authority(&self.rpc_client, address).await?;
+        let token_account_address =
+            spl_associated_token_account::get_associated_token_address(&address, &program::mint());
+        let token_balance = match self
             .rpc_client
             .get_token_account(&token_account_address)
             .await
         {
-            token_account.token_amount.ui_amount_string
-        } else {
-            "0".to_string()
+            Ok(Some(token_account)) => {
+                u64::from_str(&token_account.token_amount.amount).unwrap_or(0)
+            }
+            _ => 0,
         };
-        println!(
-            "Balance: {} ORE\nStake: {} ORE",
-            token_balance,
-            amount_u64_to_string(proof.balance)
-        )
+        Ok((token_balance, proof.balance))
     }
 }
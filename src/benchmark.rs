@@ -1,62 +1,802 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use solana_rpc_client::spinner;
+use chrono::Utc;
+use colored::*;
+use core_affinity::CoreId;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-use crate::{args::BenchmarkArgs, Miner};
+use ore_api::{consts::ONE_MINUTE, state::Config};
+use ore_utils::AccountDeserialize;
 
-const TEST_DURATION: i64 = 30;
+use solana_program::native_token::{lamports_to_sol, LAMPORTS_PER_SOL};
+use tracing::{error, warn};
+
+use crate::{
+    args::BenchmarkArgs,
+    cu_limits::CU_LIMIT_MINE_TX,
+    output::{finish_progress, new_progress_bar, print_json, print_status},
+    price::{resolve_prices, Prices},
+    program,
+    schema::{
+        BenchmarkDocument, BenchmarkProfitability, BenchmarkResult, CoreBenchmarkDocument,
+        CoreBenchmarkResult, StressTestDocument,
+    },
+    utils::{amount_f64_to_u64, amount_u64_to_f64, amount_u64_to_string, ore_dir},
+    Miner,
+};
+
+/// The largest difficulty considered when estimating the best hash of a round.
+const MAX_DIFFICULTY: u32 = 63;
+
+/// The number of rounds a miner can submit per day.
+pub(crate) const ROUNDS_PER_DAY: u64 = 24 * 60 * 60 / ONE_MINUTE as u64;
+
+/// The base fee of a transaction with one signature, in lamports.
+const BASE_FEE_LAMPORTS: u64 = 5000;
+
+/// The number of seconds between stress test samples.
+const STRESS_SAMPLE_INTERVAL: u64 = 5;
+
+/// Clock speeds below this fraction of the observed peak are logged as throttling.
+const THROTTLE_CLOCK_THRESHOLD: f64 = 0.85;
+
+/// Cores below this fraction of the median hashrate are flagged as slow.
+const SLOW_CORE_THRESHOLD: f64 = 0.9;
+
+/// The challenge and nonce sequence hashed by a benchmark.
+#[derive(Clone, Copy, Debug)]
+struct Workload {
+    challenge: [u8; 32],
+    nonce: u64,
+}
+
+impl Workload {
+    fn from_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self {
+            challenge: rng.gen(),
+            nonce: rng.gen(),
+        }
+    }
+
+    /// Returns the first nonce of a thread, splitting the nonce space evenly between threads.
+    fn first_nonce(&self, threads: u64, i: u64) -> u64 {
+        self.nonce
+            .wrapping_add(u64::MAX.saturating_div(threads).saturating_mul(i))
+    }
+}
+
+/// A saved set of benchmark results to compare future runs against.
+#[derive(Debug, Deserialize, Serialize)]
+struct Baseline {
+    name: String,
+    created_at: i64,
+    version: String,
+    cpu_model: String,
+    cpu_flags: String,
+    duration: u64,
+    #[serde(default)]
+    seed: u64,
+    results: Vec<BaselineResult>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct BaselineResult {
+    threads: u64,
+    hashes_per_sec: u64,
+}
 
 impl Miner {
     pub async fn benchmark(&self, args: BenchmarkArgs) {
+        // Generate workload
+        let seed = args.seed.unwrap_or_else(rand::random);
+        let workload = Workload::from_seed(seed);
+        if !self.is_json() {
+            println!("{}: {}", "Seed".bold(), seed);
+        }
+
+        // Run stress test
+        if args.stress {
+            self.check_num_cores(args.threads);
+            let document = stress_test(args.threads, args.duration, workload, seed);
+            match self.is_json() {
+                true => print_json(&document),
+                false => print_stress_test(&document),
+            }
+            return;
+        }
+
+        // Benchmark each core
+        if args.per_core {
+            self.benchmark_per_core(args.duration, workload, seed);
+            return;
+        }
+
+        // Parse thread counts
+        let thread_counts = match &args.sweep_threads {
+            Some(range) => match parse_range(range) {
+                Some(range) => range,
+                None => {
+                    error!("Invalid thread range: {:?}", range);
+                    return;
+                }
+            },
+            None => vec![args.threads],
+        };
+
         // Check num threads
-        self.check_num_cores(args.threads);
-
-        // Dispatch job to each thread
-        let challenge = [0; 32];
-        let progress_bar = Arc::new(spinner::new_progress_bar());
-        progress_bar.set_message(format!(
-            "Benchmarking. This will take {} sec...",
-            TEST_DURATION
-        ));
-        let handles: Vec<_> = (0..args.threads)
-            .map(|i| {
-                std::thread::spawn({
-                    move || {
-                        let timer = Instant::now();
-                        let first_nonce = u64::MAX.saturating_div(args.threads).saturating_mul(i);
-                        let mut nonce = first_nonce;
-                        loop {
-                            // Create hash
-                            let _hx = drillx::hash(&challenge, &nonce.to_le_bytes());
-
-                            // Increment nonce
-                            nonce += 1;
-
-                            // Exit if time has elapsed
-                            if (timer.elapsed().as_secs() as i64).ge(&TEST_DURATION) {
-                                break;
-                            }
-                        }
+        self.check_num_cores(*thread_counts.iter().max().unwrap_or(&args.threads));
+
+        // Run benchmarks
+        let mut results = vec![];
+        for threads in thread_counts {
+            let hashes = run_benchmark(threads, args.duration, workload, None);
+            results.push((threads, hashes, hashes.saturating_div(args.duration.max(1))));
+        }
+        let Some(&(best_threads, _, hashrate)) = results.iter().max_by_key(|r| r.2) else {
+            return;
+        };
+
+        // Report best configuration
+        if results.len().gt(&1) && !self.is_json() {
+            println!(
+                "{}: {} threads ({} H/sec)",
+                "Best".bold(),
+                best_threads,
+                hashrate
+            );
+        }
+
+        // Estimate the best difficulty per round
+        let hashes = hashrate.saturating_mul(ONE_MINUTE as u64) as f64;
+        if !self.is_json() {
+            print_difficulty_distribution(hashrate);
+        }
+        let expected_reward = self.expected_reward(hashes).await;
+        if let (Some(expected_reward), false) = (expected_reward, self.is_json()) {
+            println!(
+                "  Expected reward: {} ORE per round (before stake multiplier)",
+                amount_u64_to_string(expected_reward as u64)
+            );
+        }
 
-                        // Return hash count
-                        nonce - first_nonce
+        // Estimate profitability
+        let mut profitability = None;
+        if let (Some(watts), Some(kwh_price)) = (args.watts, args.kwh_price) {
+            match expected_reward {
+                Some(expected_reward) => {
+                    profitability = estimate_profitability(
+                        expected_reward,
+                        watts,
+                        kwh_price,
+                        args.ore_price,
+                        args.sol_price,
+                    )
+                    .await;
+                    if let (Some(estimate), false) = (&profitability, self.is_json()) {
+                        print_profitability(estimate);
                     }
-                })
+                }
+                None => error!("Could not fetch the reward rates to estimate profitability"),
+            }
+        }
+
+        // Compare against baseline
+        let baseline = match &args.compare {
+            Some(name) => {
+                let baseline = load_baseline(name);
+                match &baseline {
+                    Some(baseline) if !self.is_json() => print_comparison(baseline, &results),
+                    Some(_) => {}
+                    None => error!("No baseline named {:?}", name),
+                }
+                baseline
+            }
+            None => None,
+        };
+
+        // Save as baseline
+        if let Some(name) = &args.save {
+            let baseline = Baseline {
+                name: name.clone(),
+                created_at: Utc::now().timestamp(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                cpu_model: cpu_info("model name"),
+                cpu_flags: cpu_info("flags"),
+                duration: args.duration,
+                seed,
+                results: results
+                    .iter()
+                    .map(|(threads, _, hashrate)| BaselineResult {
+                        threads: *threads,
+                        hashes_per_sec: *hashrate,
+                    })
+                    .collect(),
+            };
+            let path = baseline_path(name);
+            match serde_json::to_string_pretty(&baseline)
+                .map_err(|err| err.to_string())
+                .and_then(|s| std::fs::write(&path, s).map_err(|err| err.to_string()))
+            {
+                Ok(_) => print_status(format!("Saved baseline {:?}", name)),
+                Err(err) => error!("Failed to save baseline {:?}: {}", name, err),
+            }
+        }
+
+        // Export to csv
+        if let Some(filepath) = &args.csv {
+            let mut s = "threads,duration_sec,hashes,hashes_per_sec".to_string();
+            for (threads, hashes, hashrate) in results.iter() {
+                s = format!(
+                    "{}\n{},{},{},{}",
+                    s, threads, args.duration, hashes, hashrate
+                );
+            }
+            if let Err(err) = std::fs::write(filepath, format!("{}\n", s)) {
+                error!("Failed to write {}: {}", filepath, err);
+            } else {
+                print_status(format!("Exported benchmark results to {}", filepath));
+            }
+        }
+
+        // Print as json
+        if self.is_json() {
+            print_json(&BenchmarkDocument {
+                seed,
+                duration: args.duration,
+                results: results
+                    .iter()
+                    .map(|&(threads, hashes, hashes_per_sec)| BenchmarkResult {
+                        threads,
+                        hashes,
+                        hashes_per_sec,
+                        baseline_hashes_per_sec: baseline.as_ref().and_then(|baseline| {
+                            baseline
+                                .results
+                                .iter()
+                                .find(|r| r.threads.eq(&threads))
+                                .map(|r| r.hashes_per_sec)
+                        }),
+                    })
+                    .collect(),
+                best_threads,
+                expected_difficulty: expected_difficulty(hashes),
+                expected_reward: expected_reward.map(|reward| reward as u64),
+                profitability: profitability.map(|estimate| BenchmarkProfitability {
+                    ore_price: estimate.ore_price,
+                    sol_price: estimate.sol_price,
+                    ore_per_day: amount_f64_to_u64(estimate.ore_per_day),
+                    revenue: estimate.revenue_per_day,
+                    electricity: estimate.power_cost_per_day,
+                    base_fees: estimate.base_fee_per_day,
+                    break_even_fee: estimate
+                        .break_even_fee
+                        .gt(&0.0)
+                        .then_some(estimate.break_even_fee as u64),
+                }),
+                baseline: baseline.map(|baseline| baseline.name),
+            });
+        }
+    }
+
+    fn benchmark_per_core(&self, duration: u64, workload: Workload, seed: u64) {
+        let cores = physical_cores();
+        if cores.is_empty() {
+            error!("Could not detect the cores of this machine");
+            return;
+        }
+
+        // Benchmark one pinned thread per core
+        let mut results = vec![];
+        for core in cores {
+            print_status(format!("Core {}", core.id));
+            let hashes = run_benchmark(1, duration, workload, Some(core));
+            results.push((core.id, hashes.saturating_div(duration.max(1))));
+        }
+
+        // Flag cores significantly slower than the median
+        let mut hashrates: Vec<u64> = results.iter().map(|r| r.1).collect();
+        hashrates.sort();
+        let median = hashrates[hashrates.len() / 2];
+        let is_slow = |hashrate: u64| (hashrate as f64) < median as f64 * SLOW_CORE_THRESHOLD;
+        if !self.is_json() {
+            println!("\n{}: {} H/sec", "Median".bold(), median);
+        }
+        for &(id, hashrate) in results.iter() {
+            if is_slow(hashrate) {
+                warn!(
+                    "Core {} is slow: {} H/sec ({:.1}% of median)",
+                    id,
+                    hashrate,
+                    hashrate as f64 / median.max(1) as f64 * 100.0
+                );
+            }
+        }
+        if self.is_json() {
+            print_json(&CoreBenchmarkDocument {
+                seed,
+                duration,
+                median,
+                cores: results
+                    .iter()
+                    .map(|&(core, hashes_per_sec)| CoreBenchmarkResult {
+                        core,
+                        hashes_per_sec,
+                        slow: is_slow(hashes_per_sec),
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    /// Returns the expected reward of a round of `hashes` hashes, before the stake
+    /// multiplier, if the program config can be fetched.
+    async fn expected_reward(&self, hashes: f64) -> Option<f64> {
+        let data = self
+            .rpc_client
+            .get_account_data(&program::config_address())
+            .await
+            .ok()?;
+        let config = Config::try_from_bytes(&data).ok()?;
+        Some(expected_reward_per_round(hashes, config))
+    }
+}
+
+/// Returns the expected best difficulty of `hashes` hashes.
+fn expected_difficulty(hashes: f64) -> f64 {
+    (1..=MAX_DIFFICULTY)
+        .map(|d| prob_best_at_least(hashes, d))
+        .sum::<f64>()
+}
+
+/// Prints the expected best difficulty of a round, and the odds of reaching the
+/// difficulties around it.
+fn print_difficulty_distribution(hashrate: u64) {
+    let hashes = hashrate.saturating_mul(ONE_MINUTE as u64) as f64;
+    let expected = expected_difficulty(hashes);
+    println!(
+        "\n{}: {:.2} (over a {} sec round at {} H/sec)",
+        "Expected best difficulty".bold(),
+        expected,
+        ONE_MINUTE,
+        hashrate
+    );
+    let center = expected.round() as u32;
+    for d in center.saturating_sub(3).max(1)..=center + 3 {
+        println!(
+            "  P(difficulty >= {}): {:.2}%",
+            d,
+            prob_best_at_least(hashes, d) * 100.0
+        );
+    }
+}
+
+/// Hashes with the given number of threads for the duration and returns the hash count.
+/// If a core is provided, every thread is pinned to it.
+fn run_benchmark(threads: u64, duration: u64, workload: Workload, core: Option<CoreId>) -> u64 {
+    // Dispatch job to each thread
+    let challenge = workload.challenge;
+    let progress_bar = Arc::new(new_progress_bar());
+    progress_bar.set_message(format!(
+        "Benchmarking {} threads. This will take {} sec...",
+        threads, duration
+    ));
+    let handles: Vec<_> = (0..threads)
+        .map(|i| {
+            std::thread::spawn({
+                move || {
+                    if let Some(core) = core {
+                        core_affinity::set_for_current(core);
+                    }
+                    let timer = Instant::now();
+                    let first_nonce = workload.first_nonce(threads, i);
+                    let mut nonce = first_nonce;
+                    loop {
+                        // Create hash
+                        let _hx = drillx::hash(&challenge, &nonce.to_le_bytes());
+
+                        // Increment nonce
+                        nonce = nonce.wrapping_add(1);
+
+                        // Exit if time has elapsed
+                        if timer.elapsed().as_secs().ge(&duration) {
+                            break;
+                        }
+                    }
+
+                    // Return hash count
+                    nonce.wrapping_sub(first_nonce)
+                }
             })
-            .collect();
+        })
+        .collect();
 
-        // Join handles and return best nonce
-        let mut total_nonces = 0;
-        for h in handles {
-            if let Ok(count) = h.join() {
-                total_nonces += count;
+    // Join handles and return best nonce
+    let mut total_nonces = 0;
+    for h in handles {
+        if let Ok(count) = h.join() {
+            total_nonces += count;
+        }
+    }
+
+    // Update log
+    finish_progress(
+        &progress_bar,
+        format!(
+            "Hashpower: {} H/sec ({} threads)",
+            total_nonces.saturating_div(duration.max(1)),
+            threads
+        ),
+    );
+    total_nonces
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    let dir = ore_dir().join("benchmarks");
+    std::fs::create_dir_all(&dir).ok();
+    dir.join(format!("{}.json", name))
+}
+
+fn load_baseline(name: &str) -> Option<Baseline> {
+    let s = std::fs::read_to_string(baseline_path(name)).ok()?;
+    serde_json::from_str(&s).ok()
+}
+
+fn print_comparison(baseline: &Baseline, results: &[(u64, u64, u64)]) {
+    println!(
+        "{} {:?} ({}, ore-cli {})",
+        "Baseline".bold(),
+        baseline.name,
+        baseline.cpu_model,
+        baseline.version
+    );
+    if baseline.cpu_model.ne(&cpu_info("model name")) {
+        warn!("Baseline was recorded on a different CPU");
+    }
+    for (threads, _, hashrate) in results.iter() {
+        match baseline.results.iter().find(|r| r.threads.eq(threads)) {
+            Some(base) => {
+                let delta = if base.hashes_per_sec.gt(&0) {
+                    (*hashrate as f64 - base.hashes_per_sec as f64) / base.hashes_per_sec as f64
+                        * 100.0
+                } else {
+                    0.0
+                };
+                let delta = format!("{:+.2}%", delta);
+                println!(
+                    "  {} threads: {} H/sec vs {} H/sec ({})",
+                    threads,
+                    hashrate,
+                    base.hashes_per_sec,
+                    if hashrate.ge(&base.hashes_per_sec) {
+                        delta.green()
+                    } else {
+                        delta.red()
+                    }
+                );
             }
+            None => println!("  {} threads: not in baseline", threads),
         }
+    }
+}
+
+/// Returns the expected reward of the best of `hashes` hashes, before the stake multiplier.
+pub fn expected_reward_per_round(hashes: f64, config: &Config) -> f64 {
+    (config.min_difficulty as u32..=MAX_DIFFICULTY)
+        .map(|d| {
+            let p = prob_best_at_least(hashes, d) - prob_best_at_least(hashes, d + 1);
+            let reward = config.base_reward_rate.saturating_mul(
+                2u64.saturating_pow(d.saturating_sub(config.min_difficulty as u32)),
+            );
+            p * reward as f64
+        })
+        .sum::<f64>()
+}
 
-        // Update log
-        progress_bar.finish_with_message(format!(
-            "Hashpower: {} H/sec",
-            total_nonces.saturating_div(TEST_DURATION as u64),
+/// Daily earnings and costs of mining at an expected reward per round.
+pub(crate) struct Profitability {
+    pub ore_price: f64,
+    pub sol_price: f64,
+    pub ore_per_day: f64,
+    pub revenue_per_day: f64,
+    pub power_cost_per_day: f64,
+    pub base_fee_per_day: f64,
+    /// The priority fee, in microlamports per compute unit, that consumes all profit.
+    pub break_even_fee: f64,
+}
+
+impl Profitability {
+    pub fn estimate(expected_reward: f64, watts: f64, kwh_price: f64, prices: Prices) -> Self {
+        let rounds_per_day = ROUNDS_PER_DAY as f64;
+        let ore_per_day = amount_u64_to_f64(expected_reward as u64) * rounds_per_day;
+        let revenue_per_day = ore_per_day * prices.ore;
+        let power_cost_per_day = watts / 1000.0 * 24.0 * kwh_price;
+        let base_fee_per_day = lamports_to_sol(BASE_FEE_LAMPORTS * ROUNDS_PER_DAY) * prices.sol;
+        let margin_per_round =
+            (revenue_per_day - power_cost_per_day - base_fee_per_day) / rounds_per_day;
+
+        // Solve for the priority fee that consumes the remaining margin
+        let break_even_fee = margin_per_round / prices.sol * LAMPORTS_PER_SOL as f64 * 1_000_000.0
+            / CU_LIMIT_MINE_TX as f64;
+        Self {
+            ore_price: prices.ore,
+            sol_price: prices.sol,
+            ore_per_day,
+            revenue_per_day,
+            power_cost_per_day,
+            base_fee_per_day,
+            break_even_fee,
+        }
+    }
+
+    /// Returns the daily cost of paying a priority fee on every mine transaction.
+    pub fn priority_fee_per_day(&self, priority_fee: u64) -> f64 {
+        let lamports = priority_fee.saturating_mul(CU_LIMIT_MINE_TX as u64) / 1_000_000;
+        lamports_to_sol(lamports * ROUNDS_PER_DAY) * self.sol_price
+    }
+}
+
+/// Measures the hashrate of the threads over the duration.
+pub(crate) fn measure_hashrate(threads: u64, duration: u64) -> u64 {
+    let hashes = run_benchmark(threads, duration, Workload::from_seed(rand::random()), None);
+    hashes.saturating_div(duration.max(1))
+}
+
+/// Estimates daily earnings, electricity cost, and the break-even priority fee.
+async fn estimate_profitability(
+    expected_reward: f64,
+    watts: f64,
+    kwh_price: f64,
+    ore_price: Option<f64>,
+    sol_price: Option<f64>,
+) -> Option<Profitability> {
+    let Some(prices) = resolve_prices(ore_price, sol_price).await else {
+        error!("Could not fetch ORE and SOL prices. Pass --ore-price and --sol-price.");
+        return None;
+    };
+    Some(Profitability::estimate(
+        expected_reward,
+        watts,
+        kwh_price,
+        prices,
+    ))
+}
+
+fn print_profitability(estimate: &Profitability) {
+    println!("\n{}", "Profitability".bold());
+    println!("  ORE price: ${:.4}", estimate.ore_price);
+    println!("  SOL price: ${:.2}", estimate.sol_price);
+    println!(
+        "  Earnings: {:.6} ORE/day (${:.2}/day)",
+        estimate.ore_per_day, estimate.revenue_per_day
+    );
+    println!("  Electricity: ${:.2}/day", estimate.power_cost_per_day);
+    println!("  Base fees: ${:.2}/day", estimate.base_fee_per_day);
+    if estimate.break_even_fee.gt(&0.0) {
+        println!(
+            "  Break-even priority fee: {:.0} microlamports",
+            estimate.break_even_fee
+        );
+    } else {
+        println!(
+            "  Break-even priority fee: {}",
+            "unprofitable at any fee".bold().red()
+        );
+    }
+}
+
+/// Returns the probability that the best of `hashes` hashes has at least `difficulty` leading zeros.
+fn prob_best_at_least(hashes: f64, difficulty: u32) -> f64 {
+    let p = 0.5f64.powi(difficulty as i32);
+    -(hashes * (-p).ln_1p()).exp_m1()
+}
+
+/// Returns one logical core per physical core, where the topology is known.
+fn physical_cores() -> Vec<CoreId> {
+    let cores = core_affinity::get_core_ids().unwrap_or_default();
+    let mut seen = vec![];
+    cores
+        .into_iter()
+        .filter(|core| {
+            let topology = format!("/sys/devices/system/cpu/cpu{}/topology", core.id);
+            let package = std::fs::read_to_string(format!("{}/physical_package_id", topology));
+            let core_id = std::fs::read_to_string(format!("{}/core_id", topology));
+            match (package, core_id) {
+                (Ok(package), Ok(core_id)) => {
+                    let key = (package.trim().to_string(), core_id.trim().to_string());
+                    if seen.contains(&key) {
+                        false
+                    } else {
+                        seen.push(key);
+                        true
+                    }
+                }
+                _ => true,
+            }
+        })
+        .collect()
+}
+
+/// Reads a field from /proc/cpuinfo, where available.
+fn cpu_info(field: &str) -> String {
+    std::fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|s| {
+            s.lines()
+                .find(|line| line.starts_with(field))
+                .and_then(|line| line.split_once(':'))
+                .map(|(_, value)| value.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Hashes at full load for the duration, sampling temperature and clock speed and
+/// logging throttling events.
+fn stress_test(threads: u64, duration: u64, workload: Workload, seed: u64) -> StressTestDocument {
+    // Dispatch job to each thread
+    let challenge = workload.challenge;
+    let hashes = Arc::new(AtomicU64::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let handles: Vec<_> = (0..threads)
+        .map(|i| {
+            let hashes = hashes.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                let mut nonce = workload.first_nonce(threads, i);
+                while !stop.load(Ordering::Relaxed) {
+                    let _hx = drillx::hash(&challenge, &nonce.to_le_bytes());
+                    hashes.fetch_add(1, Ordering::Relaxed);
+                    nonce = nonce.wrapping_add(1);
+                }
+            })
+        })
+        .collect();
+
+    // Sample until the duration has elapsed
+    print_status(format!(
+        "Stress testing {} threads for {} sec...",
+        threads, duration
+    ));
+    let timer = Instant::now();
+    let mut last_hashes = 0;
+    let mut max_clock = 0.0f64;
+    let mut max_temp: Option<f64> = None;
+    let mut last_throttle_count = throttle_count();
+    let mut throttle_events = 0;
+    while timer.elapsed().as_secs().lt(&duration) {
+        std::thread::sleep(Duration::from_secs(STRESS_SAMPLE_INTERVAL));
+        let total = hashes.load(Ordering::Relaxed);
+        let hashrate = (total - last_hashes) / STRESS_SAMPLE_INTERVAL;
+        last_hashes = total;
+        let temp = cpu_temperature();
+        let clock = cpu_clock_mhz();
+        max_temp = match (max_temp, temp) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        print_status(format!(
+            "[{}] {} H/sec, {}, {}",
+            Utc::now().format("%H:%M:%S"),
+            hashrate,
+            temp.map(|t| format!("{:.1}°C", t))
+                .unwrap_or_else(|| "temp unknown".to_string()),
+            clock
+                .map(|c| format!("{:.0} MHz", c))
+                .unwrap_or_else(|| "clock unknown".to_string()),
         ));
+
+        // Detect throttling from kernel counters or a clock drop
+        let count = throttle_count();
+        if count.gt(&last_throttle_count) {
+            throttle_events += 1;
+            warn!(
+                "Thermal throttling reported by the kernel ({} events)",
+                count - last_throttle_count
+            );
+        }
+        last_throttle_count = count;
+        if let Some(clock) = clock {
+            if clock < max_clock * THROTTLE_CLOCK_THRESHOLD {
+                throttle_events += 1;
+                warn!(
+                    "Clock speed dropped to {:.0} MHz from a peak of {:.0} MHz",
+                    clock, max_clock
+                );
+            }
+            max_clock = max_clock.max(clock);
+        }
+    }
+
+    // Stop threads
+    stop.store(true, Ordering::Relaxed);
+    for h in handles {
+        h.join().ok();
+    }
+    let total = hashes.load(Ordering::Relaxed);
+    StressTestDocument {
+        seed,
+        threads,
+        duration,
+        hashes_per_sec: total.saturating_div(timer.elapsed().as_secs().max(1)),
+        throttle_events,
+        max_temperature: max_temp,
+    }
+}
+
+fn print_stress_test(document: &StressTestDocument) {
+    println!(
+        "\n{}: {} H/sec average, {} throttling events, max temperature {}",
+        "Stress test complete".bold(),
+        document.hashes_per_sec,
+        document.throttle_events,
+        document
+            .max_temperature
+            .map(|t| format!("{:.1}°C", t))
+            .unwrap_or_else(|| "unknown".to_string())
+    );
+}
+
+/// Returns the hottest thermal zone reading in degrees Celsius, where available.
+fn cpu_temperature() -> Option<f64> {
+    let entries = std::fs::read_dir("/sys/class/thermal").ok()?;
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("temp")).ok())
+        .filter_map(|s| s.trim().parse::<f64>().ok())
+        .map(|millidegrees| millidegrees / 1000.0)
+        .reduce(f64::max)
+}
+
+/// Returns the average clock speed across cores in MHz, where available.
+fn cpu_clock_mhz() -> Option<f64> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    let clocks: Vec<f64> = cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("cpu MHz"))
+        .filter_map(|line| line.split_once(':'))
+        .filter_map(|(_, value)| value.trim().parse::<f64>().ok())
+        .collect();
+    if clocks.is_empty() {
+        return None;
+    }
+    Some(clocks.iter().sum::<f64>() / clocks.len() as f64)
+}
+
+/// Returns the total number of thermal throttling events reported by the kernel.
+fn throttle_count() -> u64 {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/cpu") else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            std::fs::read_to_string(entry.path().join("thermal_throttle/core_throttle_count")).ok()
+        })
+        .filter_map(|s| s.trim().parse::<u64>().ok())
+        .sum()
+}
+
+/// Parses an inclusive range like `1..32`.
+fn parse_range(s: &str) -> Option<Vec<u64>> {
+    let (start, end) = s.split_once("..")?;
+    let start = start.trim().parse::<u64>().ok()?;
+    let end = end.trim().trim_start_matches('=').parse::<u64>().ok()?;
+    if start.eq(&0) || start.gt(&end) {
+        return None;
     }
+    Some((start..=end).collect())
 }
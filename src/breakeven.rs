@@ -0,0 +1,133 @@
+use colored::*;
+use ore_api::consts::ONE_MINUTE;
+
+use crate::{
+    args::BreakevenArgs,
+    benchmark::{expected_reward_per_round, measure_hashrate, Profitability, ROUNDS_PER_DAY},
+    error::OreError,
+    output::print_json,
+    price::resolve_prices,
+    schema::BreakevenDocument,
+    utils::{
+        amount_f64_to_u64, amount_u64_to_string, calculate_multiplier, get_config,
+        get_proof_with_authority,
+    },
+    Miner,
+};
+
+impl Miner {
+    pub async fn breakeven(&self, args: BreakevenArgs) -> Result<(), OreError> {
+        // Resolve the hashrate
+        let hashrate = match args.hashrate {
+            Some(hashrate) => hashrate,
+            None => {
+                if !self.is_json() {
+                    println!(
+                        "Measuring the hashrate of {} threads for {} sec",
+                        args.threads, args.duration
+                    );
+                }
+                measure_hashrate(args.threads, args.duration)
+            }
+        };
+
+        // Fetch the reward rates and prices
+        let config = get_config(&self.rpc_client).await?;
+        let multiplier = match self.signer() {
+            Ok(signer) => get_proof_with_authority(&self.rpc_client, signer.pubkey())
+                .await
+                .map(|proof| calculate_multiplier(proof.balance, config.top_balance))
+                .unwrap_or(1.0),
+            Err(_) => 1.0,
+        };
+        let prices = resolve_prices(args.ore_price, args.sol_price)
+            .await
+            .ok_or(OreError::PricesUnavailable)?;
+        let priority_fee = self.priority_fee().await;
+
+        // Estimate daily profit
+        let hashes = hashrate.saturating_mul(ONE_MINUTE as u64) as f64;
+        let expected_reward = expected_reward_per_round(hashes, &config) * multiplier;
+        let estimate = Profitability::estimate(expected_reward, args.watts, args.kwh_price, prices);
+        let priority_fees = estimate.priority_fee_per_day(priority_fee);
+        let profit = estimate.revenue_per_day
+            - estimate.power_cost_per_day
+            - estimate.base_fee_per_day
+            - priority_fees;
+        let break_even_fee = estimate
+            .break_even_fee
+            .gt(&0.0)
+            .then_some(estimate.break_even_fee as u64);
+
+        // Print as json
+        if self.is_json() {
+            print_json(&BreakevenDocument {
+                hashrate,
+                multiplier,
+                ore_price: estimate.ore_price,
+                sol_price: estimate.sol_price,
+                ore_per_day: amount_f64_to_u64(estimate.ore_per_day),
+                revenue: estimate.revenue_per_day,
+                electricity: estimate.power_cost_per_day,
+                base_fees: estimate.base_fee_per_day,
+                priority_fee,
+                priority_fees,
+                profit,
+                break_even_fee,
+            });
+            return Ok(());
+        }
+
+        // Print
+        println!("{}: {} H/sec", "Hashrate".bold(), hashrate);
+        println!("{}: {:.2}x", "Multiplier".bold(), multiplier);
+        println!("{}: ${:.4}", "ORE price".bold(), estimate.ore_price);
+        println!("{}: ${:.2}", "SOL price".bold(), estimate.sol_price);
+        println!(
+            "{}: {} ORE/day over {} rounds",
+            "Earnings".bold(),
+            amount_u64_to_string(amount_f64_to_u64(estimate.ore_per_day)),
+            ROUNDS_PER_DAY
+        );
+        println!("{}: ${:.2}/day", "Revenue".bold(), estimate.revenue_per_day);
+        println!(
+            "{}: ${:.2}/day",
+            "Electricity".bold(),
+            estimate.power_cost_per_day
+        );
+        println!(
+            "{}: ${:.2}/day",
+            "Base fees".bold(),
+            estimate.base_fee_per_day
+        );
+        println!(
+            "{}: ${:.2}/day at {} microlamports",
+            "Priority fees".bold(),
+            priority_fees,
+            priority_fee
+        );
+        let profit_str = format!("${:.2}/day", profit);
+        println!(
+            "{}: {}",
+            "Profit".bold(),
+            if profit.ge(&0.0) {
+                profit_str.bold().green()
+            } else {
+                profit_str.bold().red()
+            }
+        );
+        match break_even_fee {
+            Some(fee) => println!(
+                "{}: {} microlamports",
+                "Break-even priority fee".bold(),
+                fee
+            ),
+            None => println!(
+                "{}: {}",
+                "Break-even priority fee".bold(),
+                "unprofitable at any fee".bold().red()
+            ),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tokio_util::sync::CancellationToken;
+
+use crate::{dynamic_fee::fee_strategy, error::BuildError, events, output::OutputFormat, Miner};
+
+/// Builds a [`Miner`], checking that the fee settings make sense together.
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use ore_miner::MinerBuilder;
+/// # use solana_client::nonblocking::rpc_client::RpcClient;
+/// let rpc_client = Arc::new(RpcClient::new("https://api.mainnet-beta.solana.com".to_string()));
+/// let miner = MinerBuilder::rpc(rpc_client)
+///     .keypair("id.json")
+///     .dynamic_fee_url("https://mainnet.helius-rpc.com/?api-key=...")
+///     .dynamic_fee_strategy("helius")
+///     .build()?;
+/// # Ok::<(), ore_miner::error::BuildError>(())
+/// ```
+pub struct MinerBuilder {
+    rpc_client: Arc<RpcClient>,
+    keypair_filepath: Option<String>,
+    fee_payer_filepath: Option<String>,
+    priority_fee: Option<u64>,
+    dynamic_fee_url: Option<String>,
+    dynamic_fee_strategy: Option<String>,
+    dynamic_fee_max: Option<u64>,
+    output: OutputFormat,
+    cancel: CancellationToken,
+}
+
+impl MinerBuilder {
+    pub fn rpc(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            keypair_filepath: None,
+            fee_payer_filepath: None,
+            priority_fee: None,
+            dynamic_fee_url: None,
+            dynamic_fee_strategy: None,
+            dynamic_fee_max: None,
+            output: OutputFormat::Text,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// The keypair URI that signs transactions. See [`crate::signer::from_uri`].
+    pub fn keypair(mut self, keypair_filepath: impl Into<String>) -> Self {
+        self.keypair_filepath = Some(keypair_filepath.into());
+        self
+    }
+
+    /// The keypair URI that pays transaction fees. Defaults to the signing keypair.
+    pub fn fee_payer(mut self, fee_payer_filepath: impl Into<String>) -> Self {
+        self.fee_payer_filepath = Some(fee_payer_filepath.into());
+        self
+    }
+
+    /// The static priority fee, in microlamports.
+    pub fn priority_fee(mut self, priority_fee: u64) -> Self {
+        self.priority_fee = Some(priority_fee);
+        self
+    }
+
+    /// The URL the dynamic fee strategy queries. Setting it enables dynamic fees.
+    pub fn dynamic_fee_url(mut self, url: impl Into<String>) -> Self {
+        self.dynamic_fee_url = Some(url.into());
+        self
+    }
+
+    /// The name of a built-in or registered fee strategy. Defaults to `alchemy`.
+    pub fn dynamic_fee_strategy(mut self, strategy: impl Into<String>) -> Self {
+        self.dynamic_fee_strategy = Some(strategy.into());
+        self
+    }
+
+    /// The highest dynamic fee to pay, in microlamports. Also caps fee escalation.
+    pub fn dynamic_fee_max(mut self, max: u64) -> Self {
+        self.dynamic_fee_max = Some(max);
+        self
+    }
+
+    pub fn output(mut self, output: OutputFormat) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// A token to cancel the miner with. Defaults to a new one; see
+    /// [`Miner::cancellation_token`].
+    pub fn cancellation_token(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn build(self) -> Result<Miner, BuildError> {
+        // The dynamic fee strategy only applies with a URL
+        if self.dynamic_fee_url.is_none() && self.dynamic_fee_strategy.is_some() {
+            return Err(BuildError::MissingDynamicFeeUrl("dynamic_fee_strategy"));
+        }
+        let dynamic_fee_strategy = match (&self.dynamic_fee_url, self.dynamic_fee_strategy) {
+            (Some(_), None) => Some("alchemy".to_string()),
+            (_, strategy) => strategy,
+        };
+        if let Some(strategy) = &dynamic_fee_strategy {
+            if fee_strategy(strategy).is_none() {
+                return Err(BuildError::UnknownFeeStrategy(strategy.clone()));
+            }
+        }
+        if let Some(url) = &self.dynamic_fee_url {
+            if reqwest::Url::parse(url).is_err() {
+                return Err(BuildError::InvalidDynamicFeeUrl(url.clone()));
+            }
+        }
+        let fee_payer_filepath = self
+            .fee_payer_filepath
+            .or_else(|| self.keypair_filepath.clone());
+        Ok(Miner {
+            rpc_client: self.rpc_client,
+            keypair_filepath: self.keypair_filepath,
+            priority_fee: self.priority_fee,
+            dynamic_fee_url: self.dynamic_fee_url,
+            dynamic_fee_strategy,
+            dynamic_fee_max: self.dynamic_fee_max,
+            fee_payer_filepath,
+            output: self.output,
+            events: events::channel(),
+            cancel: self.cancel,
+        })
+    }
+}
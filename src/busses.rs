@@ -1,23 +1,95 @@
+use std::time::Duration;
+
+use colored::*;
 use ore_api::{
-    consts::{BUS_ADDRESSES, TOKEN_DECIMALS},
+    consts::{EPOCH_DURATION, ONE_MINUTE},
     state::Bus,
 };
-use ore_utils::AccountDeserialize;
+use solana_program::pubkey::Pubkey;
 
-use crate::Miner;
+use crate::{
+    args::BussesArgs,
+    error::OreError,
+    mine::find_bus,
+    output::print_json,
+    program,
+    schema::{BusDocument, BussesDocument},
+    utils::{amount_u64_to_string, get_busses, get_mining_state, MiningState},
+    Miner,
+};
 
 impl Miner {
-    pub async fn busses(&self) {
-        let client = self.rpc_client.clone();
-        for address in BUS_ADDRESSES.iter() {
-            let data = client.get_account_data(address).await.unwrap();
-            match Bus::try_from_bytes(&data) {
-                Ok(bus) => {
-                    let rewards = (bus.rewards as f64) / 10f64.powf(TOKEN_DECIMALS as f64);
-                    println!("Bus {}: {:} ORE", bus.id, rewards);
+    pub async fn busses(&self, args: BussesArgs) -> Result<(), OreError> {
+        if !args.watch {
+            let busses = get_busses(&self.rpc_client).await?;
+            if self.is_json() {
+                print_json(&busses_document(&busses, None));
+                return Ok(());
+            }
+            for bus in busses {
+                println!("Bus {}: {:} ORE", bus.id, amount_u64_to_string(bus.rewards));
+            }
+            return Ok(());
+        }
+
+        // Refresh until interrupted. The selection only changes once per round, like a miner's.
+        let mut selection: Option<(i64, Pubkey)> = None;
+        loop {
+            let MiningState {
+                config,
+                clock,
+                busses,
+            } = get_mining_state(&self.rpc_client).await?;
+            let round = clock.unix_timestamp.div_euclid(ONE_MINUTE);
+            let selected = match selection {
+                Some((selected_round, selected)) if selected_round.eq(&round) => selected,
+                _ => find_bus(
+                    args.bus_strategy,
+                    &busses,
+                    config.base_reward_rate,
+                    round as usize,
+                ),
+            };
+            selection = Some((round, selected));
+            if self.is_json() {
+                print_json(&busses_document(&busses, Some(selected)));
+                tokio::time::sleep(Duration::from_secs(args.interval)).await;
+                continue;
+            }
+            let reset_in = config
+                .last_reset_at
+                .saturating_add(EPOCH_DURATION)
+                .saturating_sub(clock.unix_timestamp)
+                .max(0);
+
+            // Clear the screen and redraw
+            print!("\x1B[2J\x1B[1;1H");
+            for bus in busses {
+                let line = format!("Bus {}: {:} ORE", bus.id, amount_u64_to_string(bus.rewards));
+                if program::bus_addresses()[bus.id as usize].eq(&selected) {
+                    println!("{} {}", line.bold().green(), "<- selected".dimmed());
+                } else {
+                    println!("{}", line);
                 }
-                Err(_) => {}
             }
+            println!("\n{}: {} sec", "Next epoch reset".bold(), reset_in);
+            tokio::time::sleep(Duration::from_secs(args.interval)).await;
         }
     }
 }
+
+fn busses_document(busses: &[Bus], selected: Option<Pubkey>) -> BussesDocument {
+    BussesDocument {
+        busses: busses
+            .iter()
+            .map(|bus| BusDocument {
+                id: bus.id,
+                address: program::bus_addresses()[bus.id as usize].to_string(),
+                rewards: bus.rewards,
+                theoretical_rewards: bus.theoretical_rewards,
+                top_balance: bus.top_balance,
+            })
+            .collect(),
+        selected: selected.map(|selected| selected.to_string()),
+    }
+}
@@ -0,0 +1,68 @@
+//! An on-disk cache for accounts that rarely change, like the config, treasury, and token
+//! mints, so repeated commands and polling loops don't refetch them. Each entry expires at
+//! a set time. Entries are kept per RPC URL, since clusters share account addresses.
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use chrono::Utc;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{hash::hash, pubkey::Pubkey};
+
+use crate::utils::ore_dir;
+
+const CACHE_DIR: &str = "cache";
+
+/// Whether the cache is bypassed, set by `--no-cache`.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Bypasses the cache for the rest of the process.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+fn path(client: &RpcClient, address: &Pubkey) -> PathBuf {
+    let cluster = hash(client.url().as_bytes()).to_string();
+    ore_dir()
+        .join(CACHE_DIR)
+        .join(&cluster[..8])
+        .join(format!("{}.bin", address))
+}
+
+/// Returns the cached data of an account, unless it expired.
+pub fn get(client: &RpcClient, address: &Pubkey) -> Option<Vec<u8>> {
+    if DISABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let bytes = std::fs::read(path(client, address)).ok()?;
+    if bytes.len().lt(&8) {
+        return None;
+    }
+    let (expires_at, data) = bytes.split_at(8);
+    let expires_at = i64::from_le_bytes(expires_at.try_into().ok()?);
+    Utc::now()
+        .timestamp()
+        .lt(&expires_at)
+        .then(|| data.to_vec())
+}
+
+/// Caches the data of an account until the unix timestamp.
+pub fn insert(client: &RpcClient, address: &Pubkey, data: &[u8], expires_at: i64) {
+    if DISABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let path = path(client, address);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let mut bytes = expires_at.to_le_bytes().to_vec();
+    bytes.extend_from_slice(data);
+
+    // Write to a temporary file first, so concurrent readers never see a partial entry
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    if std::fs::write(&tmp, bytes).is_ok() {
+        std::fs::rename(&tmp, &path).ok();
+    }
+}
@@ -0,0 +1,37 @@
+use ore_api::consts::EPOCH_DURATION;
+
+use crate::{
+    args::ChallengeArgs,
+    clock_drift,
+    error::OreError,
+    output::print_json,
+    schema::ChallengeDocument,
+    utils::{get_mining_state_with_proof, proof_pubkey},
+    Miner,
+};
+
+impl Miner {
+    pub async fn challenge(&self, args: ChallengeArgs) -> Result<(), OreError> {
+        // Fetch the proof, and the clock to time the cutoff by
+        let signer = self.signer()?;
+        let (state, proof) = get_mining_state_with_proof(&self.rpc_client, signer.pubkey()).await?;
+        clock_drift::observe(&state.clock);
+
+        // Print the work
+        let cutoff_at = proof
+            .last_hash_at
+            .saturating_add(60)
+            .saturating_sub(args.buffer_time as i64);
+        print_json(&ChallengeDocument {
+            authority: proof.authority.to_string(),
+            proof: proof_pubkey(proof.authority).to_string(),
+            challenge: hex::encode(proof.challenge),
+            min_difficulty: state.config.min_difficulty,
+            last_hash_at: proof.last_hash_at,
+            cutoff_at,
+            cutoff_in: cutoff_at.saturating_sub(clock_drift::cluster_now()).max(0) as u64,
+            reset_at: state.config.last_reset_at.saturating_add(EPOCH_DURATION),
+        });
+        Ok(())
+    }
+}
@@ -1,52 +1,135 @@
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr, sync::Mutex, time::Duration};
 
 use colored::*;
-use ore_api::consts::MINT_ADDRESS;
-use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
+use serde::{Deserialize, Serialize};
+use solana_program::{native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::signature::Signature;
 use spl_token::amount_to_ui_amount;
+use tracing::warn;
 
 use crate::{
     args::ClaimArgs,
     cu_limits::CU_LIMIT_CLAIM,
-    send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64, ask_confirm, get_proof_with_authority},
+    error::OreError,
+    exit,
+    ledger::LedgerKind,
+    notify::{notify, Event},
+    output::{finish_batch, new_batch_progress, print_json, print_status},
+    preview::Preview,
+    program,
+    schema::ClaimDocument,
+    send_and_confirm::{ComputeBudget, OnSigned, SentSignature},
+    swap::{check_slippage, SwapMode},
+    utils::{
+        amount_f64_to_u64, amount_u64_to_string, get_proof_with_authority, keypair_files, ore_dir,
+        proof_pubkey,
+    },
     Miner,
 };
 
+const QUEUE_FILE: &str = "claims.json";
+
+/// How often to check whether the signatures of unfinished claims expired.
+const EXPIRY_POLL_DELAY: Duration = Duration::from_secs(5);
+
+/// The most signatures `getSignatureStatuses` accepts in one request.
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// The claims of a `--keypair-dir` batch that have not landed, saved with every signature and
+/// after every claim so `--resume` can finish the batch without deriving the amounts again.
+#[derive(Debug, Deserialize, Serialize)]
+struct ClaimQueue {
+    dir: String,
+    claims: Vec<QueuedClaim>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct QueuedClaim {
+    keypair: String,
+    authority: String,
+    /// The amount to claim, in grains.
+    amount: u64,
+    /// The signatures of the claim, saved before each is sent.
+    #[serde(default)]
+    sent: Vec<SentSignature>,
+}
+
+impl ClaimQueue {
+    fn path() -> PathBuf {
+        ore_dir().join(QUEUE_FILE)
+    }
+
+    fn load() -> Result<Option<Self>, OreError> {
+        let path = Self::path();
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map(Some).map_err(|err| {
+                OreError::ClaimQueue(format!(
+                    "Invalid claim queue in {}: {}",
+                    path.display(),
+                    err
+                ))
+            }),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Saves the queue, or removes it once every claim has landed.
+    fn save(&self) -> Result<(), OreError> {
+        let path = Self::path();
+        if self.claims.is_empty() {
+            std::fs::remove_file(&path).ok();
+            return Ok(());
+        }
+        let contents = serde_json::to_string_pretty(self).unwrap_or_default();
+        std::fs::write(&path, contents).map_err(|err| {
+            OreError::ClaimQueue(format!("Failed to write {}: {}", path.display(), err))
+        })
+    }
+}
+
 impl Miner {
-    pub async fn claim(&self, args: ClaimArgs) {
-        let signer = self.signer();
+    pub async fn claim(&self, args: ClaimArgs) -> Result<(), OreError> {
+        if args.resume {
+            return self.resume_claims().await;
+        }
+        if let Some(dir) = args.keypair_dir {
+            return self.claim_keypair_dir(&dir).await;
+        }
+        if args.auto_sell_pct.is_some() {
+            check_slippage(args.slippage_bps, args.allow_high_slippage)?;
+        }
+        let signer = self.signer()?;
         let pubkey = signer.pubkey();
-        let proof = get_proof_with_authority(&self.rpc_client, pubkey).await;
+        let proof = get_proof_with_authority(&self.rpc_client, pubkey).await?;
         let mut ixs = vec![];
-        let beneficiary = match args.to {
-            Some(to) => {
-                // Create beneficiary token account, if needed
-                let wallet = Pubkey::from_str(&to).expect("Failed to parse wallet address");
-                let benefiary_tokens = spl_associated_token_account::get_associated_token_address(
-                    &wallet,
-                    &MINT_ADDRESS,
-                );
-                if self
-                    .rpc_client
-                    .get_token_account(&benefiary_tokens)
-                    .await
-                    .is_err()
-                {
-                    ixs.push(
-                        spl_associated_token_account::instruction::create_associated_token_account(
-                            &signer.pubkey(),
-                            &wallet,
-                            &ore_api::consts::MINT_ADDRESS,
-                            &spl_token::id(),
-                        ),
-                    );
-                }
-                benefiary_tokens
-            }
-            None => self.initialize_ata().await,
+
+        // Create beneficiary token account, if needed
+        let wallet = match args.to {
+            Some(to) => Pubkey::from_str(&to).map_err(|_| OreError::InvalidAddress(to))?,
+            None => pubkey,
         };
+        let beneficiary =
+            spl_associated_token_account::get_associated_token_address(&wallet, &program::mint());
+        let mut rent = 0;
+        if self
+            .rpc_client
+            .get_token_account(&beneficiary)
+            .await
+            .is_err()
+        {
+            rent = self
+                .rpc_client
+                .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+                .await?;
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &pubkey,
+                    &wallet,
+                    &program::mint(),
+                    &spl_token::id(),
+                ),
+            );
+        }
 
         // Parse amount to claim
         let amount = if let Some(amount) = args.amount {
@@ -55,55 +138,340 @@ impl Miner {
             proof.balance
         };
 
-        // Confirm user wants to claim
-        if !ask_confirm(
-            format!(
-                "\nYou are about to claim {}.\n\nAre you sure you want to continue? [Y/n]",
+        // Preview claim and confirm
+        let fee = self
+            .estimate_fee(&ComputeBudget::Fixed(CU_LIMIT_CLAIM))
+            .await?;
+        let mut preview = Preview::new("Claim")
+            .row(
+                "Amount",
                 format!(
                     "{} ORE",
                     amount_to_ui_amount(amount, ore_api::consts::TOKEN_DECIMALS)
-                )
-                .bold(),
+                ),
             )
-            .as_str(),
-        ) {
-            return;
+            .row("Proof", proof_pubkey(pubkey))
+            .row("Beneficiary", beneficiary);
+        if rent.gt(&0) {
+            preview = preview.row(
+                "New token account rent",
+                format!("{} SOL", lamports_to_sol(rent)),
+            );
+        }
+        if !preview.fee(fee).confirm() {
+            return Ok(());
         }
 
         // Send and confirm
         ixs.push(ore_api::instruction::claim(pubkey, beneficiary, amount));
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await
-            .ok();
+        let result = self
+            .send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
+            .await;
+        let sig = result.as_ref().ok().copied();
+        if let Some(sig) = sig {
+            self.record_transaction(LedgerKind::Claim, pubkey, Some(sig), amount, None);
+        }
+        if self.is_json() {
+            print_json(&ClaimDocument {
+                authority: None,
+                beneficiary: Some(beneficiary.to_string()),
+                amount,
+                signature: sig.map(|sig| sig.to_string()),
+                landed: sig.is_some(),
+            });
+        }
+        result?;
+
+        // Sell part of the claim, if requested
+        if let Some(pct) = args.auto_sell_pct {
+            let amount = (amount as u128 * pct as u128 / 100) as u64;
+            self.sell_ore(
+                amount,
+                args.auto_sell_to,
+                args.slippage_bps,
+                SwapMode::Confirm,
+                &|_, _| Ok(()),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Claims all rewards of every keypair in the directory after a single confirmation.
+    async fn claim_keypair_dir(&self, dir: &str) -> Result<(), OreError> {
+        let filepaths = keypair_files(dir).map_err(|err| OreError::ReadDir {
+            path: dir.to_string(),
+            reason: err.to_string(),
+        })?;
+
+        // Fetch claimable balances
+        let mut claims = vec![];
+        let progress_bar = new_batch_progress(filepaths.len());
+        progress_bar.set_message("Fetching balances...");
+        for filepath in filepaths {
+            let authority = self.with_keypair(filepath.clone()).signer()?.pubkey();
+            let proof = get_proof_with_authority(&self.rpc_client, authority).await?;
+            if proof.balance.gt(&0) {
+                claims.push(QueuedClaim {
+                    keypair: filepath,
+                    authority: authority.to_string(),
+                    amount: proof.balance,
+                    sent: vec![],
+                });
+            }
+            progress_bar.inc(1);
+        }
+        finish_batch(&progress_bar);
+        if claims.is_empty() {
+            print_status(format!("No rewards to claim in {}", dir));
+            return Ok(());
+        }
+
+        // Preview claims and confirm
+        if !self.confirm_claims(&claims).await? {
+            return Ok(());
+        }
+        if let Some(queue) = ClaimQueue::load()? {
+            warn!(
+                "Discarding {} unfinished claim(s) from {}",
+                queue.claims.len(),
+                queue.dir
+            );
+        }
+        self.claim_queue(ClaimQueue {
+            dir: dir.to_string(),
+            claims,
+        })
+        .await
     }
 
-    async fn initialize_ata(&self) -> Pubkey {
+    /// Retries the claims left over from a batch, with the amounts derived when it started.
+    async fn resume_claims(&self) -> Result<(), OreError> {
+        let Some(mut queue) = ClaimQueue::load()? else {
+            print_status("No claims to resume".to_string());
+            return Ok(());
+        };
+
+        // Skip claims that landed after all, e.g. when the RPC failed while confirming
+        let mut claims = vec![];
+        for mut claim in queue.claims {
+            if let Some(sig) = self.landed_signature(&claim.sent).await? {
+                let authority = Pubkey::from_str(&claim.authority)
+                    .map_err(|_| OreError::InvalidAddress(claim.authority.clone()))?;
+                self.record_transaction(
+                    LedgerKind::Claim,
+                    authority,
+                    Some(sig),
+                    claim.amount,
+                    None,
+                );
+                print_status(format!(
+                    "Skipping {}: its claim of {} ORE landed in {}",
+                    claim.authority,
+                    amount_u64_to_string(claim.amount),
+                    sig
+                ));
+                continue;
+            }
+            claim.sent.clear();
+            claims.push(claim);
+        }
+        queue.claims = claims;
+        if queue.claims.is_empty() {
+            queue.save()?;
+            print_status("No claims left to resume".to_string());
+            return Ok(());
+        }
+
+        // Preview claims and confirm
+        if !self.confirm_claims(&queue.claims).await? {
+            return Ok(());
+        }
+        self.claim_queue(queue).await
+    }
+
+    /// Waits until none of the signatures sent for a claim can land any more, then returns
+    /// the one that landed, if any.
+    async fn landed_signature(
+        &self,
+        sent: &[SentSignature],
+    ) -> Result<Option<Signature>, OreError> {
+        let Some(last_valid_block_height) =
+            sent.iter().map(|sent| sent.last_valid_block_height).max()
+        else {
+            return Ok(None);
+        };
+        while self
+            .rpc_client
+            .get_block_height()
+            .await?
+            .le(&last_valid_block_height)
+        {
+            self.sleep(EXPIRY_POLL_DELAY).await?;
+        }
+        let signatures: Vec<Signature> = sent
+            .iter()
+            .filter_map(|sent| sent.signature.parse().ok())
+            .collect();
+        for chunk in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+            let statuses = self
+                .rpc_client
+                .get_signature_statuses_with_history(chunk)
+                .await?
+                .value;
+            if let Some(sig) = chunk
+                .iter()
+                .zip(statuses)
+                .find_map(|(sig, status)| status.filter(|s| s.err.is_none()).map(|_| *sig))
+            {
+                return Ok(Some(sig));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn confirm_claims(&self, claims: &[QueuedClaim]) -> Result<bool, OreError> {
+        let total = claims.iter().map(|claim| claim.amount).sum::<u64>();
+        let fee = self
+            .estimate_fee(&ComputeBudget::Fixed(CU_LIMIT_CLAIM))
+            .await?;
+        Ok(Preview::new("Claims")
+            .row("Amount", format!("{} ORE", amount_u64_to_string(total)))
+            .row("Proofs", claims.len())
+            .fee(fee.saturating_mul(claims.len() as u64))
+            .confirm())
+    }
+
+    /// Claims each queued claim in sequence, saving the queue with each signature before it
+    /// is sent and after every claim that lands, so an interrupted batch can be resumed.
+    async fn claim_queue(&self, mut queue: ClaimQueue) -> Result<(), OreError> {
+        queue.save()?;
+        let count = queue.claims.len();
+        let mut failures = 0;
+        let progress_bar = new_batch_progress(count);
+        let mut i = 0;
+        while let Some(claim) = queue.claims.get(i) {
+            let miner = self.with_keypair(claim.keypair.clone());
+            let amount = claim.amount;
+            let pubkey = miner.signer()?.pubkey();
+            progress_bar.set_message(format!(
+                "Claiming {} ORE for {}",
+                amount_u64_to_string(amount),
+                pubkey
+            ));
+            let journal = Mutex::new(queue);
+            let on_signed = |signature: Signature, last_valid_block_height: u64| {
+                let mut journal = journal.lock().unwrap();
+                journal.claims[i].sent.push(SentSignature {
+                    signature: signature.to_string(),
+                    last_valid_block_height,
+                });
+                journal.save()
+            };
+            let result = miner.claim_rewards_tracked(amount, &on_signed).await;
+            queue = journal.into_inner().unwrap();
+            let sig = match result {
+                Ok(sig) => {
+                    queue.claims.remove(i);
+                    queue.save()?;
+                    Some(sig)
+                }
+                Err(err) => {
+                    print_status(format!(
+                        "{} Failed to claim for {}: {}",
+                        "ERROR".bold().red(),
+                        pubkey,
+                        err
+                    ));
+                    exit::set(err.exit_code());
+                    failures += 1;
+                    i += 1;
+                    None
+                }
+            };
+            if self.is_json() {
+                print_json(&ClaimDocument {
+                    authority: Some(pubkey.to_string()),
+                    beneficiary: None,
+                    amount,
+                    signature: sig.map(|sig| sig.to_string()),
+                    landed: sig.is_some(),
+                });
+            }
+            progress_bar.inc(1);
+        }
+        finish_batch(&progress_bar);
+        print_status(format!(
+            "Claimed from {} of {} proof(s)",
+            count - failures,
+            count
+        ));
+        if failures.gt(&0) {
+            print_status(format!(
+                "{} claim(s) left. Run `ore claim --resume` to retry them.",
+                failures
+            ));
+        }
+        Ok(())
+    }
+
+    /// Claims rewards to the signer's token account without prompting.
+    pub async fn claim_rewards(&self, amount: u64) -> Result<Signature, OreError> {
+        self.claim_rewards_tracked(amount, &|_, _| Ok(())).await
+    }
+
+    /// Like [`Miner::claim_rewards`], handing each signature of the claim to `on_signed`
+    /// before it is sent.
+    async fn claim_rewards_tracked(
+        &self,
+        amount: u64,
+        on_signed: OnSigned<'_>,
+    ) -> Result<Signature, OreError> {
+        let pubkey = self.signer()?.pubkey();
+        let beneficiary = self.initialize_ata().await?;
+        let ix = ore_api::instruction::claim(pubkey, beneficiary, amount);
+        let sig = self
+            .send_and_confirm_tracked(
+                &[ix],
+                ComputeBudget::Fixed(CU_LIMIT_CLAIM),
+                false,
+                on_signed,
+            )
+            .await?;
+        self.record_transaction(LedgerKind::Claim, pubkey, Some(sig), amount, None);
+        notify(Event::Claim {
+            signature: sig.to_string(),
+            amount,
+        });
+        Ok(sig)
+    }
+
+    pub async fn initialize_ata(&self) -> Result<Pubkey, OreError> {
         // Initialize client.
-        let signer = self.signer();
+        let signer = self.signer()?;
         let client = self.rpc_client.clone();
 
         // Build instructions.
         let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
             &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
+            &program::mint(),
         );
 
         // Check if ata already exists
         if let Ok(Some(_ata)) = client.get_token_account(&token_account_pubkey).await {
-            return token_account_pubkey;
+            return Ok(token_account_pubkey);
         }
         // Sign and send transaction.
         let ix = spl_associated_token_account::instruction::create_associated_token_account(
             &signer.pubkey(),
             &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
+            &program::mint(),
             &spl_token::id(),
         );
         self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
-            .await
-            .ok();
+            .await?;
 
         // Return token account address
-        token_account_pubkey
+        Ok(token_account_pubkey)
     }
 }
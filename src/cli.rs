@@ -0,0 +1,766 @@
+use std::{panic::AssertUnwindSafe, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use clap::{command, CommandFactory, Parser, Subcommand};
+use futures::FutureExt;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{
+    args::*,
+    cache, compat, daemon,
+    error::OreError,
+    exit, keypair, ledger,
+    logging::{self, LogFile, LogFormat, LogRotation},
+    output::{self, OutputFormat},
+    program, reload, settings, swap, timeout,
+    utils::{self, parse_size},
+    Miner, MinerBuilder,
+};
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    #[command(about = "Summarize the status of a miner account")]
+    Account(AccountArgs),
+
+    #[command(about = "Request devnet or testnet SOL for the signer and fee payer")]
+    Airdrop(AirdropArgs),
+
+    #[command(about = "Reconcile the on-chain history of an address with the local database")]
+    Audit(AuditArgs),
+
+    #[command(about = "Fetch an account balance")]
+    Balance(BalanceArgs),
+
+    #[command(about = "Benchmark your hashpower")]
+    Benchmark(BenchmarkArgs),
+
+    #[command(about = "Estimate daily earnings and the priority fee at which mining stops paying")]
+    Breakeven(BreakevenArgs),
+
+    #[command(about = "Fetch the bus account balances")]
+    Busses(BussesArgs),
+
+    #[command(
+        about = "Print the signer's current challenge as JSON, for external hashing software"
+    )]
+    Challenge(ChallengeArgs),
+
+    #[command(about = "Claim your mining rewards")]
+    Claim(ClaimArgs),
+
+    #[command(about = "Close your account to recover rent")]
+    Close(CloseArgs),
+
+    #[command(about = "Fetch the program config, or validate the ore-cli config file")]
+    Config(ConfigArgs),
+
+    #[command(about = "Sell ORE on a schedule through Jupiter")]
+    Dca(DcaArgs),
+
+    #[command(about = "Export claims and mining rewards for tax reporting")]
+    Export(ExportArgs),
+
+    #[command(about = "Summarize the treasury, total stake, and epoch timing")]
+    Global(GlobalArgs),
+
+    #[command(about = "List recent submissions and claims from the local database")]
+    History(HistoryArgs),
+
+    #[command(about = "Encrypt keypair files and store keypairs in the OS keyring")]
+    Key(KeyArgs),
+
+    #[command(about = "Generate a new keypair, optionally with a vanity address")]
+    Keygen(KeygenArgs),
+
+    #[command(about = "Start mining")]
+    Mine(Box<MineArgs>),
+
+    #[command(about = "Open a proof account to start mining")]
+    Open(OpenArgs),
+
+    #[command(about = "List named profiles and choose the default")]
+    Profile(ProfileArgs),
+
+    #[command(about = "Fetch a proof account")]
+    Proof(ProofArgs),
+
+    #[command(about = "Compare ORE earned with SOL spent on fees, per day and per wallet")]
+    Report(ReportArgs),
+
+    #[command(about = "Fetch the current reward rate for each difficulty level")]
+    Rewards(RewardsArgs),
+
+    #[command(about = "Update ore to the latest release")]
+    SelfUpdate(SelfUpdateArgs),
+
+    #[command(about = "Interactively create the ore-cli config file")]
+    Setup(SetupArgs),
+
+    #[command(about = "Run subcommands interactively, reusing the RPC client and keypair")]
+    Shell(ShellArgs),
+
+    #[command(about = "Stake to earn a rewards multiplier")]
+    Stake(StakeArgs),
+
+    #[command(about = "Submit a solution found by external hashing software")]
+    Submit(SubmitArgs),
+
+    #[command(about = "Sell ORE for another token through Jupiter")]
+    Swap(SwapArgs),
+
+    #[cfg(windows)]
+    #[command(about = "Install, start, or stop a Windows service that runs the miner")]
+    Service(ServiceArgs),
+
+    #[cfg(target_os = "linux")]
+    #[command(about = "Install a systemd service that runs the miner with these arguments")]
+    SystemdInstall(SystemdInstallArgs),
+
+    #[command(about = "List the top miners by lifetime rewards or balance")]
+    Top(TopArgs),
+
+    #[command(about = "Send ORE to another wallet")]
+    Transfer(TransferArgs),
+
+    #[command(about = "Summarize lifetime and daily mining activity")]
+    Stats(StatsArgs),
+
+    #[command(about = "Check whether a background miner is running")]
+    Status(StatusArgs),
+
+    #[command(about = "Stop a background miner started with `mine --daemon`")]
+    Stop(StopArgs),
+
+    #[command(about = "Upgrade your ORE tokens from v1 to v2")]
+    Upgrade(UpgradeArgs),
+
+    #[command(about = "Check a solution offline and report its difficulty")]
+    Verify(VerifyArgs),
+
+    #[cfg(feature = "admin")]
+    #[command(about = "Initialize the program")]
+    Initialize(InitializeArgs),
+
+    #[cfg(feature = "dev")]
+    #[command(about = "Run a local validator with the ORE program for testing")]
+    Localnet(LocalnetArgs),
+}
+
+impl Commands {
+    /// Returns true if the command sends instructions to the ORE program.
+    fn sends_program_instructions(&self) -> bool {
+        matches!(
+            self,
+            Commands::Claim(_)
+                | Commands::Close(_)
+                | Commands::Mine(_)
+                | Commands::Open(_)
+                | Commands::Stake(_)
+                | Commands::Submit(_)
+                | Commands::Upgrade(_)
+        )
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(about, version)]
+pub struct Args {
+    #[arg(
+        long,
+        value_name = "NETWORK_URL",
+        help = "Network address of your RPC provider",
+        global = true
+    )]
+    rpc: Option<String>,
+
+    #[clap(
+        global = true,
+        short = 'C',
+        long = "config",
+        id = "PATH",
+        help = "Filepath to config file."
+    )]
+    config_file: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "KEYPAIR_FILEPATH",
+        help = "Filepath to keypair to use. Use env:VAR to read it from an environment variable, - to read it from stdin, keyring:NAME to read it from the OS keyring, prompt:// to enter a seed phrase, usb://ledger to sign with a Ledger, or an http(s):// URL of a remote signer.",
+        global = true
+    )]
+    keypair: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FEE_PAYER_FILEPATH",
+        help = "Filepath to keypair to use for fee payer. Accepts the same sources as --keypair.",
+        global = true
+    )]
+    fee_payer_filepath: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DERIVATION_PATH",
+        help = "Derivation path of keypairs entered as a seed phrase with prompt://, e.g. m/44'/501'/0'/0'",
+        global = true
+    )]
+    derivation_path: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Number of microlamports to pay as priority fee per transaction",
+        default_value = "0",
+        global = true
+    )]
+    priority_fee: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "DYNAMIC_FEE_URL",
+        help = "RPC URL to use for dynamic fee estimation. If set will enable dynamic fee pricing instead of static priority fee pricing.",
+        global = true
+    )]
+    dynamic_fee_url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DYNAMIC_FEE_STRATEGY",
+        help = "Strategy to use for dynamic fee estimation. Must be one of 'helius', 'triton', 'alchemy', 'local-percentile', or 'static'.",
+        default_value = "alchemy",
+        global = true
+    )]
+    dynamic_fee_strategy: Option<String>,
+    #[arg(
+        long,
+        value_name = "DYNAMIC_FEE_MAX",
+        help = "Maximum priority fee to use for dynamic fee estimation and fee escalation.",
+        default_value = "500000",
+        global = true
+    )]
+    dynamic_fee_max: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Give up on RPC calls and HTTP requests after this many seconds, and on a transaction if it has not landed by then. Defaults to 30s for RPC calls, 10s for HTTP requests, and 90s for transactions, which a shorter timeout never lowers.",
+        value_parser = clap::value_parser!(u64).range(1..),
+        global = true
+    )]
+    timeout: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format for command results. JSON documents are versioned; see the README.",
+        default_value = "text",
+        global = true
+    )]
+    output: OutputFormat,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Export OpenTelemetry spans of the transaction pipeline to this OTLP/HTTP endpoint, e.g. http://localhost:4318/v1/traces",
+        global = true
+    )]
+    otlp_endpoint: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Format for log messages written to stderr",
+        default_value = "pretty",
+        global = true
+    )]
+    log_format: LogFormat,
+
+    #[arg(
+        short = 'v',
+        long,
+        action = clap::ArgAction::Count,
+        help = "Increase log verbosity (-v for debug, -vv for trace)",
+        global = true
+    )]
+    verbose: u8,
+
+    #[arg(
+        short = 'q',
+        long,
+        help = "Only log errors, hide progress spinners, and print each result as a single line",
+        conflicts_with = "verbose",
+        global = true
+    )]
+    quiet: bool,
+
+    #[arg(
+        short = 'y',
+        long,
+        help = "Answer yes to every confirmation prompt and never wait for input, for use in scripts",
+        global = true
+    )]
+    yes: bool,
+
+    #[arg(
+        long,
+        help = "Send instructions even if the deployed program looks incompatible with this version of ore",
+        global = true
+    )]
+    skip_version_check: bool,
+
+    #[arg(
+        long,
+        help = "Fetch every account from the RPC instead of reusing cached ones",
+        global = true
+    )]
+    no_cache: bool,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address of the ORE program to use, e.g. a fork or devnet deployment. Its accounts are derived from it.",
+        value_parser = Pubkey::from_str,
+        global = true
+    )]
+    program_id: Option<Pubkey>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address of the ORE mint. Defaults to the mint of the program.",
+        value_parser = Pubkey::from_str,
+        global = true
+    )]
+    mint: Option<Pubkey>,
+
+    #[arg(
+        long,
+        help = "Disable colors. Also honors the NO_COLOR environment variable.",
+        global = true
+    )]
+    no_color: bool,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "The Jupiter swap API to sell ORE through",
+        default_value = swap::DEFAULT_JUPITER_URL,
+        global = true
+    )]
+    jupiter_url: String,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write logs to this file, without colors",
+        global = true
+    )]
+    log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        help = "Rotate the log file once it exceeds this size, e.g. 10M. Use 0 to disable size-based rotation.",
+        default_value = "10M",
+        value_parser = parse_size,
+        global = true
+    )]
+    log_max_size: u64,
+
+    #[arg(
+        long,
+        value_name = "PERIOD",
+        help = "Rotate the log file at the start of each period",
+        default_value = "daily",
+        global = true
+    )]
+    log_rotation: LogRotation,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of rotated log files to keep",
+        default_value = "7",
+        global = true
+    )]
+    log_retention: usize,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Load ORE_* environment variables from this file instead of .env in the working directory",
+        global = true
+    )]
+    env_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Use the settings of a named profile in ~/.config/ore/config.toml",
+        global = true
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Filepath to the local database of mining activity. Defaults to ~/.config/ore/ore.db.",
+        global = true
+    )]
+    database: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Parses the command line again with the current config file, and returns the settings
+/// of `mine` that can change while it runs.
+pub(crate) fn reload_settings() -> Result<reload::Settings, String> {
+    let args = settings::try_with_defaults(&Args::command(), std::env::args_os().collect())?;
+    let args = Args::try_parse_from(args).map_err(|err| err.to_string().trim().to_string())?;
+    let Commands::Mine(mine_args) = args.command else {
+        return Err("Only `ore mine` started from the command line can be reloaded".to_string());
+    };
+    Ok(reload::Settings {
+        priority_fee: args.priority_fee,
+        dynamic_fee_max: args.dynamic_fee_max,
+        dynamic_fee_strategy: args.dynamic_fee_strategy,
+        threads: mine_args.threads,
+        notify: mine_args.notify,
+    })
+}
+
+/// Parses the command line, runs the command, and exits with its exit code.
+pub async fn main() {
+    let args = Args::try_parse_from(settings::with_defaults(
+        &Args::command(),
+        std::env::args_os().collect(),
+    ))
+    .unwrap_or_else(|err| {
+        // Help and version are printed to stdout and exit successfully
+        if !err.use_stderr() {
+            err.exit();
+        }
+        err.print().ok();
+        std::process::exit(exit::USAGE);
+    });
+
+    // Configure output
+    if args.quiet {
+        output::set_quiet();
+    }
+    if args.output.eq(&OutputFormat::Json) {
+        output::set_json();
+    }
+    if args.yes {
+        utils::set_assume_yes();
+    }
+    if args.skip_version_check {
+        compat::set_skip_check();
+    }
+    if args.no_cache {
+        cache::disable();
+    }
+    if let Some(secs) = args.timeout {
+        timeout::set_timeout(Duration::from_secs(secs));
+    }
+    let color = !args.no_color && !args.quiet && std::env::var_os("NO_COLOR").is_none();
+    if !color {
+        colored::control::set_override(false);
+    }
+
+    // Detach from the terminal
+    if let Commands::Mine(mine_args) = &args.command {
+        if mine_args.daemon && !daemon::is_daemon() {
+            match daemon::spawn(args.log_file.as_deref()) {
+                Ok(pid) => {
+                    println!("Started ore in the background (pid {})", pid);
+                    std::process::exit(exit::OK);
+                }
+                Err(err) => {
+                    eprintln!("error: Could not start the daemon: {}", err);
+                    std::process::exit(exit::ERROR);
+                }
+            }
+        }
+    }
+
+    // Initialize logging
+    let log_file = args.log_file.clone().and_then(|path| {
+        LogFile::open(
+            path.clone(),
+            args.log_max_size,
+            args.log_rotation,
+            args.log_retention,
+        )
+        .map_err(|err| eprintln!("error: Could not open log file {:?}: {}", path, err))
+        .ok()
+    });
+    let console = !matches!(&args.command, Commands::Mine(args) if args.tui);
+    let tracing_guard = logging::init(
+        console,
+        color,
+        args.log_format,
+        args.verbose,
+        args.quiet,
+        log_file,
+        args.otlp_endpoint.as_deref(),
+    );
+
+    if let Some(path) = args.database.clone() {
+        ledger::set_path(path);
+    }
+    if let Some(program_id) = args.program_id {
+        program::set_program_id(program_id);
+    }
+    if let Some(mint) = args.mint {
+        program::set_mint(mint);
+    }
+    swap::set_jupiter_url(args.jupiter_url.clone());
+    if let Some(path) = &args.derivation_path {
+        if let Err(err) = keypair::set_derivation_path(path) {
+            eprintln!("error: Invalid derivation path {:?}: {}", path, err);
+            std::process::exit(1);
+        }
+    }
+
+    // Load the config file from custom path, the default path, or use default config values
+    let cli_config = if let Some(config_file) = &args.config_file {
+        solana_cli_config::Config::load(config_file).unwrap_or_else(|_| {
+            eprintln!("error: Could not find config file `{}`", config_file);
+            std::process::exit(1);
+        })
+    } else if let Some(config_file) = &*solana_cli_config::CONFIG_FILE {
+        solana_cli_config::Config::load(config_file).unwrap_or_default()
+    } else {
+        solana_cli_config::Config::default()
+    };
+
+    // Initialize miner.
+    let cluster = args.rpc.unwrap_or(cli_config.json_rpc_url);
+    let default_keypair = args.keypair.unwrap_or(cli_config.keypair_path.clone());
+    let fee_payer_filepath = args
+        .fee_payer_filepath
+        .unwrap_or(cli_config.keypair_path.clone());
+    let rpc_client = RpcClient::new_with_timeout_and_commitment(
+        cluster,
+        timeout::rpc_timeout(),
+        CommitmentConfig::confirmed(),
+    );
+
+    let mut builder = MinerBuilder::rpc(Arc::new(rpc_client))
+        .keypair(default_keypair)
+        .fee_payer(fee_payer_filepath)
+        .output(args.output);
+    if let Some(fee) = args.priority_fee {
+        builder = builder.priority_fee(fee);
+    }
+    if let Some(url) = args.dynamic_fee_url {
+        builder = builder.dynamic_fee_url(url);
+        if let Some(strategy) = args.dynamic_fee_strategy {
+            builder = builder.dynamic_fee_strategy(strategy);
+        }
+    }
+    if let Some(max) = args.dynamic_fee_max {
+        builder = builder.dynamic_fee_max(max);
+    }
+    let miner = match builder.build() {
+        Ok(miner) => Arc::new(miner),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // Execute user command.
+    match AssertUnwindSafe(miner.run(args.command))
+        .catch_unwind()
+        .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => {
+            tracing::error!("{}", err);
+            exit::set(err.exit_code());
+        }
+        Err(_) => exit::set(exit::ERROR),
+    }
+    ledger::settle_pending().await;
+    drop(tracing_guard);
+    std::process::exit(exit::code());
+}
+
+impl Miner {
+    /// Runs a subcommand.
+    pub async fn run(&self, command: Commands) -> Result<(), OreError> {
+        if command.sends_program_instructions() {
+            self.check_program_version().await?;
+        }
+        match command {
+            Commands::Account(args) => {
+                self.account(args).await?;
+            }
+            Commands::Airdrop(args) => {
+                self.airdrop(args).await?;
+            }
+            Commands::Audit(args) => {
+                self.audit(args).await?;
+            }
+            Commands::Balance(args) => {
+                self.balance(args).await?;
+            }
+            Commands::Benchmark(args) => {
+                self.benchmark(args).await;
+            }
+            Commands::Breakeven(args) => {
+                self.breakeven(args).await?;
+            }
+            Commands::Busses(args) => {
+                self.busses(args).await?;
+            }
+            Commands::Challenge(args) => {
+                self.challenge(args).await?;
+            }
+            Commands::Claim(args) => {
+                self.claim(args).await?;
+            }
+            Commands::Close(args) => {
+                self.close(args).await?;
+            }
+            Commands::Config(args) => {
+                self.config(args).await?;
+            }
+            Commands::Dca(args) => {
+                self.dca(args).await?;
+            }
+            Commands::Export(args) => {
+                self.export(args).await?;
+            }
+            Commands::Global(_) => {
+                self.global().await?;
+            }
+            Commands::History(args) => {
+                self.history(args).await;
+            }
+            Commands::Key(args) => {
+                self.key(args).await;
+            }
+            Commands::Keygen(args) => {
+                self.keygen(args).await?;
+            }
+            Commands::Mine(args) => {
+                self.mine(*args).await?;
+            }
+            Commands::Open(args) => {
+                self.open_proofs(args).await?;
+            }
+            Commands::Proof(args) => {
+                self.proof(args).await?;
+            }
+            Commands::Report(args) => {
+                self.report(args).await;
+            }
+            Commands::Rewards(args) => {
+                self.rewards(args).await?;
+            }
+            Commands::Stake(args) => {
+                self.stake(args).await?;
+            }
+            Commands::Profile(args) => {
+                self.profile(args).await;
+            }
+            Commands::SelfUpdate(args) => {
+                self.self_update(args).await;
+            }
+            Commands::Shell(args) => {
+                self.shell(args).await;
+            }
+            #[cfg(windows)]
+            Commands::Service(args) => {
+                self.service(args).await?;
+            }
+            Commands::Setup(args) => {
+                self.setup(args).await;
+            }
+            Commands::Stats(args) => {
+                self.stats(args).await;
+            }
+            Commands::Status(args) => {
+                self.status(args).await;
+            }
+            Commands::Stop(args) => {
+                self.stop(args).await?;
+            }
+            Commands::Submit(args) => {
+                self.submit(args).await?;
+            }
+            Commands::Swap(args) => {
+                self.swap(args).await?;
+            }
+            #[cfg(target_os = "linux")]
+            Commands::SystemdInstall(args) => {
+                self.systemd_install(args).await?;
+            }
+            Commands::Top(args) => {
+                self.top(args).await?;
+            }
+            Commands::Transfer(args) => {
+                self.transfer(args).await?;
+            }
+            Commands::Upgrade(args) => {
+                self.upgrade(args).await?;
+            }
+            Commands::Verify(args) => {
+                self.verify(args)?;
+            }
+            #[cfg(feature = "admin")]
+            Commands::Initialize(_) => {
+                self.initialize().await?;
+            }
+            #[cfg(feature = "dev")]
+            Commands::Localnet(args) => {
+                self.localnet(args).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.output.eq(&OutputFormat::Json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::{Args, Commands};
+
+    #[test]
+    fn args_are_consistent() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn export_parses_with_global_output() {
+        let args = Args::try_parse_from([
+            "ore",
+            "--output",
+            "json",
+            "export",
+            "--address",
+            "11111111111111111111111111111111",
+            "--out-file",
+            "/tmp/report.csv",
+        ])
+        .unwrap();
+        match args.command {
+            Commands::Export(args) => assert_eq!(args.out_file.as_deref(), Some("/tmp/report.csv")),
+            command => panic!("Parsed {:?} instead of export", command),
+        }
+    }
+    #[test]
+    fn timeout_rejects_zero() {
+        assert!(Args::try_parse_from(["ore", "--timeout", "0", "balance"]).is_err());
+        assert!(Args::try_parse_from(["ore", "--timeout", "1", "balance"]).is_ok());
+    }
+}
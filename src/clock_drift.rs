@@ -0,0 +1,43 @@
+//! Measures how far the local clock is from the cluster's. Cutoffs are timed in cluster
+//! time, so a drifted clock on a VPS makes every submission late or early by the drift.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+
+use chrono::Utc;
+use solana_sdk::clock::Clock;
+use tracing::{info, warn};
+
+use crate::metrics::metrics;
+
+/// The drift, in seconds, past which the miner warns.
+const MAX_DRIFT: i64 = 3;
+
+/// Seconds to add to the local time to get the cluster time.
+static OFFSET: AtomicI64 = AtomicI64::new(0);
+
+/// Whether the last drift measured exceeded the limit, so the warning is not repeated.
+static DRIFTED: AtomicBool = AtomicBool::new(false);
+
+/// Measures the offset of the local clock from the clock sysvar, warning when it first
+/// exceeds a few seconds, and returns it.
+pub fn observe(clock: &Clock) -> i64 {
+    let offset = clock.unix_timestamp - Utc::now().timestamp();
+    OFFSET.store(offset, Ordering::Relaxed);
+    metrics().clock_drift.store(offset, Ordering::Relaxed);
+    let drifted = offset.abs().gt(&MAX_DRIFT);
+    match (drifted, DRIFTED.swap(drifted, Ordering::Relaxed)) {
+        (true, false) => warn!(
+            "The local clock is {} sec {} the cluster clock. Cutoffs are corrected for it, but sync the clock (e.g. enable NTP) to keep the other timestamps right.",
+            offset.abs(),
+            if offset.gt(&0) { "behind" } else { "ahead of" }
+        ),
+        (false, true) => info!("The local clock is back within {} sec of the cluster clock", MAX_DRIFT),
+        _ => {}
+    }
+    offset
+}
+
+/// Returns the current cluster time, from the local time and the last offset measured.
+pub fn cluster_now() -> i64 {
+    Utc::now().timestamp() + OFFSET.load(Ordering::Relaxed)
+}
@@ -1,44 +1,79 @@
-use colored::*;
-use solana_sdk::signature::Signer;
+use ore_api::state::Proof;
+use ore_utils::AccountDeserialize;
+use solana_program::native_token::lamports_to_sol;
 use spl_token::amount_to_ui_amount;
 
 use crate::{
-    args::ClaimArgs,
-    send_and_confirm::ComputeBudget,
-    utils::{ask_confirm, get_proof_with_authority},
-    Miner,
+    args::CloseArgs, error::OreError, ledger::LedgerKind, preview::Preview,
+    send_and_confirm::ComputeBudget, utils::proof_pubkey, Miner,
 };
 
 impl Miner {
-    pub async fn close(&self) {
+    pub async fn close(&self, args: CloseArgs) -> Result<(), OreError> {
+        // Each authority has exactly one proof, and the program only closes the signer's own
+        let signer = self.signer()?;
+        let proof_address = proof_pubkey(signer.pubkey());
+
         // Confirm proof exists
-        let signer = self.signer();
-        let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
+        let account = self
+            .rpc_client
+            .get_account(&proof_address)
+            .await
+            .map_err(|err| OreError::fetch("proof", err))?;
+        let proof =
+            Proof::try_from_bytes(&account.data).map_err(|_| OreError::InvalidAccount("proof"))?;
 
-        // Confirm the user wants to close.
-        if !ask_confirm(
-            format!("{} You have {} ORE staked in this account.\nAre you sure you want to {}close this account? [Y/n]", 
-                "WARNING".yellow(),
-                amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS),
-                if proof.balance.gt(&0) { "claim your stake and "} else { "" }
-            ).as_str()
-        ) {
-            return;
+        // Refuse to close a proof with unclaimed rewards
+        if proof.balance.gt(&0) && !args.force {
+            return Err(OreError::Close(
+                "This proof has unclaimed rewards. Claim them first or rerun with --force to claim and close.".to_string(),
+            ));
         }
 
-        // Claim stake
+        // Preview the accounts to close and confirm
+        let fee = self.estimate_fee(&ComputeBudget::Dynamic).await?;
+        let mut preview = Preview::new("Accounts to close")
+            .row("Proof", proof_address)
+            .row(
+                "Reclaimable rent",
+                format!("{} SOL", lamports_to_sol(account.lamports)),
+            );
         if proof.balance.gt(&0) {
-            self.claim(ClaimArgs {
-                amount: None,
-                to: None,
-            })
-            .await;
+            preview = preview.row(
+                "Rewards to claim",
+                format!(
+                    "{} ORE",
+                    amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS)
+                ),
+            );
+        }
+        if !preview.fee(fee).confirm() {
+            return Ok(());
         }
 
-        // Submit close transaction
-        let ix = ore_api::instruction::close(signer.pubkey());
-        self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
-            .await
-            .ok();
+        // Claim rewards and close in one transaction
+        let mut ixs = vec![];
+        if proof.balance.gt(&0) {
+            let beneficiary = self.initialize_ata().await?;
+            ixs.push(ore_api::instruction::claim(
+                signer.pubkey(),
+                beneficiary,
+                proof.balance,
+            ));
+        }
+        ixs.push(ore_api::instruction::close(signer.pubkey()));
+        let sig = self
+            .send_and_confirm(&ixs, ComputeBudget::Dynamic, false)
+            .await?;
+        if proof.balance.gt(&0) {
+            self.record_transaction(
+                LedgerKind::Claim,
+                signer.pubkey(),
+                Some(sig),
+                proof.balance,
+                None,
+            );
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ore_api::state::{Bus, Config};
+use ore_utils::Discriminator;
+use solana_program::pubkey::Pubkey;
+use tracing::warn;
+
+use crate::{error::OreError, program, Miner};
+
+static SKIP_CHECK: AtomicBool = AtomicBool::new(false);
+
+/// Whether the program passed the check, so the shell only checks once.
+static CHECKED: AtomicBool = AtomicBool::new(false);
+
+/// Skips the program compatibility check for the rest of the process.
+pub fn set_skip_check() {
+    SKIP_CHECK.store(true, Ordering::Relaxed);
+}
+
+impl Miner {
+    /// Checks that the deployed program still uses the account layouts this CLI was built
+    /// against, before sending it any instructions.
+    pub async fn check_program_version(&self) -> Result<(), OreError> {
+        if SKIP_CHECK.load(Ordering::Relaxed) || CHECKED.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        // Confirm the program is deployed
+        let program = self
+            .rpc_client
+            .get_account(&program::program_id())
+            .await
+            .map_err(|err| OreError::fetch("program", err))?;
+        if !program.executable {
+            return Err(OreError::IncompatibleProgram(format!(
+                "{} is not an executable program",
+                program::program_id()
+            )));
+        }
+
+        // Confirm the singleton accounts have the expected layouts
+        self.check_layout::<Config>("config", program::config_address())
+            .await?;
+        self.check_layout::<Bus>("bus", program::bus_addresses()[0])
+            .await?;
+        CHECKED.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn check_layout<T: Discriminator>(
+        &self,
+        name: &'static str,
+        address: Pubkey,
+    ) -> Result<(), OreError> {
+        let account = self
+            .rpc_client
+            .get_account(&address)
+            .await
+            .map_err(|err| OreError::fetch(name, err))?;
+        let expected = 8 + std::mem::size_of::<T>();
+        if account.owner.ne(&program::program_id()) {
+            return Err(OreError::IncompatibleProgram(format!(
+                "the {} account is owned by {}",
+                name, account.owner
+            )));
+        }
+        if account.data.first().ne(&Some(&T::discriminator())) {
+            return Err(OreError::IncompatibleProgram(format!(
+                "the {} account has an unknown discriminator",
+                name
+            )));
+        }
+        if account.data.len().lt(&expected) {
+            return Err(OreError::IncompatibleProgram(format!(
+                "the {} account is {} bytes, expected {}",
+                name,
+                account.data.len(),
+                expected
+            )));
+        }
+        if account.data.len().gt(&expected) {
+            warn!(
+                "The {} account is {} bytes, expected {}. The program may have been upgraded; update ore if commands fail.",
+                name,
+                account.data.len(),
+                expected
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,178 @@
+use clap::CommandFactory;
 use colored::Colorize;
+use ore_api::consts::EPOCH_DURATION;
+use solana_program::program_pack::Pack;
 
 use crate::{
-    utils::{amount_u64_to_string, get_config},
+    args::{ConfigArgs, ConfigCommand},
+    cli::Args,
+    error::OreError,
+    exit,
+    output::print_json,
+    program,
+    schema::{
+        ConfigDocument, ConfigValidationDocument, MintDocument, SettingDocument, TreasuryDocument,
+    },
+    settings,
+    utils::{amount_u64_to_string, format_timestamp, get_accounts_data, get_config},
     Miner,
 };
 
 impl Miner {
-    pub async fn config(&self) {
-        let config = get_config(&self.rpc_client).await;
-        println!("{}: {}", "Last reset at".bold(), config.last_reset_at);
+    pub async fn config(&self, args: ConfigArgs) -> Result<(), OreError> {
+        if let Some(ConfigCommand::Validate(_)) = args.command {
+            self.validate_config();
+            return Ok(());
+        }
+        // Fetch the config, and the treasury and mint it pays rewards from
+        let config = get_config(&self.rpc_client).await?;
+        let mut data = get_accounts_data(
+            &self.rpc_client,
+            "treasury",
+            &[program::treasury_tokens_address(), program::mint()],
+        )
+        .await?
+        .into_iter();
+        let treasury_tokens = data
+            .next()
+            .flatten()
+            .and_then(|data| spl_token::state::Account::unpack(&data).ok())
+            .ok_or(OreError::InvalidAccount("treasury token"))?;
+        let mint = data
+            .next()
+            .flatten()
+            .and_then(|data| spl_token::state::Mint::unpack(&data).ok())
+            .ok_or(OreError::InvalidAccount("mint"))?;
+        let next_reset_at = config.last_reset_at.saturating_add(EPOCH_DURATION);
+
+        // Print as json
+        if args.json || self.is_json() {
+            print_json(&ConfigDocument {
+                last_reset_at: config.last_reset_at,
+                min_difficulty: config.min_difficulty,
+                base_reward_rate: config.base_reward_rate,
+                top_balance: config.top_balance,
+                address: program::config_address().to_string(),
+                next_reset_at,
+                treasury: TreasuryDocument {
+                    address: program::treasury_address().to_string(),
+                    token_account: program::treasury_tokens_address().to_string(),
+                    balance: treasury_tokens.amount,
+                },
+                mint: MintDocument {
+                    address: program::mint().to_string(),
+                    supply: mint.supply,
+                    decimals: mint.decimals,
+                },
+            });
+            return Ok(());
+        }
+
+        // Print human readable
+        println!("{}: {}", "Config".bold(), program::config_address());
+        println!(
+            "{}: {} ORE per hash of the min difficulty, doubling with each extra level",
+            "Base reward rate".bold(),
+            amount_u64_to_string(config.base_reward_rate)
+        );
         println!("{}: {}", "Min difficulty".bold(), config.min_difficulty);
-        println!("{}: {}", "Base reward rate".bold(), config.base_reward_rate);
         println!(
             "{}: {} ORE",
             "Top stake".bold(),
             amount_u64_to_string(config.top_balance)
         );
+        println!(
+            "{}: {}",
+            "Last reset at".bold(),
+            format_timestamp(config.last_reset_at)
+        );
+        println!(
+            "{}: {}",
+            "Next reset at".bold(),
+            format_timestamp(next_reset_at)
+        );
+        println!("{}: {}", "Treasury".bold(), program::treasury_address());
+        println!(
+            "{}: {}",
+            "Treasury token account".bold(),
+            program::treasury_tokens_address()
+        );
+        println!(
+            "{}: {} ORE",
+            "Treasury balance".bold(),
+            amount_u64_to_string(treasury_tokens.amount)
+        );
+        println!("{}: {}", "Mint".bold(), program::mint());
+        println!(
+            "{}: {} ORE",
+            "Supply".bold(),
+            amount_u64_to_string(mint.supply)
+        );
+        println!("{}: {}", "Decimals".bold(), mint.decimals);
+        Ok(())
+    }
+}
+
+impl Miner {
+    fn validate_config(&self) {
+        let path = settings::path();
+        let validation =
+            settings::validate(&Args::command(), &std::env::args_os().collect::<Vec<_>>());
+        if !validation.errors.is_empty() {
+            exit::set(exit::ERROR);
+        }
+        if self.is_json() {
+            print_json(&ConfigValidationDocument {
+                path: path.display().to_string(),
+                exists: path.exists(),
+                profile: validation.profile.clone(),
+                valid: validation.errors.is_empty(),
+                errors: validation.errors.clone(),
+                warnings: validation.warnings.clone(),
+                settings: validation
+                    .settings
+                    .iter()
+                    .map(|setting| SettingDocument {
+                        section: setting.section.clone(),
+                        key: setting.key.clone(),
+                        value: serde_json::to_value(&setting.value).unwrap_or_default(),
+                        source: setting.source.clone(),
+                    })
+                    .collect(),
+            });
+            return;
+        }
+
+        // Print problems
+        match path.exists() {
+            true => println!("{}: {}", "Config file".bold(), path.display()),
+            false => println!("{}: {} (not found)", "Config file".bold(), path.display()),
+        }
+        if let Some(profile) = &validation.profile {
+            println!("{}: {}", "Profile".bold(), profile);
+        }
+        for error in validation.errors.iter() {
+            println!("{} {}", "ERROR".bold().red(), error);
+        }
+        for warning in validation.warnings.iter() {
+            println!("{} {}", "WARNING".bold().yellow(), warning);
+        }
+        if validation.errors.is_empty() && validation.warnings.is_empty() {
+            println!("{} No problems found", "OK".bold().green());
+        }
+
+        // Print effective settings, global ones first
+        if validation.settings.is_empty() {
+            return;
+        }
+        println!("\n{}", "Effective settings".bold());
+        let mut section = None;
+        for setting in validation.settings.iter() {
+            if setting.section.ne(&section) {
+                section = setting.section.clone();
+                println!("[{}]", setting.section.as_deref().unwrap_or_default());
+            }
+            println!("  {} = {} ({})", setting.key, setting.value, setting.source);
+        }
     }
 }
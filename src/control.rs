@@ -0,0 +1,200 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    http::{self, Request, Response},
+    metrics::status_json,
+    reload, Miner,
+};
+
+/// Runtime controls for a running miner, changed through the control API.
+pub struct Control {
+    paused: AtomicBool,
+    claim_requested: AtomicBool,
+    priority_fee: Mutex<Option<u64>>,
+    dynamic_fee_max: Mutex<Option<u64>>,
+    fee_strategy: Mutex<Option<String>>,
+    threads: Mutex<Option<u64>>,
+}
+
+static CONTROL: Control = Control {
+    paused: AtomicBool::new(false),
+    claim_requested: AtomicBool::new(false),
+    priority_fee: Mutex::new(None),
+    dynamic_fee_max: Mutex::new(None),
+    fee_strategy: Mutex::new(None),
+    threads: Mutex::new(None),
+};
+
+pub fn control() -> &'static Control {
+    &CONTROL
+}
+
+impl Control {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn request_claim(&self) {
+        self.claim_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether a claim was requested, and clears the request.
+    pub fn take_claim_request(&self) -> bool {
+        self.claim_requested.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn priority_fee(&self) -> Option<u64> {
+        *self.priority_fee.lock().unwrap()
+    }
+
+    pub fn dynamic_fee_max(&self) -> Option<u64> {
+        *self.dynamic_fee_max.lock().unwrap()
+    }
+
+    pub fn fee_strategy(&self) -> Option<String> {
+        self.fee_strategy.lock().unwrap().clone()
+    }
+
+    /// Returns the number of threads to mine the next round with, if changed since start.
+    pub fn threads(&self) -> Option<u64> {
+        *self.threads.lock().unwrap()
+    }
+
+    /// Applies settings reloaded from the config file. They take effect from the next
+    /// transaction or round.
+    pub fn reload(
+        &self,
+        priority_fee: Option<u64>,
+        dynamic_fee_max: Option<u64>,
+        fee_strategy: Option<String>,
+        threads: u64,
+    ) {
+        *self.priority_fee.lock().unwrap() = priority_fee;
+        *self.dynamic_fee_max.lock().unwrap() = dynamic_fee_max;
+        *self.fee_strategy.lock().unwrap() = fee_strategy;
+        *self.threads.lock().unwrap() = Some(threads);
+    }
+}
+
+impl Miner {
+    /// Returns the static priority fee, preferring a fee set through the control API.
+    pub fn static_priority_fee(&self) -> u64 {
+        control().priority_fee().or(self.priority_fee).unwrap_or(0)
+    }
+
+    /// Returns the dynamic fee cap, preferring a cap set through the control API.
+    pub fn max_dynamic_fee(&self) -> Option<u64> {
+        control().dynamic_fee_max().or(self.dynamic_fee_max)
+    }
+
+    /// Returns the dynamic fee strategy, preferring one reloaded from the config file.
+    pub fn fee_strategy(&self) -> Option<String> {
+        self.dynamic_fee_url.as_ref().and(
+            control()
+                .fee_strategy()
+                .or_else(|| self.dynamic_fee_strategy.clone()),
+        )
+    }
+}
+
+/// Serves the control API on a background thread. Every request must carry the token as
+/// a bearer token.
+pub fn serve(addr: &str, token: String) {
+    http::serve("control API", addr, move |request| {
+        if !authorized(request, &token) {
+            return Response::json(401, json!({ "error": "Unauthorized" }));
+        }
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/status") => Response::json(200, api_status()),
+            ("POST", "/pause") => {
+                control().set_paused(true);
+                Response::json(200, api_status())
+            }
+            ("POST", "/resume") => {
+                control().set_paused(false);
+                Response::json(200, api_status())
+            }
+            ("POST", "/claim") => {
+                control().request_claim();
+                Response::json(200, api_status())
+            }
+            ("POST", "/fees") => update_fees(request),
+            ("POST", "/reload") => match reload::reload() {
+                Ok(()) => Response::json(200, api_status()),
+                Err(err) => Response::json(400, json!({ "error": err })),
+            },
+            (_, "/status" | "/pause" | "/resume" | "/claim" | "/fees" | "/reload") => {
+                Response::json(405, json!({ "error": "Method not allowed" }))
+            }
+            _ => Response::json(404, json!({ "error": "Not found" })),
+        }
+    });
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    let Some(value) = request.headers.get("authorization") else {
+        return false;
+    };
+    let expected = format!("Bearer {}", token);
+
+    // Compare in constant time
+    value.len().eq(&expected.len())
+        && value
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            .eq(&0)
+}
+
+fn api_status() -> Value {
+    let mut value = status_json();
+    if let Value::Object(map) = &mut value {
+        map.insert("paused".to_string(), control().is_paused().into());
+        map.insert(
+            "claim_requested".to_string(),
+            control().claim_requested.load(Ordering::Relaxed).into(),
+        );
+        map.insert("priority_fee".to_string(), control().priority_fee().into());
+        map.insert(
+            "dynamic_fee_max".to_string(),
+            control().dynamic_fee_max().into(),
+        );
+        map.insert("fee_strategy".to_string(), control().fee_strategy().into());
+        map.insert("threads".to_string(), control().threads().into());
+    }
+    value
+}
+
+fn update_fees(request: &Request) -> Response {
+    let Ok(body) = serde_json::from_slice::<Value>(&request.body) else {
+        return Response::json(400, json!({ "error": "Invalid JSON body" }));
+    };
+    for (key, setting) in [
+        ("priority_fee", &control().priority_fee),
+        ("dynamic_fee_max", &control().dynamic_fee_max),
+    ] {
+        match body.get(key) {
+            None => {}
+            Some(Value::Null) => *setting.lock().unwrap() = None,
+            Some(value) => match value.as_u64() {
+                Some(fee) => *setting.lock().unwrap() = Some(fee),
+                None => {
+                    return Response::json(
+                        400,
+                        json!({ "error": format!("{} must be an integer or null", key) }),
+                    )
+                }
+            },
+        }
+    }
+    Response::json(200, api_status())
+}
@@ -0,0 +1,271 @@
+//! Runs `mine` in the background. The daemon is the same command started again as a
+//! detached process, with logs going to the log file and its pid in a pid file.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use colored::*;
+
+use crate::{
+    args::{StatusArgs, StopArgs},
+    error::OreError,
+    exit,
+    utils::ore_dir,
+    Miner,
+};
+
+/// Set in the environment of the detached process, so it doesn't detach again.
+const DAEMON_ENV: &str = "ORE_DAEMONIZED";
+
+const PID_FILE: &str = "ore.pid";
+
+const LOG_FILE: &str = "ore.log";
+
+/// How long to wait for the daemon to fail at startup before reporting it started.
+const STARTUP_GRACE: Duration = Duration::from_secs(1);
+
+/// How long `ore stop` waits for the daemon to exit.
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn default_pid_file() -> PathBuf {
+    ore_dir().join(PID_FILE)
+}
+
+pub fn default_log_file() -> PathBuf {
+    ore_dir().join(LOG_FILE)
+}
+
+/// Whether this process is a detached daemon.
+pub fn is_daemon() -> bool {
+    std::env::var_os(DAEMON_ENV).is_some()
+}
+
+/// Starts this command again as a detached process and returns its pid. Logs go to the
+/// log file, which is added to the arguments if none is set.
+pub fn spawn(log_file: Option<&Path>) -> Result<u32, String> {
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let mut command = Command::new(exe);
+    command
+        .args(std::env::args_os().skip(1))
+        .env(DAEMON_ENV, "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if log_file.is_none() {
+        command.arg("--log-file").arg(default_log_file());
+    }
+    detach(&mut command);
+    let mut child = command.spawn().map_err(|err| err.to_string())?;
+    std::thread::sleep(STARTUP_GRACE);
+    if let Ok(Some(status)) = child.try_wait() {
+        return Err(format!("The daemon exited at startup with {}", status));
+    }
+    Ok(child.id())
+}
+
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    // Leave the terminal's process group, so its signals don't reach the daemon
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+/// A pid file that is removed when dropped.
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Writes the pid of this process to the file, unless another running process owns it.
+    pub fn create(path: PathBuf) -> Result<Self, OreError> {
+        if let Some(pid) = read_pid(&path) {
+            if pid.ne(&std::process::id()) && is_running(pid) {
+                return Err(OreError::Daemon(format!(
+                    "ore is already running with pid {} ({})",
+                    pid,
+                    path.display()
+                )));
+            }
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        std::fs::write(&path, format!("{}\n", std::process::id())).map_err(|err| {
+            OreError::Daemon(format!("Failed to write {}: {}", path.display(), err))
+        })?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if read_pid(&self.path).eq(&Some(std::process::id())) {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // Signal 0 checks for the process without signalling it
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result.eq(&0)
+        || std::io::Error::last_os_error()
+            .raw_os_error()
+            .eq(&Some(libc::EPERM))
+}
+
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+pub fn terminate(pid: u32) -> std::io::Result<()> {
+    match unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+/// The name of the event that asks a process to shut down on Windows, which has no
+/// SIGTERM. Console control events can't reach a detached process, which has no console.
+#[cfg(windows)]
+fn stop_event_name(pid: u32) -> Vec<u16> {
+    format!("Local\\ore-stop-{}", pid)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+/// Asks the process to shut down by setting its stop event. Kills it if it doesn't listen
+/// for one, e.g. when it runs an older version of ore.
+#[cfg(windows)]
+pub fn terminate(pid: u32) -> std::io::Result<()> {
+    use windows_sys::Win32::{
+        Foundation::CloseHandle,
+        System::Threading::{OpenEventW, SetEvent, EVENT_MODIFY_STATE},
+    };
+    let name = stop_event_name(pid);
+    let event = unsafe { OpenEventW(EVENT_MODIFY_STATE, 0, name.as_ptr()) };
+    if event.ne(&0) {
+        let set = unsafe { SetEvent(event) };
+        unsafe { CloseHandle(event) };
+        if set.ne(&0) {
+            return Ok(());
+        }
+    }
+    let status = Command::new("taskkill")
+        .args(["/F", "/PID", &pid.to_string()])
+        .stdout(Stdio::null())
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(std::io::Error::other(format!(
+            "taskkill exited with {}",
+            status
+        ))),
+    }
+}
+
+/// Waits until the stop event of this process is set. Returns `None` if it can't be
+/// created.
+#[cfg(windows)]
+fn stop_event() -> Option<impl std::future::Future<Output = ()>> {
+    use windows_sys::Win32::System::Threading::{CreateEventW, WaitForSingleObject, INFINITE};
+    let name = stop_event_name(std::process::id());
+    let event = unsafe { CreateEventW(std::ptr::null(), 1, 0, name.as_ptr()) };
+    if event.eq(&0) {
+        return None;
+    }
+    Some(async move {
+        tokio::task::spawn_blocking(move || unsafe { WaitForSingleObject(event, INFINITE) })
+            .await
+            .ok();
+    })
+}
+
+/// Waits for Ctrl-C, or for `ore stop`: SIGTERM on unix, and the stop event on Windows.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    #[cfg(windows)]
+    {
+        if let Some(stop) = stop_event() {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = stop => {}
+            }
+            return;
+        }
+    }
+    tokio::signal::ctrl_c().await.ok();
+}
+
+impl Miner {
+    pub async fn stop(&self, args: StopArgs) -> Result<(), OreError> {
+        let path = args.pid_file.unwrap_or_else(default_pid_file);
+        let Some(pid) = read_pid(&path).filter(|pid| is_running(*pid)) else {
+            return Err(OreError::Daemon(format!(
+                "ore is not running ({} has no running pid)",
+                path.display()
+            )));
+        };
+        terminate(pid)
+            .map_err(|err| OreError::Daemon(format!("Failed to stop pid {}: {}", pid, err)))?;
+
+        // Wait for the session to wind down
+        let started_at = std::time::Instant::now();
+        while is_running(pid) {
+            if started_at.elapsed().gt(&STOP_TIMEOUT) {
+                return Err(OreError::Daemon(format!(
+                    "pid {} did not exit within {} sec",
+                    pid,
+                    STOP_TIMEOUT.as_secs()
+                )));
+            }
+            self.sleep(Duration::from_millis(200)).await?;
+        }
+        println!("{} Stopped ore (pid {})", "OK".bold().green(), pid);
+        Ok(())
+    }
+
+    pub async fn status(&self, args: StatusArgs) {
+        let path = args.pid_file.unwrap_or_else(default_pid_file);
+        match read_pid(&path).filter(|pid| is_running(*pid)) {
+            Some(pid) => println!("{}: running (pid {})", "Status".bold(), pid),
+            None => {
+                println!("{}: {}", "Status".bold(), "not running".bold().red());
+                exit::set(exit::ERROR);
+            }
+        }
+    }
+}
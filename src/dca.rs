@@ -0,0 +1,250 @@
+use std::{path::PathBuf, sync::Mutex, time::Duration};
+
+use chrono::Utc;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Signature;
+use tracing::{error, info};
+
+use crate::{
+    args::DcaArgs,
+    error::OreError,
+    send_and_confirm::SentSignature,
+    swap::{check_slippage, SwapMode},
+    utils::{amount_f64_to_u64, amount_u64_to_string, format_timestamp, ore_dir, parse_duration},
+    Miner,
+};
+
+const STATE_FILE: &str = "dca.json";
+
+/// The shortest interval between sales, in seconds.
+const MIN_INTERVAL: u64 = 60;
+
+/// The longest to wait before retrying a failed sale.
+const MAX_RETRY_DELAY: u64 = 15 * 60;
+
+/// How often to check whether the signatures of an unfinished sale expired.
+const EXPIRY_POLL_DELAY: Duration = Duration::from_secs(5);
+
+/// Parses the interval between sales, e.g. `12h`, of at least a minute.
+pub fn parse_interval(s: &str) -> Result<u64, String> {
+    let secs = parse_duration(s)?;
+    if secs.lt(&MIN_INTERVAL) {
+        return Err(format!("The interval must be at least {}s", MIN_INTERVAL));
+    }
+    Ok(secs)
+}
+
+/// The progress of a DCA schedule, saved after every sale.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct DcaState {
+    /// When the last sale was sent.
+    last_sale_at: Option<i64>,
+    /// When a sale was started that isn't known to have finished.
+    pending_since: Option<i64>,
+    /// The signatures of the unfinished sale, saved before each is sent.
+    #[serde(default)]
+    sent: Vec<SentSignature>,
+    /// The ORE sold so far, in grains.
+    sold: u64,
+    sales: u64,
+}
+
+impl DcaState {
+    fn load(path: &PathBuf) -> Result<Self, OreError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|err| {
+                OreError::Swap(format!("Invalid DCA state in {}: {}", path.display(), err))
+            }),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Counts the unfinished sale as sold.
+    fn record_sale(&mut self, amount: u64) {
+        self.last_sale_at = self.pending_since.take().or(self.last_sale_at);
+        self.sent.clear();
+        self.sold += amount;
+        self.sales += 1;
+    }
+
+    fn save(&self, path: &PathBuf) -> Result<(), OreError> {
+        let contents = serde_json::to_string_pretty(self).unwrap_or_default();
+        std::fs::write(path, contents)
+            .map_err(|err| OreError::Swap(format!("Failed to write {}: {}", path.display(), err)))
+    }
+}
+
+impl Miner {
+    pub async fn dca(&self, args: DcaArgs) -> Result<(), OreError> {
+        check_slippage(args.slippage_bps, args.allow_high_slippage)?;
+        let path = args
+            .state_file
+            .clone()
+            .unwrap_or_else(|| ore_dir().join(STATE_FILE));
+        let mut state = DcaState::load(&path)?;
+        let amount = amount_f64_to_u64(args.amount);
+
+        // Stop on Ctrl-C
+        let cancel = self.cancellation_token();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                cancel.cancel();
+            }
+        });
+
+        // A sale that was interrupted may still land, so find out whether it did before
+        // selling again
+        if let Some(pending_since) = state.pending_since {
+            info!(
+                "Checking whether the sale started at {} landed",
+                format_timestamp(pending_since)
+            );
+            self.settle_sale(amount, &path, &mut state).await?;
+        }
+
+        info!(
+            "Selling {} ORE every {}s. State is kept in {}",
+            amount_u64_to_string(amount),
+            args.every,
+            path.display()
+        );
+        match self.dca_loop(&args, amount, &path, &mut state).await {
+            Err(OreError::Cancelled) => {
+                println!(
+                    "Stopped after selling {} ORE in {} sales",
+                    amount_u64_to_string(state.sold),
+                    state.sales
+                );
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    async fn dca_loop(
+        &self,
+        args: &DcaArgs,
+        amount: u64,
+        path: &PathBuf,
+        state: &mut DcaState,
+    ) -> Result<(), OreError> {
+        loop {
+            // Wait for the next sale
+            let now = Utc::now().timestamp();
+            let next_sale_at = state
+                .last_sale_at
+                .map(|at| at.saturating_add(args.every as i64))
+                .unwrap_or(now);
+            if next_sale_at.gt(&now) {
+                info!("Next sale at {}", format_timestamp(next_sale_at));
+                self.sleep(Duration::from_secs((next_sale_at - now) as u64))
+                    .await?;
+            }
+
+            // Sell, marking the sale as pending until it finishes, and saving each
+            // signature before it is sent
+            state.pending_since = Some(Utc::now().timestamp());
+            state.sent.clear();
+            state.save(path)?;
+            let journal = Mutex::new(state.clone());
+            let on_signed = |signature: Signature, last_valid_block_height: u64| {
+                let mut journal = journal.lock().unwrap();
+                journal.sent.push(SentSignature {
+                    signature: signature.to_string(),
+                    last_valid_block_height,
+                });
+                journal.save(path)
+            };
+            let result = self
+                .sell_ore(
+                    amount,
+                    args.to,
+                    args.slippage_bps,
+                    SwapMode::Unattended,
+                    &on_signed,
+                )
+                .await;
+            *state = journal.into_inner().unwrap();
+            match result {
+                Ok(_) => {
+                    state.record_sale(amount);
+                    state.save(path)?;
+                    println!(
+                        "{} Sold {} ORE in total over {} sales",
+                        "OK".bold().green(),
+                        amount_u64_to_string(state.sold),
+                        state.sales
+                    );
+                }
+
+                // The swap may still land, so the next start checks whether it did
+                Err(OreError::Cancelled) => return Err(OreError::Cancelled),
+
+                // The swap may also land after an error like a confirm timeout, so only
+                // retry once it can't
+                Err(err) => {
+                    error!("Sale failed: {}", err);
+                    if self.settle_sale(amount, path, state).await? {
+                        continue;
+                    }
+                    let delay = args.every.min(MAX_RETRY_DELAY);
+                    info!("Retrying in {}s", delay);
+                    self.sleep(Duration::from_secs(delay)).await?;
+                }
+            }
+        }
+    }
+
+    /// Waits until none of the signatures sent for the unfinished sale can land any more,
+    /// then counts it as sold if one of them landed. Returns whether it did.
+    async fn settle_sale(
+        &self,
+        amount: u64,
+        path: &PathBuf,
+        state: &mut DcaState,
+    ) -> Result<bool, OreError> {
+        let signatures: Vec<Signature> = state
+            .sent
+            .iter()
+            .filter_map(|sent| sent.signature.parse().ok())
+            .collect();
+        let mut landed = None;
+        if let Some(last_valid_block_height) = state
+            .sent
+            .iter()
+            .map(|sent| sent.last_valid_block_height)
+            .max()
+        {
+            while self
+                .rpc_client
+                .get_block_height()
+                .await?
+                .le(&last_valid_block_height)
+            {
+                self.sleep(EXPIRY_POLL_DELAY).await?;
+            }
+            let statuses = self
+                .rpc_client
+                .get_signature_statuses_with_history(&signatures)
+                .await?
+                .value;
+            landed = signatures
+                .into_iter()
+                .zip(statuses)
+                .find_map(|(sig, status)| status.filter(|s| s.err.is_none()).map(|_| sig));
+        }
+        match landed {
+            Some(sig) => {
+                info!("The sale landed: {}", sig);
+                state.record_sale(amount);
+            }
+            None => {
+                state.pending_since = None;
+                state.sent.clear();
+            }
+        }
+        state.save(path)?;
+        Ok(landed.is_some())
+    }
+}
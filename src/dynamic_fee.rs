@@ -1,115 +1,256 @@
-use crate::Miner;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use crate::{fee_escalation::escalation, program, timeout::http_client, Miner};
 
-use ore_api::consts::BUS_ADDRESSES;
-use reqwest::Client;
 use serde_json::{json, Value};
+use tracing::{debug, info_span, warn, Instrument};
 
-impl Miner {
-    pub async fn dynamic_fee(&self) -> u64 {
-        let ore_addresses: Vec<String> =
-            std::iter::once("oreV2ZymfyeXgNgBdqMkumTqqAprVqgBWQfoYkrtKWQ".to_string())
-                .chain(BUS_ADDRESSES.iter().map(|pubkey| pubkey.to_string()))
-                .collect();
-
-        match &self.dynamic_fee_strategy {
-            None => self.priority_fee.unwrap_or(0),
-            Some(strategy) => {
-                let client = Client::new();
-
-                let body = match strategy.as_str() {
-                    "helius" => {
-                        json!({
-                            "jsonrpc": "2.0",
-                            "id": "priority-fee-estimate",
-                            "method": "getPriorityFeeEstimate",
-                            "params": [{
-                                "accountKeys": ore_addresses,
-                                "options": {
-                                    "recommended": true
-                                }
-                            }]
-                        })
-                    }
-                    "triton" => {
-                        json!({
-                            "jsonrpc": "2.0",
-                            "id": "priority-fee-estimate",
-                            "method": "getRecentPrioritizationFees",
-                            "params": [
-                                ore_addresses,
-                                {
-                                    "percentile": 5000,
-                                }
-                            ]
-                        })
+/// The percentile of recent fees the local-percentile strategy pays.
+const LOCAL_PERCENTILE: usize = 75;
+
+/// The strategies registered with [`register_fee_strategy`].
+static CUSTOM_STRATEGIES: Mutex<Vec<(String, Arc<dyn FeeStrategy>)>> = Mutex::new(vec![]);
+
+pub type FeeFuture<'a> = Pin<Box<dyn Future<Output = Result<u64, String>> + Send + 'a>>;
+
+/// What a fee strategy can use to estimate the priority fee.
+pub struct FeeContext<'a> {
+    /// The URL given with `--dynamic-fee-url`.
+    pub url: &'a str,
+    /// The accounts ORE transactions write to, as base58 addresses.
+    pub accounts: &'a [String],
+    /// The static priority fee, in microlamports.
+    pub static_fee: u64,
+}
+
+/// Estimates the priority fee of a transaction, in microlamports per compute unit.
+pub trait FeeStrategy: Send + Sync {
+    fn estimate<'a>(&'a self, context: &'a FeeContext<'a>) -> FeeFuture<'a>;
+}
+
+/// Makes a custom strategy available to `--dynamic-fee-strategy` under the name. It takes
+/// precedence over a built-in strategy with the same name.
+pub fn register_fee_strategy(name: &str, strategy: impl FeeStrategy + 'static) {
+    let mut strategies = CUSTOM_STRATEGIES.lock().unwrap();
+    strategies.retain(|(n, _)| n.ne(name));
+    strategies.push((name.to_string(), Arc::new(strategy)));
+}
+
+/// Returns the strategy with the name, custom strategies first.
+pub fn fee_strategy(name: &str) -> Option<Arc<dyn FeeStrategy>> {
+    if let Some((_, strategy)) = CUSTOM_STRATEGIES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(n, _)| n.eq(name))
+    {
+        return Some(strategy.clone());
+    }
+    let strategy: Arc<dyn FeeStrategy> = match name {
+        "static" => Arc::new(StaticFee),
+        "helius" => Arc::new(HeliusFee),
+        "triton" => Arc::new(TritonFee),
+        "alchemy" => Arc::new(AlchemyFee),
+        "local-percentile" => Arc::new(LocalPercentileFee {
+            percentile: LOCAL_PERCENTILE,
+        }),
+        _ => return None,
+    };
+    Some(strategy)
+}
+
+/// Pays the static priority fee.
+pub struct StaticFee;
+
+impl FeeStrategy for StaticFee {
+    fn estimate<'a>(&'a self, context: &'a FeeContext<'a>) -> FeeFuture<'a> {
+        Box::pin(async move { Ok(context.static_fee) })
+    }
+}
+
+/// Pays Helius' recommended fee from `getPriorityFeeEstimate`.
+pub struct HeliusFee;
+
+impl FeeStrategy for HeliusFee {
+    fn estimate<'a>(&'a self, context: &'a FeeContext<'a>) -> FeeFuture<'a> {
+        Box::pin(async move {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": "priority-fee-estimate",
+                "method": "getPriorityFeeEstimate",
+                "params": [{
+                    "accountKeys": context.accounts,
+                    "options": {
+                        "recommended": true
                     }
-                    "alchemy" => {
-                        json!({
-                            "jsonrpc": "2.0",
-                            "id": "priority-fee-estimate",
-                            "method": "getRecentPrioritizationFees",
-                            "params": [
-                                ore_addresses,
-                            ]
-                        })
+                }]
+            });
+            let response = post(context.url, &body).await?;
+            response["result"]["priorityFeeEstimate"]
+                .as_f64()
+                .map(|fee| fee as u64)
+                .ok_or_else(|| format!("Failed to parse priority fee. Response: {:?}", response))
+        })
+    }
+}
+
+/// Pays the median fee of Triton's `getRecentPrioritizationFees`.
+pub struct TritonFee;
+
+impl FeeStrategy for TritonFee {
+    fn estimate<'a>(&'a self, context: &'a FeeContext<'a>) -> FeeFuture<'a> {
+        Box::pin(async move {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": "priority-fee-estimate",
+                "method": "getRecentPrioritizationFees",
+                "params": [
+                    context.accounts,
+                    {
+                        "percentile": 5000,
                     }
-                    _ => return self.priority_fee.unwrap_or(0),
-                };
-
-                let response: Value = client
-                    .post(self.dynamic_fee_url.as_ref().unwrap())
-                    .json(&body)
-                    .send()
-                    .await
-                    .unwrap()
-                    .json()
-                    .await
-                    .unwrap();
-
-                let calculated_fee =
-                    match strategy.as_str() {
-                        "helius" => response["result"]["priorityFeeEstimate"]
-                            .as_f64()
-                            .map(|fee| fee as u64)
-                            .ok_or_else(|| {
-                                format!("Failed to parse priority fee. Response: {:?}", response)
-                            })
-                            .unwrap(),
-                        "triton" => response["result"]
-                            .as_array()
-                            .and_then(|arr| arr.last())
-                            .and_then(|last| last["prioritizationFee"].as_u64())
-                            .ok_or_else(|| {
-                                format!("Failed to parse priority fee. Response: {:?}", response)
-                            })
-                            .unwrap(),
-                        "alchemy" => response["result"]
-                            .as_array()
-                            .and_then(|arr| {
-                                Some(
-                                    arr.into_iter()
-                                        .map(|v| v["prioritizationFee"].as_u64().unwrap())
-                                        .collect::<Vec<u64>>(),
-                                )
-                            })
-                            .and_then(|fees| {
-                                Some((fees.iter().sum::<u64>() as f32 / fees.len() as f32).ceil()
-                                    as u64)
-                            })
-                            .ok_or_else(|| {
-                                format!("Failed to parse priority fee. Response: {:?}", response)
-                            })
-                            .unwrap(),
-                        _ => return self.priority_fee.unwrap_or(0),
-                    };
-
-                // Check if the calculated fee is higher than self.dynamic_fee_max
-                if let Some(max_fee) = self.dynamic_fee_max {
-                    calculated_fee.min(max_fee)
-                } else {
-                    calculated_fee
-                }
+                ]
+            });
+            let response = post(context.url, &body).await?;
+            response["result"]
+                .as_array()
+                .and_then(|arr| arr.last())
+                .and_then(|last| last["prioritizationFee"].as_u64())
+                .ok_or_else(|| format!("Failed to parse priority fee. Response: {:?}", response))
+        })
+    }
+}
+
+/// Pays the mean of recent fees from `getRecentPrioritizationFees`.
+pub struct AlchemyFee;
+
+impl FeeStrategy for AlchemyFee {
+    fn estimate<'a>(&'a self, context: &'a FeeContext<'a>) -> FeeFuture<'a> {
+        Box::pin(async move {
+            let fees = recent_fees(context).await?;
+            Ok((fees.iter().sum::<u64>() as f32 / fees.len() as f32).ceil() as u64)
+        })
+    }
+}
+
+/// Pays a percentile of recent fees from `getRecentPrioritizationFees`, computed locally,
+/// so it works with any RPC.
+pub struct LocalPercentileFee {
+    pub percentile: usize,
+}
+
+impl FeeStrategy for LocalPercentileFee {
+    fn estimate<'a>(&'a self, context: &'a FeeContext<'a>) -> FeeFuture<'a> {
+        Box::pin(async move {
+            let mut fees = recent_fees(context).await?;
+            fees.sort_unstable();
+            let index = (fees.len() * self.percentile.min(100) / 100).min(fees.len() - 1);
+            Ok(fees[index])
+        })
+    }
+}
+
+/// Returns the fees of recent slots with transactions that wrote to the accounts.
+async fn recent_fees(context: &FeeContext<'_>) -> Result<Vec<u64>, String> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": "priority-fee-estimate",
+        "method": "getRecentPrioritizationFees",
+        "params": [
+            context.accounts,
+        ]
+    });
+    let response = post(context.url, &body).await?;
+    let fees: Vec<u64> = response["result"]
+        .as_array()
+        .ok_or_else(|| format!("Failed to parse priority fee. Response: {:?}", response))?
+        .iter()
+        .filter_map(|v| v["prioritizationFee"].as_u64())
+        .collect();
+    if fees.is_empty() {
+        return Err("No recent prioritization fees".to_string());
+    }
+    Ok(fees)
+}
+
+async fn post(url: &str, body: &Value) -> Result<Value, String> {
+    http_client()
+        .post(url)
+        .json(body)
+        .send()
+        .instrument(info_span!("http", url))
+        .await
+        .map_err(|err| format!("Failed to fetch priority fee: {}", err))?
+        .json()
+        .await
+        .map_err(|err| format!("Failed to parse priority fee: {}", err))
+}
+
+impl Miner {
+    /// Returns the priority fee to pay: the dynamic fee if a dynamic fee URL is set,
+    /// otherwise the static fee, raised while mine transactions keep expiring.
+    pub async fn priority_fee(&self) -> u64 {
+        let fee = match &self.dynamic_fee_url {
+            Some(_) => self.dynamic_fee().await,
+            None => self.static_priority_fee(),
+        };
+        let bump = escalation().bump();
+        if bump.eq(&0) {
+            return fee;
+        }
+        let escalated = fee.saturating_add(bump);
+        match self.max_dynamic_fee() {
+            Some(max) => escalated.min(max.max(fee)),
+            None => escalated,
+        }
+    }
+
+    /// Estimates the priority fee with the dynamic fee strategy, capped at the maximum. Falls
+    /// back to the static fee if the strategy is unknown or fails.
+    #[tracing::instrument(skip_all, fields(strategy = ?self.fee_strategy()))]
+    pub async fn dynamic_fee(&self) -> u64 {
+        let static_fee = self.static_priority_fee();
+        let (Some(name), Some(url)) = (self.fee_strategy(), &self.dynamic_fee_url) else {
+            return static_fee;
+        };
+        let Some(strategy) = fee_strategy(&name) else {
+            warn!(
+                "Unknown fee strategy {:?}. Using the static priority fee.",
+                name
+            );
+            return static_fee;
+        };
+        let accounts: Vec<String> = std::iter::once(program::program_id().to_string())
+            .chain(
+                program::bus_addresses()
+                    .iter()
+                    .map(|pubkey| pubkey.to_string()),
+            )
+            .collect();
+        let context = FeeContext {
+            url,
+            accounts: &accounts,
+            static_fee,
+        };
+        let calculated_fee = match self.cancellable(strategy.estimate(&context)).await {
+            Ok(Ok(fee)) => fee,
+            Ok(Err(err)) => {
+                warn!("{}. Using the static priority fee.", err);
+                return static_fee;
             }
+            Err(_) => return static_fee,
+        };
+        debug!("Estimated priority fee of {} microlamports", calculated_fee);
+
+        // Check if the calculated fee is higher than self.dynamic_fee_max
+        if let Some(max_fee) = self.max_dynamic_fee() {
+            calculated_fee.min(max_fee)
+        } else {
+            calculated_fee
         }
     }
 }
@@ -0,0 +1,149 @@
+use solana_client::client_error::ClientError;
+
+use crate::exit;
+
+/// An error that stops a command.
+#[derive(Debug, thiserror::Error)]
+pub enum OreError {
+    #[error("No keypair provided")]
+    MissingKeypair,
+
+    #[error("No keypair found at {path}: {reason}")]
+    Keypair { path: String, reason: String },
+
+    #[error(
+        "Insufficient balance: {balance} {token}. Please top up with at least {required} {token}."
+    )]
+    InsufficientFunds {
+        balance: f64,
+        required: f64,
+        token: &'static str,
+    },
+
+    #[error("Invalid address: {0:?}")]
+    InvalidAddress(String),
+
+    #[error("No {0} account found")]
+    AccountNotFound(&'static str),
+
+    #[error("Failed to parse {0} account")]
+    InvalidAccount(&'static str),
+
+    #[error("Failed to get {0} account: {1}")]
+    Fetch(&'static str, ClientError),
+
+    #[error("Failed to read directory {path}: {reason}")]
+    ReadDir { path: String, reason: String },
+
+    #[error(
+        "Incompatible ORE program: {0}. Update ore, or pass --skip-version-check to run anyway."
+    )]
+    IncompatibleProgram(String),
+
+    #[error("{0}")]
+    Localnet(String),
+
+    #[error("Swap failed: {0}")]
+    Swap(String),
+
+    #[error("{0}")]
+    Close(String),
+
+    #[error("{0}")]
+    Keygen(String),
+
+    #[error("{0}")]
+    Airdrop(String),
+
+    #[error("Could not fetch ORE and SOL prices. Pass --ore-price and --sol-price.")]
+    PricesUnavailable,
+
+    #[error("Unknown format: {0:?}")]
+    UnknownFormat(String),
+
+    #[error("{0}")]
+    Daemon(String),
+
+    #[error("{0}")]
+    Systemd(String),
+
+    #[error("{0}")]
+    Service(String),
+
+    #[error("{0}")]
+    Locked(String),
+
+    #[error("{0}")]
+    ClaimQueue(String),
+
+    #[error("Invalid solution: {0}")]
+    InvalidSolution(String),
+
+    #[error("Cancelled")]
+    Cancelled,
+
+    #[error("Transaction expired before it landed")]
+    TransactionExpired,
+
+    #[error("Transaction failed: {0}")]
+    TransactionFailed(String),
+
+    #[error(transparent)]
+    Rpc(#[from] ClientError),
+}
+
+/// A [`crate::MinerBuilder`] setting that is invalid or conflicts with another.
+#[derive(Debug, thiserror::Error)]
+pub enum BuildError {
+    #[error("{0} requires a dynamic fee URL")]
+    MissingDynamicFeeUrl(&'static str),
+
+    #[error("Invalid dynamic fee URL: {0:?}")]
+    InvalidDynamicFeeUrl(String),
+
+    #[error("Unknown dynamic fee strategy: {0:?}")]
+    UnknownFeeStrategy(String),
+}
+
+impl OreError {
+    /// Returns the exit code for the error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            OreError::InsufficientFunds { .. } => exit::INSUFFICIENT_FUNDS,
+            OreError::Fetch(..) | OreError::Rpc(_) | OreError::PricesUnavailable => {
+                exit::RPC_FAILURE
+            }
+            OreError::TransactionExpired => exit::TX_EXPIRED,
+            OreError::Cancelled => exit::INTERRUPTED,
+            OreError::TransactionFailed(_) => exit::TX_FAILED,
+            OreError::MissingKeypair
+            | OreError::Keypair { .. }
+            | OreError::InvalidAddress(_)
+            | OreError::AccountNotFound(_)
+            | OreError::InvalidAccount(_)
+            | OreError::IncompatibleProgram(_)
+            | OreError::Localnet(_)
+            | OreError::Swap(_)
+            | OreError::Close(_)
+            | OreError::Keygen(_)
+            | OreError::Airdrop(_)
+            | OreError::UnknownFormat(_)
+            | OreError::ReadDir { .. }
+            | OreError::Daemon(_)
+            | OreError::Systemd(_)
+            | OreError::Service(_)
+            | OreError::Locked(_)
+            | OreError::ClaimQueue(_)
+            | OreError::InvalidSolution(_) => exit::ERROR,
+        }
+    }
+
+    /// Converts an error fetching an account, telling missing accounts apart from RPC
+    /// failures.
+    pub fn fetch(account: &'static str, err: ClientError) -> Self {
+        match err.to_string().contains("AccountNotFound") {
+            true => OreError::AccountNotFound(account),
+            false => OreError::Fetch(account, err),
+        }
+    }
+}
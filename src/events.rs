@@ -0,0 +1,168 @@
+use serde::Serialize;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::{
+    metrics::{status, LandedTransaction},
+    notify::{notify, Event},
+    output::print_json,
+    schema::{MineRoundDocument, MineSolutionDocument, MineSubmissionDocument},
+};
+
+/// How many events a slow subscriber can fall behind before it misses some.
+const CAPACITY: usize = 1024;
+
+/// Progress of the mining loop. Subscribe with [`crate::Miner::subscribe`].
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MiningEvent {
+    /// A round started on a new challenge.
+    RoundStarted {
+        round: usize,
+        challenge: String,
+        stake: u64,
+        multiplier: f64,
+        min_difficulty: u64,
+        cutoff_time: u64,
+    },
+    /// The hashrate and best difficulty so far, about once a second while hashing, with the
+    /// reward the best hash would earn including the stake multiplier. The reward is 0 until
+    /// the best hash reaches the min difficulty.
+    HashrateSample {
+        round: usize,
+        hashrate: u64,
+        best_difficulty: u32,
+        expected_reward: u64,
+    },
+    /// Hashing stopped with the best solution of the round.
+    SolutionFound {
+        round: usize,
+        difficulty: u32,
+        hash: String,
+        hashrate: u64,
+    },
+    /// The solution is being submitted to a bus.
+    TxSubmitted {
+        round: usize,
+        difficulty: u32,
+        bus: String,
+    },
+    /// The submission landed, or didn't if `signature` is `None`.
+    TxConfirmed {
+        round: usize,
+        difficulty: u32,
+        bus: String,
+        signature: Option<String>,
+    },
+    /// A landed submission paid out.
+    RewardReceived {
+        round: usize,
+        signature: String,
+        timestamp: i64,
+        difficulty: u32,
+        reward: u64,
+        fee: u64,
+    },
+}
+
+pub fn channel() -> broadcast::Sender<MiningEvent> {
+    broadcast::channel(CAPACITY).0
+}
+
+/// Renders events for the CLI until stopped, then renders the events still queued.
+pub fn spawn_renderer(
+    mut events: broadcast::Receiver<MiningEvent>,
+    json: bool,
+) -> (oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+    let (stop, mut stopped) = oneshot::channel();
+    let handle = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+                event = events.recv() => match event {
+                    Ok(event) => render(&event, json),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = &mut stopped => {
+                    while let Ok(event) = events.try_recv() {
+                        render(&event, json);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+    (stop, handle)
+}
+
+fn render(event: &MiningEvent, json: bool) {
+    match event {
+        MiningEvent::RoundStarted {
+            round,
+            challenge,
+            stake,
+            multiplier,
+            min_difficulty,
+            ..
+        } if json => print_json(&MineRoundDocument {
+            round: *round,
+            challenge: challenge.clone(),
+            stake: *stake,
+            multiplier: *multiplier,
+            min_difficulty: *min_difficulty,
+        }),
+        MiningEvent::HashrateSample {
+            best_difficulty,
+            expected_reward,
+            ..
+        } => {
+            let mut status = status();
+            status.round_best_difficulty = *best_difficulty;
+            status.round_expected_reward = *expected_reward;
+        }
+        MiningEvent::SolutionFound {
+            round,
+            difficulty,
+            hash,
+            ..
+        } if json => print_json(&MineSolutionDocument {
+            round: *round,
+            difficulty: *difficulty,
+            hash: hash.clone(),
+        }),
+        MiningEvent::TxConfirmed {
+            round,
+            difficulty,
+            bus,
+            signature,
+        } if json => print_json(&MineSubmissionDocument {
+            round: *round,
+            difficulty: *difficulty,
+            bus: bus.clone(),
+            signature: signature.clone(),
+            landed: signature.is_some(),
+        }),
+        MiningEvent::RewardReceived {
+            signature,
+            timestamp,
+            difficulty,
+            reward,
+            fee,
+            ..
+        } => {
+            notify(Event::Landed {
+                signature: signature.clone(),
+                difficulty: *difficulty,
+                reward: *reward,
+            });
+            status().push_landed(LandedTransaction {
+                signature: signature.clone(),
+                timestamp: *timestamp,
+                difficulty: *difficulty,
+                reward: *reward,
+                fee: *fee,
+            });
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The command succeeded.
+pub const OK: i32 = 0;
+
+/// The command failed for a reason without a more specific code.
+pub const ERROR: i32 = 1;
+
+/// The fee payer does not have enough SOL to pay for transactions, or the signer does not
+/// have the ORE to send.
+pub const INSUFFICIENT_FUNDS: i32 = 2;
+
+/// The RPC could not be reached or returned an error, or the price API could not be reached.
+pub const RPC_FAILURE: i32 = 3;
+
+/// A transaction was sent but did not land before its blockhash expired.
+pub const TX_EXPIRED: i32 = 4;
+
+/// A transaction landed but failed.
+pub const TX_FAILED: i32 = 5;
+
+/// A confirmation prompt was declined.
+pub const DECLINED: i32 = 6;
+
+/// The command line arguments are invalid.
+pub const USAGE: i32 = 64;
+
+/// The process was interrupted with Ctrl-C.
+pub const INTERRUPTED: i32 = 130;
+
+static CODE: AtomicI32 = AtomicI32::new(OK);
+
+/// Records why the command failed. A specific code replaces the generic [`ERROR`], but
+/// otherwise the first failure is kept.
+pub fn set(code: i32) {
+    CODE.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+        match current.eq(&OK) || current.eq(&ERROR) {
+            true => Some(code),
+            false => None,
+        }
+    })
+    .ok();
+}
+
+/// Clears the recorded failure, e.g. before the shell runs the next command.
+pub fn reset() {
+    CODE.store(OK, Ordering::Relaxed);
+}
+
+/// Returns the code the process should exit with.
+pub fn code() -> i32 {
+    CODE.load(Ordering::Relaxed)
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    str::FromStr,
+};
+
+use base64::Engine;
+use chrono::DateTime;
+use ore_api::{event::MineEvent, instruction::OreInstruction};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiReturnDataEncoding, UiTransactionEncoding,
+};
+use tracing::error;
+
+use crate::{
+    args::ExportArgs,
+    error::OreError,
+    ledger,
+    price::fetch_usd_price_at,
+    program,
+    utils::{amount_u64_to_f64, amount_u64_to_string, format_timestamp, proof_pubkey},
+    Miner,
+};
+
+/// The kind of ORE transaction recorded in the history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryKind {
+    Mine,
+    Claim,
+}
+
+/// A mining reward or claim found in the transaction history of a proof.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub kind: HistoryKind,
+    pub signature: Signature,
+    pub timestamp: i64,
+    pub amount: u64,
+    pub difficulty: Option<u64>,
+    pub fee: u64,
+}
+
+impl Miner {
+    pub async fn export(&self, args: ExportArgs) -> Result<(), OreError> {
+        // Parse args
+        if !["csv", "koinly"].contains(&args.format.as_str()) {
+            return Err(OreError::UnknownFormat(args.format));
+        }
+        let authority = if let Some(address) = args.address {
+            Pubkey::from_str(&address).map_err(|_| OreError::InvalidAddress(address))?
+        } else {
+            self.signer()?.pubkey()
+        };
+
+        // Fetch history
+        let history = if args.local {
+            ledger::history(authority, args.limit)
+        } else {
+            self.fetch_history(authority, args.limit).await
+        };
+
+        // Fetch prices
+        let mut prices: HashMap<String, Option<f64>> = HashMap::new();
+        if args.usd {
+            for entry in history.iter() {
+                let date = format_date(entry.timestamp, "%d-%m-%Y");
+                if let Entry::Vacant(vacant) = prices.entry(date) {
+                    let price = fetch_usd_price_at(vacant.key()).await;
+                    vacant.insert(price);
+                }
+            }
+        }
+        let price_of = |entry: &HistoryEntry| -> Option<f64> {
+            prices
+                .get(&format_date(entry.timestamp, "%d-%m-%Y"))
+                .copied()
+                .flatten()
+        };
+
+        // Build report
+        let mut lines = vec![];
+        match args.format.as_str() {
+            "koinly" => {
+                lines.push("Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Net Worth Amount,Net Worth Currency,Label,Description,TxHash".to_string());
+                for entry in history.iter().filter(|e| e.kind.eq(&HistoryKind::Mine)) {
+                    let net_worth = price_of(entry)
+                        .map(|price| format!("{:.2}", price * amount_u64_to_f64(entry.amount)))
+                        .unwrap_or_default();
+                    lines.push(format!(
+                        "{},,,{},ORE,{},SOL,{},{},mining,ORE mining reward,{}",
+                        format_timestamp(entry.timestamp),
+                        amount_u64_to_string(entry.amount),
+                        lamports_to_sol(entry.fee),
+                        net_worth,
+                        if net_worth.is_empty() { "" } else { "USD" },
+                        entry.signature
+                    ));
+                }
+            }
+            _ => {
+                lines.push(
+                    "timestamp,date,type,amount_ore,difficulty,fee_sol,usd_price,usd_value,signature"
+                        .to_string(),
+                );
+                for entry in history.iter() {
+                    let price = price_of(entry);
+                    lines.push(format!(
+                        "{},{},{},{},{},{},{},{},{}",
+                        entry.timestamp,
+                        format_timestamp(entry.timestamp),
+                        match entry.kind {
+                            HistoryKind::Mine => "mine",
+                            HistoryKind::Claim => "claim",
+                        },
+                        amount_u64_to_string(entry.amount),
+                        entry.difficulty.map(|d| d.to_string()).unwrap_or_default(),
+                        lamports_to_sol(entry.fee),
+                        price.map(|p| p.to_string()).unwrap_or_default(),
+                        price
+                            .map(|p| format!("{:.2}", p * amount_u64_to_f64(entry.amount)))
+                            .unwrap_or_default(),
+                        entry.signature
+                    ));
+                }
+            }
+        }
+
+        // Write report
+        let report = format!("{}\n", lines.join("\n"));
+        match args.out_file {
+            Some(filepath) => match std::fs::write(&filepath, report) {
+                Ok(_) => println!("Exported {} transactions to {}", history.len(), filepath),
+                Err(err) => error!("Failed to write {}: {}", filepath, err),
+            },
+            None => print!("{}", report),
+        }
+        Ok(())
+    }
+
+    /// Scans the transaction history of a proof for mining rewards and claims, oldest first.
+    pub async fn fetch_history(&self, authority: Pubkey, limit: usize) -> Vec<HistoryEntry> {
+        // Fetch signatures
+        let proof_address = proof_pubkey(authority);
+        let mut signatures = vec![];
+        let mut before = None;
+        while signatures.len() < limit {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some((limit - signatures.len()).min(1000)),
+                commitment: Some(CommitmentConfig::confirmed()),
+            };
+            let Ok(page) = self
+                .rpc_client
+                .get_signatures_for_address_with_config(&proof_address, config)
+                .await
+            else {
+                break;
+            };
+            if page.is_empty() {
+                break;
+            }
+            for status in page.iter() {
+                if status.err.is_none() {
+                    if let Ok(sig) = Signature::from_str(&status.signature) {
+                        signatures.push(sig);
+                    }
+                }
+            }
+            before = page
+                .last()
+                .and_then(|s| Signature::from_str(&s.signature).ok());
+        }
+
+        // Parse transactions
+        let mut history = vec![];
+        for sig in signatures.iter() {
+            if let Some(entry) = fetch_history_entry(&self.rpc_client, sig, authority).await {
+                history.push(entry);
+            }
+        }
+        history.sort_by_key(|e| e.timestamp);
+        history
+    }
+}
+
+/// Parses the ORE reward or claim made by the authority in a transaction.
+pub async fn fetch_history_entry(
+    rpc_client: &RpcClient,
+    sig: &Signature,
+    authority: Pubkey,
+) -> Option<HistoryEntry> {
+    let tx = rpc_client
+        .get_transaction_with_config(
+            sig,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+        .ok()?;
+    let meta = tx.transaction.meta?;
+    let versioned_tx = tx.transaction.transaction.decode()?;
+    let keys = versioned_tx.message.static_account_keys();
+
+    // Find the ore instruction signed by the authority
+    for ix in versioned_tx.message.instructions() {
+        let program_id = keys.get(ix.program_id_index as usize)?;
+        if program_id.ne(&program::program_id()) || ix.data.is_empty() {
+            continue;
+        }
+        let signer = ix.accounts.first().and_then(|i| keys.get(*i as usize));
+        match OreInstruction::try_from(ix.data[0]).ok()? {
+            OreInstruction::Mine => {
+                let event = match &meta.return_data {
+                    OptionSerializer::Some(return_data) => {
+                        parse_mine_event(&return_data.data.0, return_data.data.1)
+                    }
+                    _ => None,
+                };
+                return Some(HistoryEntry {
+                    kind: HistoryKind::Mine,
+                    signature: *sig,
+                    timestamp: tx.block_time.unwrap_or(0),
+                    amount: event.map(|e| e.reward).unwrap_or(0),
+                    difficulty: event.map(|e| e.difficulty),
+                    fee: meta.fee,
+                });
+            }
+            OreInstruction::Claim if signer.eq(&Some(&authority)) => {
+                let amount = u64::from_le_bytes(ix.data.get(1..9)?.try_into().ok()?);
+                return Some(HistoryEntry {
+                    kind: HistoryKind::Claim,
+                    signature: *sig,
+                    timestamp: tx.block_time.unwrap_or(0),
+                    amount,
+                    difficulty: None,
+                    fee: meta.fee,
+                });
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_mine_event(data: &str, encoding: UiReturnDataEncoding) -> Option<MineEvent> {
+    let bytes = match encoding {
+        UiReturnDataEncoding::Base64 => base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .ok()?,
+    };
+    bytemuck::try_pod_read_unaligned::<MineEvent>(&bytes).ok()
+}
+
+fn format_date(timestamp: i64, fmt: &str) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|d| d.format(fmt).to_string())
+        .unwrap_or_default()
+}
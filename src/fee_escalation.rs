@@ -0,0 +1,67 @@
+use std::sync::Mutex;
+
+/// Raises the priority fee while mine transactions expire without landing, and lowers it
+/// again as they land. Disabled until [`FeeEscalation::configure`] sets a step.
+pub struct FeeEscalation {
+    state: Mutex<EscalationState>,
+}
+
+struct EscalationState {
+    step: u64,
+    rounds: u64,
+    misses: u64,
+    bump: u64,
+}
+
+static ESCALATION: FeeEscalation = FeeEscalation {
+    state: Mutex::new(EscalationState {
+        step: 0,
+        rounds: 1,
+        misses: 0,
+        bump: 0,
+    }),
+};
+
+pub fn escalation() -> &'static FeeEscalation {
+    &ESCALATION
+}
+
+impl FeeEscalation {
+    /// Adds the step to the fee after every `rounds` consecutive rounds that expire.
+    pub fn configure(&self, step: u64, rounds: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.step = step;
+        state.rounds = rounds.max(1);
+    }
+
+    /// Returns the amount added to the priority fee, in microlamports.
+    pub fn bump(&self) -> u64 {
+        self.state.lock().unwrap().bump
+    }
+
+    /// Records whether a round landed. Returns the new bump if it changed. The bump never
+    /// grows past the cap.
+    pub fn observe(&self, landed: bool, cap: Option<u64>) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        if state.step.eq(&0) {
+            return None;
+        }
+        let bump = if landed {
+            state.misses = 0;
+            state.bump.saturating_sub(state.step)
+        } else {
+            state.misses += 1;
+            if state.misses.lt(&state.rounds) {
+                return None;
+            }
+            state.misses = 0;
+            let bump = state.bump.saturating_add(state.step);
+            cap.map_or(bump, |cap| bump.min(cap))
+        };
+        if bump.eq(&state.bump) {
+            return None;
+        }
+        state.bump = bump;
+        Some(bump)
+    }
+}
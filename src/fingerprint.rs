@@ -0,0 +1,52 @@
+//! Spreads duplicate deployments of a keypair over the nonce space. Each machine starts
+//! its nonces at an offset derived from its machine ID, MAC addresses, and hostname, so
+//! two machines mining the same proof by accident hash different nonces instead of
+//! repeating each other's work.
+
+use std::sync::OnceLock;
+
+use solana_program::hash::hash;
+
+static NONCE_OFFSET: OnceLock<u64> = OnceLock::new();
+
+/// Starts nonces at this offset instead of the one derived from the machine.
+pub fn set_nonce_offset(offset: u64) {
+    NONCE_OFFSET.set(offset).ok();
+}
+
+/// Returns the offset to start nonces at, stable across restarts of this machine.
+pub fn nonce_offset() -> u64 {
+    *NONCE_OFFSET.get_or_init(|| {
+        let digest = hash(machine_id().as_bytes()).to_bytes();
+        u64::from_le_bytes(digest[..8].try_into().unwrap_or_default())
+    })
+}
+
+/// Identifies the machine, from whatever sources it has.
+fn machine_id() -> String {
+    let mut parts = vec![];
+    #[cfg(target_os = "linux")]
+    {
+        for path in ["/etc/machine-id", "/var/lib/dbus/machine-id"] {
+            if let Ok(id) = std::fs::read_to_string(path) {
+                parts.push(id.trim().to_string());
+                break;
+            }
+        }
+
+        // VMs cloned from one image share a machine ID, but not MAC addresses
+        if let Ok(entries) = std::fs::read_dir("/sys/class/net") {
+            let mut macs: Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().ne("lo"))
+                .filter_map(|entry| std::fs::read_to_string(entry.path().join("address")).ok())
+                .map(|mac| mac.trim().to_string())
+                .filter(|mac| !mac.is_empty() && mac.ne("00:00:00:00:00:00"))
+                .collect();
+            macs.sort();
+            parts.extend(macs);
+        }
+    }
+    parts.push(gethostname::gethostname().to_string_lossy().to_string());
+    parts.join("\n")
+}
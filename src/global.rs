@@ -0,0 +1,77 @@
+use colored::*;
+use ore_api::consts::EPOCH_DURATION;
+
+use crate::{
+    error::OreError,
+    output::print_json,
+    program,
+    schema::GlobalDocument,
+    utils::{amount_u64_to_string, format_timestamp, get_mining_state, get_proofs, MiningState},
+    Miner,
+};
+
+impl Miner {
+    pub async fn global(&self) -> Result<(), OreError> {
+        // Fetch the program state
+        let MiningState { config, clock, .. } = get_mining_state(&self.rpc_client).await?;
+        let treasury_balance = self
+            .rpc_client
+            .get_token_account_balance(&program::treasury_tokens_address())
+            .await
+            .map_err(|err| OreError::fetch("treasury tokens", err))?;
+        let treasury_balance = treasury_balance
+            .amount
+            .parse::<u64>()
+            .map_err(|_| OreError::InvalidAccount("treasury tokens"))?;
+        let proofs = get_proofs(&self.rpc_client).await?;
+        let total_staked = proofs.iter().map(|(_, proof)| proof.balance).sum::<u64>();
+        let total_hashes = proofs
+            .iter()
+            .map(|(_, proof)| proof.total_hashes)
+            .sum::<u64>();
+        let next_reset_at = config.last_reset_at.saturating_add(EPOCH_DURATION);
+        let reset_in = next_reset_at.saturating_sub(clock.unix_timestamp).max(0);
+
+        // Print as json
+        if self.is_json() {
+            print_json(&GlobalDocument {
+                treasury_balance,
+                total_staked,
+                top_balance: config.top_balance,
+                total_hashes,
+                miners: proofs.len(),
+                epoch_duration: EPOCH_DURATION,
+                last_reset_at: config.last_reset_at,
+                next_reset_at,
+            });
+            return Ok(());
+        }
+
+        // Print human readable
+        println!(
+            "{}: {} ORE",
+            "Treasury".bold(),
+            amount_u64_to_string(treasury_balance)
+        );
+        println!(
+            "{}: {} ORE",
+            "Total staked".bold(),
+            amount_u64_to_string(total_staked)
+        );
+        println!(
+            "{}: {} ORE",
+            "Max multiplier stake".bold(),
+            amount_u64_to_string(config.top_balance)
+        );
+        println!("{}: {}", "Total hashes".bold(), total_hashes);
+        println!("{}: {}", "Miners".bold(), proofs.len());
+        println!("{}: {} sec", "Epoch duration".bold(), EPOCH_DURATION);
+        println!(
+            "{}: {}",
+            "Last reset".bold(),
+            format_timestamp(config.last_reset_at)
+        );
+        println!("{}: {} sec", "Next reset in".bold(), reset_in);
+        Ok(())
+    }
+}
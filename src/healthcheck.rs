@@ -0,0 +1,73 @@
+//! Liveness checks for container orchestrators. The miner is healthy while rounds keep
+//! landing, or while it is paused on purpose.
+
+use std::{
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::Utc;
+use serde_json::json;
+use tracing::warn;
+
+use crate::http::Response;
+
+struct Healthcheck {
+    file: Option<PathBuf>,
+    max_age: i64,
+    last_beat_at: i64,
+}
+
+fn healthcheck() -> &'static Mutex<Healthcheck> {
+    static HEALTHCHECK: OnceLock<Mutex<Healthcheck>> = OnceLock::new();
+    HEALTHCHECK.get_or_init(|| {
+        Mutex::new(Healthcheck {
+            file: None,
+            max_age: 0,
+            last_beat_at: Utc::now().timestamp(),
+        })
+    })
+}
+
+/// Sets the file to touch on each beat, and how many seconds after the last beat the miner
+/// counts as stalled. The miner starts out healthy.
+pub fn configure(file: Option<PathBuf>, max_age: u64) {
+    let mut healthcheck = healthcheck().lock().unwrap();
+    healthcheck.file = file;
+    healthcheck.max_age = max_age as i64;
+    drop(healthcheck);
+    beat();
+}
+
+/// Records that the mining loop is making progress, and touches the health file.
+pub fn beat() {
+    let mut healthcheck = healthcheck().lock().unwrap();
+    let now = Utc::now().timestamp();
+    healthcheck.last_beat_at = now;
+    if let Some(path) = &healthcheck.file {
+        if let Err(err) = std::fs::write(path, format!("{}\n", now)) {
+            warn!("Failed to write health file {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// When the mining loop last made progress, as a unix timestamp. Also beats while paused
+/// on purpose.
+pub fn last_beat_at() -> i64 {
+    healthcheck().lock().unwrap().last_beat_at
+}
+
+/// Answers `/healthz`, with 503 once the miner has stalled.
+pub fn response() -> Response {
+    let healthcheck = healthcheck().lock().unwrap();
+    let age = Utc::now().timestamp() - healthcheck.last_beat_at;
+    let healthy = age.le(&healthcheck.max_age);
+    Response::json(
+        if healthy { 200 } else { 503 },
+        json!({
+            "status": if healthy { "ok" } else { "stalled" },
+            "last_beat_at": healthcheck.last_beat_at,
+            "age": age,
+        }),
+    )
+}
@@ -0,0 +1,59 @@
+use solana_program::native_token::lamports_to_sol;
+
+use crate::{
+    args::HistoryArgs,
+    ledger::{self, LedgerKind},
+    output::print_json,
+    schema::{HistoryDocument, TransactionDocument},
+    utils::{amount_u64_to_string, format_timestamp},
+    Miner,
+};
+
+impl Miner {
+    pub async fn history(&self, args: HistoryArgs) {
+        let entries = ledger::load();
+        let recent = &entries[entries.len().saturating_sub(args.limit)..];
+        if self.is_json() {
+            print_json(&HistoryDocument {
+                transactions: recent
+                    .iter()
+                    .map(|entry| TransactionDocument {
+                        kind: entry.kind.as_str().to_string(),
+                        timestamp: entry.timestamp,
+                        authority: entry.authority.clone(),
+                        signature: entry.signature.clone(),
+                        landed: entry.landed,
+                        amount: entry.amount,
+                        difficulty: entry.difficulty,
+                        fee: entry.fee,
+                    })
+                    .collect(),
+            });
+            return;
+        }
+        if recent.is_empty() {
+            println!("No transactions have been recorded yet");
+            return;
+        }
+        println!(
+            "{:<23} {:<6} {:<8} {:>10} {:>14} {:>12}  Signature",
+            "Time", "Kind", "Status", "Difficulty", "Amount (ORE)", "Fee (SOL)"
+        );
+        for entry in recent.iter().rev() {
+            println!(
+                "{:<23} {:<6} {:<8} {:>10} {:>14} {:>12}  {}",
+                format_timestamp(entry.timestamp),
+                match entry.kind {
+                    LedgerKind::Mine => "mine",
+                    LedgerKind::Claim => "claim",
+                    LedgerKind::Swap => "swap",
+                },
+                if entry.landed { "landed" } else { "failed" },
+                entry.difficulty.map(|d| d.to_string()).unwrap_or_default(),
+                amount_u64_to_string(entry.amount),
+                lamports_to_sol(entry.fee),
+                entry.signature.clone().unwrap_or_default()
+            );
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tracing::{debug, error, info};
+
+/// The largest request body accepted by the embedded servers.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// How long a client may take to send its request or read the response before it is
+/// disconnected.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The most connections each embedded server handles at once. Others are closed at once.
+const MAX_CONNECTIONS: usize = 32;
+
+/// A parsed HTTP request.
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// An HTTP response.
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn json(status: u16, value: serde_json::Value) -> Self {
+        Self::new(status, "application/json", value.to_string())
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(404, "text/plain", "Not found")
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Serves HTTP requests on a background thread, answering each with the handler.
+pub fn serve<F>(name: &str, addr: &str, handler: F)
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("Failed to serve {} on {}: {}", name, addr, err);
+            return;
+        }
+    };
+    info!("Serving {} on http://{}", name, addr);
+    let handler = Arc::new(handler);
+    let connections = Arc::new(AtomicUsize::new(0));
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if connections
+                .fetch_add(1, Ordering::SeqCst)
+                .ge(&MAX_CONNECTIONS)
+            {
+                connections.fetch_sub(1, Ordering::SeqCst);
+                debug!("Too many HTTP connections, closing one");
+                continue;
+            }
+            let handler = handler.clone();
+            let connections = connections.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = handle(stream, handler.as_ref()) {
+                    debug!("Failed to handle HTTP request: {}", err);
+                }
+                connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+}
+
+fn handle<F>(stream: TcpStream, handler: &F) -> std::io::Result<()>
+where
+    F: Fn(&Request) -> Response,
+{
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    // Parse request line and headers
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)?.eq(&0) || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    // Read body
+    let length = headers
+        .get("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0)
+        .min(MAX_BODY_SIZE);
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body)?;
+
+    let response = handler(&Request {
+        method,
+        path,
+        headers,
+        body,
+    });
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)
+}
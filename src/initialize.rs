@@ -1,25 +1,31 @@
-use ore_api::consts::TREASURY_ADDRESS;
 use solana_sdk::{signature::Signer, transaction::Transaction};
 
-use crate::Miner;
+use crate::{error::OreError, program, Miner};
 
 impl Miner {
-    pub async fn initialize(&self) {
+    pub async fn initialize(&self) -> Result<(), OreError> {
         // Return early if program is already initialized
-        if self.rpc_client.get_account(&TREASURY_ADDRESS).await.is_ok() {
-            return;
+        if self
+            .rpc_client
+            .get_account(&program::treasury_address())
+            .await
+            .is_ok()
+        {
+            return Ok(());
         }
 
         // Submit initialize tx
-        let blockhash = self.rpc_client.get_latest_blockhash().await.unwrap();
-        let ix = ore_api::instruction::initialize(self.signer().pubkey());
+        let signer = self.signer()?;
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let ix = program::retarget(ore_api::instruction::initialize(signer.pubkey()));
         let tx = Transaction::new_signed_with_payer(
             &[ix],
-            Some(&self.signer().pubkey()),
-            &[&self.signer()],
+            Some(&signer.pubkey()),
+            &[signer.as_ref() as &dyn Signer],
             blockhash,
         );
         let res = self.rpc_client.send_and_confirm_transaction(&tx).await;
         println!("{:?}", res);
+        Ok(())
     }
 }
@@ -0,0 +1,63 @@
+//! Keeps two miners from racing the same proof. Each `mine` holds an advisory lock on a
+//! lock file named after its proof until it exits. The OS releases the lock when the
+//! process exits, even after a crash, so a stale file never blocks the next start.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use fs2::FileExt;
+use solana_program::pubkey::Pubkey;
+
+use crate::{error::OreError, utils::ore_dir};
+
+/// An advisory lock on the proof of a wallet, released when dropped.
+pub struct InstanceLock {
+    file: File,
+}
+
+impl InstanceLock {
+    /// Locks the proof, unless another process holds the lock.
+    pub fn acquire(proof: Pubkey) -> Result<Self, OreError> {
+        let path = lock_file(proof);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|err| {
+                OreError::Locked(format!("Failed to open {}: {}", path.display(), err))
+            })?;
+        if file.try_lock_exclusive().is_err() {
+            let owner = std::fs::read_to_string(&path).unwrap_or_default();
+            return Err(OreError::Locked(format!(
+                "Another ore process{} is already mining proof {}. Pass --force to mine anyway.",
+                match owner.trim() {
+                    "" => String::new(),
+                    pid => format!(" (pid {})", pid),
+                },
+                proof
+            )));
+        }
+
+        // Record the owner, for the error above
+        file.set_len(0).ok();
+        writeln!(file, "{}", std::process::id()).ok();
+        Ok(Self { file })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        self.file.unlock().ok();
+    }
+}
+
+fn lock_file(proof: Pubkey) -> PathBuf {
+    let dir = ore_dir().join("locks");
+    std::fs::create_dir_all(&dir).ok();
+    dir.join(format!("{}.lock", proof))
+}
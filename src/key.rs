@@ -0,0 +1,130 @@
+use std::io::Write;
+
+use colored::*;
+use solana_sdk::signature::{read_keypair_file, Signer};
+use tracing::error;
+
+use crate::{
+    args::{KeyArgs, KeyCommand, KeyEncryptArgs, KeyRemoveArgs, KeyStoreArgs},
+    keypair::{self, PASSPHRASE_VAR},
+    utils::assume_yes,
+    Miner,
+};
+
+impl Miner {
+    pub async fn key(&self, args: KeyArgs) {
+        match args.command {
+            KeyCommand::Encrypt(args) => self.encrypt_key(args),
+            KeyCommand::Store(args) => self.store_key(args),
+            KeyCommand::Remove(args) => self.remove_key(args),
+        }
+    }
+
+    fn store_key(&self, args: KeyStoreArgs) {
+        let signer = match keypair::read_keypair(&args.source) {
+            Ok(signer) => signer,
+            Err(err) => {
+                error!("Failed to read {}: {}", args.source, err);
+                return;
+            }
+        };
+        match keypair::store_in_keyring(&args.name, &signer) {
+            Ok(_) => println!(
+                "{} Stored {} in the keyring. Use it with --keypair keyring:{}",
+                "OK".bold().green(),
+                signer.pubkey(),
+                args.name
+            ),
+            Err(err) => error!("Failed to store keypair: {}", err),
+        }
+    }
+
+    fn remove_key(&self, args: KeyRemoveArgs) {
+        match keypair::remove_from_keyring(&args.name) {
+            Ok(_) => println!(
+                "{} Removed {} from the keyring",
+                "OK".bold().green(),
+                args.name
+            ),
+            Err(err) => error!("Failed to remove keypair: {}", err),
+        }
+    }
+
+    fn encrypt_key(&self, args: KeyEncryptArgs) {
+        // Read keypair
+        if keypair::is_encrypted(&args.path) {
+            error!("{} is already encrypted", args.path);
+            return;
+        }
+        let signer = match read_keypair_file(&args.path) {
+            Ok(signer) => signer,
+            Err(err) => {
+                error!("Failed to read {}: {}", args.path, err);
+                return;
+            }
+        };
+
+        // Ask for the passphrase twice, unless it is set in the environment
+        let passphrase = match std::env::var(PASSPHRASE_VAR) {
+            Ok(passphrase) => passphrase,
+            Err(_) if assume_yes() => {
+                error!("{} must be set when --yes is given", PASSPHRASE_VAR);
+                return;
+            }
+            Err(_) => {
+                let passphrase = rpassword::prompt_password("New passphrase: ").unwrap_or_default();
+                let confirmation =
+                    rpassword::prompt_password("Confirm passphrase: ").unwrap_or_default();
+                if passphrase.ne(&confirmation) {
+                    error!("Passphrases do not match");
+                    return;
+                }
+                passphrase
+            }
+        };
+        if passphrase.is_empty() {
+            error!("Passphrase must not be empty");
+            return;
+        }
+
+        // Write encrypted keypair
+        let contents = match keypair::encrypt_keypair(&signer, &passphrase) {
+            Ok(contents) => contents,
+            Err(err) => {
+                error!("{}", err);
+                return;
+            }
+        };
+        let output = args.out_file.unwrap_or(args.path.clone());
+        let tmp = format!("{}.tmp", output);
+        if let Err(err) =
+            write_private(&tmp, &contents).and_then(|_| std::fs::rename(&tmp, &output))
+        {
+            std::fs::remove_file(&tmp).ok();
+            error!("Failed to write {}: {}", output, err);
+            return;
+        }
+        println!(
+            "{} Encrypted {} to {}",
+            "OK".bold().green(),
+            signer.pubkey(),
+            output
+        );
+        if output.ne(&args.path) {
+            println!(
+                "{} The plaintext keypair is still at {}",
+                "WARNING".bold().yellow(),
+                args.path
+            );
+        }
+    }
+}
+
+/// Writes a file readable only by the current user.
+fn write_private(path: &str, contents: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents.as_bytes())
+}
@@ -0,0 +1,148 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use colored::*;
+use solana_sdk::signature::{write_keypair_file, Keypair, Signer};
+
+use crate::{
+    args::KeygenArgs,
+    error::OreError,
+    output::{finish_progress, new_progress_bar, print_json},
+    schema::KeygenDocument,
+    Miner,
+};
+
+/// The characters that can appear in a base58 address.
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+impl Miner {
+    pub async fn keygen(&self, args: KeygenArgs) -> Result<(), OreError> {
+        // Check the destination
+        if Path::new(&args.outfile).exists() && !args.force {
+            return Err(OreError::Keygen(format!(
+                "{} already exists. Pass --force to overwrite it.",
+                args.outfile
+            )));
+        }
+
+        // Generate keypair
+        let keypair = match &args.prefix {
+            Some(prefix) => {
+                if let Some(c) = prefix.chars().find(|c| !BASE58_ALPHABET.contains(*c)) {
+                    return Err(OreError::Keygen(format!(
+                        "Prefix contains {:?}, which never appears in an address",
+                        c
+                    )));
+                }
+                let threads = args.threads.unwrap_or(num_cpus::get() as u64);
+                let prefix = prefix.clone();
+                let ignore_case = args.ignore_case;
+                match tokio::task::spawn_blocking(move || grind(&prefix, ignore_case, threads))
+                    .await
+                {
+                    Ok(keypair) => keypair,
+                    Err(err) => {
+                        return Err(OreError::Keygen(format!(
+                            "Failed to grind keypair: {}",
+                            err
+                        )))
+                    }
+                }
+            }
+            None => Keypair::new(),
+        };
+
+        // Write keypair
+        if let Some(dir) = Path::new(&args.outfile).parent() {
+            std::fs::create_dir_all(dir).ok();
+        }
+        write_keypair_file(&keypair, &args.outfile).map_err(|err| {
+            OreError::Keygen(format!("Failed to write {}: {}", args.outfile, err))
+        })?;
+        if self.is_json() {
+            print_json(&KeygenDocument {
+                address: keypair.pubkey().to_string(),
+                outfile: args.outfile.clone(),
+            });
+        } else {
+            println!(
+                "{} Wrote {} to {}",
+                "OK".bold().green(),
+                keypair.pubkey(),
+                args.outfile
+            );
+        }
+
+        // Open proof
+        if args.open {
+            self.with_keypair(args.outfile).open().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates keypairs on every thread until one has an address starting with the prefix.
+fn grind(prefix: &str, ignore_case: bool, threads: u64) -> Keypair {
+    let prefix = match ignore_case {
+        true => prefix.to_lowercase(),
+        false => prefix.to_string(),
+    };
+    let progress_bar = new_progress_bar();
+    progress_bar.set_message(format!("Grinding for {}...", prefix));
+    let found = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicU64::new(0));
+    let result = Arc::new(Mutex::new(None));
+    let handles: Vec<_> = (0..threads.max(1))
+        .map(|_| {
+            let (prefix, found, attempts, result) = (
+                prefix.clone(),
+                found.clone(),
+                attempts.clone(),
+                result.clone(),
+            );
+            std::thread::spawn(move || {
+                while !found.load(Ordering::Relaxed) {
+                    let keypair = Keypair::new();
+                    let address = keypair.pubkey().to_string();
+                    let address = match ignore_case {
+                        true => address.to_lowercase(),
+                        false => address,
+                    };
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    if address.starts_with(&prefix) && !found.swap(true, Ordering::Relaxed) {
+                        *result.lock().unwrap() = Some(keypair);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    // Report progress until a thread finds a match
+    while !found.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_millis(500));
+        progress_bar.set_message(format!(
+            "Grinding for {}... ({} attempts)",
+            prefix,
+            attempts.load(Ordering::Relaxed)
+        ));
+    }
+    for handle in handles {
+        handle.join().ok();
+    }
+    let keypair = result.lock().unwrap().take().unwrap();
+    finish_progress(
+        &progress_bar,
+        format!(
+            "Found {} after {} attempts",
+            keypair.pubkey(),
+            attempts.load(Ordering::Relaxed)
+        ),
+    );
+    keypair
+}
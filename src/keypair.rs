@@ -0,0 +1,274 @@
+use std::{
+    io::Read,
+    sync::{Mutex, OnceLock},
+};
+
+use aes_gcm_siv::{
+    aead::{Aead, NewAead},
+    Aes256GcmSiv, Key, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    signature::{
+        generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed,
+        keypair_from_seed_and_derivation_path, read_keypair_file, Keypair,
+    },
+};
+
+use crate::utils::assume_yes;
+
+/// The environment variable holding the passphrase of encrypted keypair files.
+pub const PASSPHRASE_VAR: &str = "ORE_KEY_PASSPHRASE";
+
+/// The keypair read from stdin, kept since stdin can only be read once.
+static STDIN_KEYPAIR: OnceLock<Result<Vec<u8>, String>> = OnceLock::new();
+
+/// The service name keypairs are stored under in the OS keyring.
+const KEYRING_SERVICE: &str = "ore-cli";
+
+/// The derivation path of keypairs entered as a seed phrase.
+static DERIVATION_PATH: OnceLock<DerivationPath> = OnceLock::new();
+
+/// Keypairs decrypted from files, read from the OS keyring, or entered as a seed phrase,
+/// kept so they are only asked for once.
+static LOADED_KEYPAIRS: Mutex<Vec<(String, Vec<u8>)>> = Mutex::new(vec![]);
+
+/// A keypair file encrypted with a key derived from a passphrase by Argon2id.
+#[derive(Serialize, Deserialize)]
+struct EncryptedKeypair {
+    version: u8,
+    kdf: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Reads a keypair from a source given on the command line:
+///
+/// - `env:VAR` reads a base58 string or JSON byte array from an environment variable.
+/// - `-` reads a base58 string or JSON byte array from stdin.
+/// - `keyring:NAME` reads a keypair stored with `ore key store` from the OS keyring.
+/// - `prompt://` asks for a seed phrase, derived with the `--derivation-path` if given.
+/// - Anything else is a path to a keypair file, which may be encrypted.
+pub fn read_keypair(source: &str) -> Result<Keypair, String> {
+    if let Some(var) = source.strip_prefix("env:") {
+        let value = std::env::var(var).map_err(|_| format!("{} is not set", var))?;
+        return parse_keypair(&value);
+    }
+    if source.eq("-") {
+        let bytes = STDIN_KEYPAIR.get_or_init(|| {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|err| err.to_string())?;
+            parse_keypair(&input).map(|keypair| keypair.to_bytes().to_vec())
+        });
+        return bytes
+            .clone()
+            .and_then(|bytes| Keypair::from_bytes(&bytes).map_err(|err| err.to_string()));
+    }
+    if let Some(name) = source.strip_prefix("keyring:") {
+        return cached(source, || {
+            let value = keyring_entry(name)?
+                .get_password()
+                .map_err(|err| err.to_string())?;
+            parse_keypair(&value).map(|keypair| keypair.to_bytes().to_vec())
+        });
+    }
+    if source.starts_with("prompt:") {
+        return cached(source, prompt_seed_phrase);
+    }
+    if is_encrypted(source) {
+        return cached(source, || decrypt_keypair_file(source));
+    }
+    read_keypair_file(source).map_err(|err| err.to_string())
+}
+
+/// Describes a keypair source for error messages, without echoing a key given in place of
+/// a path.
+pub fn describe_source(source: &str) -> String {
+    if let Some(var) = source.strip_prefix("env:") {
+        format!("environment variable {}", var)
+    } else if source.eq("-") {
+        "stdin".into()
+    } else if parse_keypair(source).is_ok() {
+        "<redacted>".into()
+    } else {
+        source.into()
+    }
+}
+
+/// Sets the derivation path of keypairs entered as a seed phrase or held on a Ledger, given as a full path
+/// like `m/44'/501'/0'/0'` or just the account and change like `0/0`.
+pub fn set_derivation_path(path: &str) -> Result<(), String> {
+    let path = if path.starts_with('m') {
+        DerivationPath::from_absolute_path_str(path)
+    } else {
+        DerivationPath::from_key_str(path)
+    }
+    .map_err(|err| err.to_string())?;
+    DERIVATION_PATH.set(path).ok();
+    Ok(())
+}
+
+/// Returns the derivation path set with `--derivation-path`, if any.
+pub fn derivation_path() -> Option<DerivationPath> {
+    DERIVATION_PATH.get().cloned()
+}
+
+/// Asks for a BIP39 seed phrase and optional passphrase, returning the keypair bytes.
+fn prompt_seed_phrase() -> Result<Vec<u8>, String> {
+    if assume_yes() {
+        return Err("Cannot ask for a seed phrase when --yes is given".to_string());
+    }
+    let phrase = rpassword::prompt_password("Seed phrase: ").map_err(|err| err.to_string())?;
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    bip39::Mnemonic::validate(&phrase, bip39::Language::English)
+        .map_err(|err| format!("Invalid seed phrase: {}", err))?;
+    let passphrase = rpassword::prompt_password(
+        "If this seed phrase has a passphrase, enter it now. Otherwise, press ENTER: ",
+    )
+    .map_err(|err| err.to_string())?;
+    let seed = generate_seed_from_seed_phrase_and_passphrase(&phrase, &passphrase);
+    let keypair = match DERIVATION_PATH.get() {
+        Some(path) => keypair_from_seed_and_derivation_path(&seed, Some(path.clone())),
+        None => keypair_from_seed(&seed),
+    }
+    .map_err(|err| err.to_string())?;
+    Ok(keypair.to_bytes().to_vec())
+}
+
+/// Returns whether the file at the path is an encrypted keypair.
+pub fn is_encrypted(path: &str) -> bool {
+    std::fs::read_to_string(path)
+        .map(|contents| contents.trim_start().starts_with('{'))
+        .unwrap_or(false)
+}
+
+/// Returns the passphrase from `ORE_KEY_PASSPHRASE`, or asks for it on the terminal.
+pub fn passphrase(prompt: &str) -> Result<String, String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) {
+        return Ok(passphrase);
+    }
+    if assume_yes() {
+        return Err(format!(
+            "{} must be set when --yes is given",
+            PASSPHRASE_VAR
+        ));
+    }
+    rpassword::prompt_password(prompt).map_err(|err| err.to_string())
+}
+
+/// Encrypts a keypair with the passphrase, returning the contents of the encrypted file.
+pub fn encrypt_keypair(keypair: &Keypair, passphrase: &str) -> Result<String, String> {
+    let params = Params::default();
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let cipher = cipher(passphrase, &salt, params.clone())?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), keypair.to_bytes().as_ref())
+        .map_err(|_| "Failed to encrypt keypair".to_string())?;
+    let file = EncryptedKeypair {
+        version: 1,
+        kdf: "argon2id".to_string(),
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+        salt: BASE64_STANDARD.encode(salt),
+        nonce: BASE64_STANDARD.encode(nonce),
+        ciphertext: BASE64_STANDARD.encode(ciphertext),
+    };
+    serde_json::to_string_pretty(&file).map_err(|err| err.to_string())
+}
+
+/// Stores a keypair in the OS keyring under the name.
+pub fn store_in_keyring(name: &str, keypair: &Keypair) -> Result<(), String> {
+    keyring_entry(name)?
+        .set_password(&keypair.to_base58_string())
+        .map_err(|err| err.to_string())
+}
+
+/// Removes a keypair from the OS keyring.
+pub fn remove_from_keyring(name: &str) -> Result<(), String> {
+    keyring_entry(name)?
+        .delete_password()
+        .map_err(|err| err.to_string())
+}
+
+fn keyring_entry(name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, name).map_err(|err| err.to_string())
+}
+
+/// Returns the keypair loaded from the source, loading it the first time.
+fn cached(source: &str, load: impl FnOnce() -> Result<Vec<u8>, String>) -> Result<Keypair, String> {
+    let mut loaded = LOADED_KEYPAIRS.lock().unwrap();
+    let bytes = match loaded.iter().find(|(s, _)| s.eq(source)) {
+        Some((_, bytes)) => bytes.clone(),
+        None => {
+            let bytes = load()?;
+            loaded.push((source.to_string(), bytes.clone()));
+            bytes
+        }
+    };
+    Keypair::from_bytes(&bytes).map_err(|err| err.to_string())
+}
+
+/// Decrypts an encrypted keypair file, returning the keypair bytes.
+fn decrypt_keypair_file(path: &str) -> Result<Vec<u8>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let file: EncryptedKeypair = serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+    if file.version.ne(&1) || file.kdf.ne("argon2id") {
+        return Err(format!(
+            "Unsupported encryption {} v{}",
+            file.kdf, file.version
+        ));
+    }
+    let params =
+        Params::new(file.m_cost, file.t_cost, file.p_cost, None).map_err(|err| err.to_string())?;
+    let decode = |value: &str| BASE64_STANDARD.decode(value).map_err(|err| err.to_string());
+    let (salt, nonce, ciphertext) = (
+        decode(&file.salt)?,
+        decode(&file.nonce)?,
+        decode(&file.ciphertext)?,
+    );
+    if nonce.len().ne(&12) {
+        return Err("Invalid nonce".to_string());
+    }
+    let passphrase = passphrase(&format!("Passphrase for {}: ", path))?;
+    let bytes = cipher(&passphrase, &salt, params)?
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| "Wrong passphrase".to_string())?;
+    Keypair::from_bytes(&bytes).map_err(|err| err.to_string())?;
+    Ok(bytes)
+}
+
+/// Derives the cipher key from the passphrase.
+fn cipher(passphrase: &str, salt: &[u8], params: Params) -> Result<Aes256GcmSiv, String> {
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| err.to_string())?;
+    Ok(Aes256GcmSiv::new(Key::from_slice(&key)))
+}
+
+/// Parses a keypair from a base58 string or a JSON byte array, the format of keypair files.
+fn parse_keypair(value: &str) -> Result<Keypair, String> {
+    let value = value.trim();
+    let bytes = if value.starts_with('[') {
+        serde_json::from_str::<Vec<u8>>(value).map_err(|err| err.to_string())?
+    } else {
+        bs58::decode(value)
+            .into_vec()
+            .map_err(|err| err.to_string())?
+    };
+    Keypair::from_bytes(&bytes).map_err(|err| err.to_string())
+}
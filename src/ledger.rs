@@ -0,0 +1,439 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use chrono::Utc;
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_sdk::signature::Signature;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::{
+    export::{fetch_history_entry, HistoryEntry, HistoryKind},
+    utils::{amount_u64_to_string, format_timestamp, ore_dir},
+    Miner,
+};
+
+const DATABASE_FILE: &str = "ore.db";
+
+/// The JSON lines ledger used before the SQLite store. It is imported once, then renamed.
+const LEGACY_LEDGER_FILE: &str = "ledger.jsonl";
+
+/// The version of the database, stored as its `user_version`. Databases at version 0 still
+/// need the legacy ledger imported.
+const SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS rounds (
+    id INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    authority TEXT NOT NULL,
+    challenge TEXT NOT NULL,
+    stake INTEGER NOT NULL,
+    difficulty INTEGER NOT NULL,
+    hashrate INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transactions (
+    id INTEGER PRIMARY KEY,
+    kind TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    authority TEXT NOT NULL,
+    signature TEXT,
+    landed INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    difficulty INTEGER,
+    fee INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transactions_timestamp ON transactions (timestamp);
+";
+
+static DATABASE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// How long to wait before the first on-chain lookup of a recorded transaction. Each retry
+/// waits twice as long as the last.
+const SETTLE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How many times to look up a recorded transaction before keeping it as it was sent.
+const SETTLE_ATTEMPTS: usize = 6;
+
+/// How long a command waits on exit for the lookups of the transactions it recorded.
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The kind of event recorded in the local ledger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerKind {
+    Mine,
+    Claim,
+    Swap,
+}
+
+impl LedgerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerKind::Mine => "mine",
+            LedgerKind::Claim => "claim",
+            LedgerKind::Swap => "swap",
+        }
+    }
+}
+
+/// A single submission or claim recorded during operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LedgerEntry {
+    pub kind: LedgerKind,
+    pub timestamp: i64,
+    pub authority: String,
+    pub signature: Option<String>,
+    pub landed: bool,
+    pub amount: u64,
+    pub difficulty: Option<u64>,
+    pub fee: u64,
+}
+
+/// The best hash found in a single mining round, whether or not it landed.
+#[derive(Clone, Debug)]
+pub struct RoundEntry {
+    pub timestamp: i64,
+    pub authority: String,
+    pub challenge: String,
+    pub stake: u64,
+    pub difficulty: u32,
+    pub hashrate: u64,
+}
+
+/// Sets the path of the local database. Defaults to ~/.config/ore/ore.db.
+pub fn set_path(path: PathBuf) {
+    DATABASE_PATH.set(path).ok();
+}
+
+/// Opens the local database, creating its tables if needed.
+fn open() -> rusqlite::Result<Connection> {
+    let path = DATABASE_PATH
+        .get()
+        .cloned()
+        .unwrap_or_else(|| ore_dir().join(DATABASE_FILE));
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.execute_batch(SCHEMA)?;
+    migrate(&conn)?;
+    Ok(conn)
+}
+
+/// Upgrades a database created by an older version, tracked by its `user_version`.
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version = |conn: &Connection| -> rusqlite::Result<u32> {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+    };
+    if version(conn)?.ge(&SCHEMA_VERSION) {
+        return Ok(());
+    }
+
+    // Check again under a write lock, in case another process migrated it meanwhile
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    if version(&tx)?.lt(&SCHEMA_VERSION) {
+        import_legacy_ledger(&tx)?;
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    tx.commit()
+}
+
+/// Moves entries from the old JSON lines ledger into the database.
+fn import_legacy_ledger(conn: &Connection) -> rusqlite::Result<()> {
+    let path = ore_dir().join(LEGACY_LEDGER_FILE);
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    for entry in contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<LedgerEntry>(line).ok())
+    {
+        insert(conn, &entry)?;
+    }
+    std::fs::rename(&path, path.with_extension("jsonl.imported")).ok();
+    Ok(())
+}
+
+fn insert(conn: &Connection, entry: &LedgerEntry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO transactions (kind, timestamp, authority, signature, landed, amount, difficulty, fee)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            entry.kind.as_str(),
+            entry.timestamp,
+            entry.authority,
+            entry.signature,
+            entry.landed,
+            entry.amount as i64,
+            entry.difficulty.map(|d| d as i64),
+            entry.fee as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Appends an entry to the local ledger.
+pub fn record(entry: &LedgerEntry) {
+    if let Err(err) = open().and_then(|conn| insert(&conn, entry)) {
+        warn!(
+            "Failed to record transaction in the local database: {}",
+            err
+        );
+    }
+}
+
+/// Updates the reward, fee and time of a recorded transaction, found by its signature.
+fn update(entry: &LedgerEntry) {
+    let result = open().and_then(|conn| {
+        conn.execute(
+            "UPDATE transactions SET timestamp = ?1, amount = ?2, difficulty = ?3, fee = ?4
+             WHERE signature = ?5",
+            params![
+                entry.timestamp,
+                entry.amount as i64,
+                entry.difficulty.map(|d| d as i64),
+                entry.fee as i64,
+                entry.signature,
+            ],
+        )
+    });
+    if let Err(err) = result {
+        warn!(
+            "Failed to update transaction in the local database: {}",
+            err
+        );
+    }
+}
+
+fn pending() -> &'static Mutex<Vec<JoinHandle<()>>> {
+    static PENDING: OnceLock<Mutex<Vec<JoinHandle<()>>>> = OnceLock::new();
+    PENDING.get_or_init(Default::default)
+}
+
+/// Waits for the rewards and fees of recorded transactions to be looked up, so they aren't
+/// lost when the command exits. Gives up after [`SETTLE_TIMEOUT`].
+pub async fn settle_pending() {
+    let handles = std::mem::take(&mut *pending().lock().unwrap());
+    if handles.is_empty() {
+        return;
+    }
+    tokio::time::timeout(SETTLE_TIMEOUT, futures::future::join_all(handles))
+        .await
+        .ok();
+}
+
+/// Appends a mining round to the local ledger.
+pub fn record_round(round: &RoundEntry) {
+    let result = open().and_then(|conn| {
+        conn.execute(
+            "INSERT INTO rounds (timestamp, authority, challenge, stake, difficulty, hashrate)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                round.timestamp,
+                round.authority,
+                round.challenge,
+                round.stake as i64,
+                round.difficulty,
+                round.hashrate as i64,
+            ],
+        )
+    });
+    if let Err(err) = result {
+        warn!("Failed to record round in the local database: {}", err);
+    }
+}
+
+/// Loads every entry in the local ledger, oldest first.
+pub fn load() -> Vec<LedgerEntry> {
+    let result = open().and_then(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT kind, timestamp, authority, signature, landed, amount, difficulty, fee
+             FROM transactions ORDER BY timestamp, id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(LedgerEntry {
+                kind: match row.get::<_, String>(0)?.as_str() {
+                    "claim" => LedgerKind::Claim,
+                    "swap" => LedgerKind::Swap,
+                    _ => LedgerKind::Mine,
+                },
+                timestamp: row.get(1)?,
+                authority: row.get(2)?,
+                signature: row.get(3)?,
+                landed: row.get(4)?,
+                amount: row.get::<_, i64>(5)? as u64,
+                difficulty: row.get::<_, Option<i64>>(6)?.map(|d| d as u64),
+                fee: row.get::<_, i64>(7)? as u64,
+            })
+        })?;
+        rows.collect()
+    });
+    result.unwrap_or_else(|err| {
+        warn!("Failed to read the local database: {}", err);
+        vec![]
+    })
+}
+
+/// Appends a submission to a CSV file, writing the header first if the file is new.
+pub fn append_csv(path: &Path, entry: &LedgerEntry) {
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| {
+            if file.metadata()?.len().eq(&0) {
+                writeln!(
+                    file,
+                    "timestamp,difficulty,fee_sol,signature,landed,reward_ore,authority"
+                )?;
+            }
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{}",
+                format_timestamp(entry.timestamp),
+                entry.difficulty.map(|d| d.to_string()).unwrap_or_default(),
+                lamports_to_sol(entry.fee),
+                entry.signature.clone().unwrap_or_default(),
+                entry.landed,
+                amount_u64_to_string(entry.amount),
+                entry.authority
+            )
+        });
+    if let Err(err) = result {
+        warn!("Failed to write to {:?}: {}", path, err);
+    }
+}
+
+/// Summarizes the mining rounds in the local ledger as (rounds, average hashrate), of
+/// every authority or only the given one.
+pub fn round_summary(authority: Option<&str>) -> (u64, u64) {
+    open()
+        .and_then(|conn| {
+            conn.query_row(
+                "SELECT COUNT(*), COALESCE(AVG(hashrate), 0) FROM rounds
+                 WHERE ?1 IS NULL OR authority = ?1",
+                params![authority],
+                |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, f64>(1)? as u64)),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Counts the mining rounds in the local ledger by the difficulty of their best hash, of
+/// every authority or only the given one.
+pub fn difficulty_histogram(authority: Option<&str>) -> Vec<(u32, u64)> {
+    let result = open().and_then(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT difficulty, COUNT(*) FROM rounds WHERE ?1 IS NULL OR authority = ?1
+             GROUP BY difficulty ORDER BY difficulty",
+        )?;
+        let rows = stmt.query_map(params![authority], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        rows.collect()
+    });
+    result.unwrap_or_default()
+}
+
+/// Returns the landed rewards and claims of an authority in the local ledger, oldest first.
+pub fn history(authority: Pubkey, limit: usize) -> Vec<HistoryEntry> {
+    let entries: Vec<HistoryEntry> = load()
+        .into_iter()
+        .filter(|entry| entry.landed && entry.authority.eq(&authority.to_string()))
+        .filter_map(|entry| {
+            Some(HistoryEntry {
+                kind: match entry.kind {
+                    LedgerKind::Mine => HistoryKind::Mine,
+                    LedgerKind::Claim => HistoryKind::Claim,
+                    LedgerKind::Swap => return None,
+                },
+                signature: entry.signature?.parse().ok()?,
+                timestamp: entry.timestamp,
+                amount: entry.amount,
+                difficulty: entry.difficulty,
+                fee: entry.fee,
+            })
+        })
+        .collect();
+    entries[entries.len().saturating_sub(limit)..].to_vec()
+}
+
+impl Miner {
+    /// Records a landed or failed transaction. Its reward and fee are looked up on-chain
+    /// in the background, and the stored entry is updated once they're found.
+    pub fn record_transaction(
+        &self,
+        kind: LedgerKind,
+        authority: Pubkey,
+        sig: Option<Signature>,
+        amount: u64,
+        difficulty: Option<u64>,
+    ) -> LedgerEntry {
+        self.record_transaction_then(kind, authority, sig, amount, difficulty, |_| {})
+    }
+
+    /// Like [`Miner::record_transaction`], and calls `on_settled` with the entry once its
+    /// reward and fee are known, or once the lookup gives up.
+    pub fn record_transaction_then(
+        &self,
+        kind: LedgerKind,
+        authority: Pubkey,
+        sig: Option<Signature>,
+        amount: u64,
+        difficulty: Option<u64>,
+        on_settled: impl FnOnce(&LedgerEntry) + Send + 'static,
+    ) -> LedgerEntry {
+        let entry = LedgerEntry {
+            kind,
+            timestamp: Utc::now().timestamp(),
+            authority: authority.to_string(),
+            signature: sig.map(|sig| sig.to_string()),
+            landed: sig.is_some(),
+            amount,
+            difficulty,
+            fee: 0,
+        };
+        record(&entry);
+
+        // Swaps aren't parsed from the transaction history, so keep what was sent
+        let expected_kind = match kind {
+            LedgerKind::Mine => Some(HistoryKind::Mine),
+            LedgerKind::Claim => Some(HistoryKind::Claim),
+            LedgerKind::Swap => None,
+        };
+        let (Some(sig), Some(expected_kind)) = (sig, expected_kind) else {
+            on_settled(&entry);
+            return entry;
+        };
+        let rpc_client = self.rpc_client.clone();
+        let mut settled = entry.clone();
+        let handle = tokio::spawn(async move {
+            // The transaction may take a few seconds to be served after it's confirmed
+            let mut delay = SETTLE_RETRY_DELAY;
+            for _ in 0..SETTLE_ATTEMPTS {
+                tokio::time::sleep(delay).await;
+                if let Some(history) = fetch_history_entry(&rpc_client, &sig, authority).await {
+                    if history.kind.eq(&expected_kind) {
+                        settled.timestamp = history.timestamp;
+                        settled.amount = history.amount;
+                        settled.difficulty = history.difficulty.or(settled.difficulty);
+                        settled.fee = history.fee;
+                        update(&settled);
+                    }
+                    break;
+                }
+                delay *= 2;
+            }
+            on_settled(&settled);
+        });
+        let mut pending = pending().lock().unwrap();
+        pending.retain(|handle| !handle.is_finished());
+        pending.push(handle);
+        entry
+    }
+}
@@ -0,0 +1,184 @@
+//! The mining logic behind the `ore` command line interface, for bots and GUIs that embed
+//! it instead of shelling out to the binary.
+
+mod account;
+mod airdrop;
+pub mod args;
+mod audit;
+mod balance;
+mod benchmark;
+mod breakeven;
+mod builder;
+mod busses;
+mod cache;
+mod challenge;
+mod claim;
+pub mod cli;
+mod clock_drift;
+mod close;
+mod compat;
+mod config;
+mod control;
+pub mod cu_limits;
+mod daemon;
+mod dca;
+pub mod dynamic_fee;
+pub mod error;
+pub mod events;
+pub mod exit;
+mod export;
+mod fee_escalation;
+mod fingerprint;
+mod global;
+mod healthcheck;
+mod history;
+mod http;
+#[cfg(feature = "admin")]
+mod initialize;
+mod instance_lock;
+mod key;
+mod keygen;
+pub mod keypair;
+mod ledger;
+#[cfg(feature = "dev")]
+mod localnet;
+mod logging;
+mod metrics;
+mod mine;
+mod notify;
+mod open;
+pub mod output;
+mod preview;
+mod price;
+mod profile;
+mod profit;
+pub mod program;
+mod proof;
+mod reload;
+mod report;
+mod rewards;
+pub mod schema;
+mod self_update;
+pub mod send_and_confirm;
+#[cfg(windows)]
+mod service;
+mod session;
+mod settings;
+mod setup;
+mod shell;
+pub mod signer;
+mod slots;
+mod smtp;
+mod stake;
+mod stall;
+mod stats;
+mod submit;
+mod swap;
+#[cfg(target_os = "linux")]
+mod systemd;
+mod telemetry;
+mod timeout;
+mod top;
+mod transfer;
+mod tui;
+mod upgrade;
+pub mod utils;
+mod verify;
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use args::*;
+pub use builder::MinerBuilder;
+use error::OreError;
+use events::MiningEvent;
+use output::OutputFormat;
+use signer::BoxedSigner;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+/// Runs commands against the ORE program with one RPC client, keypair, and fee settings.
+/// Build one with [`MinerBuilder`].
+pub struct Miner {
+    pub keypair_filepath: Option<String>,
+    pub priority_fee: Option<u64>,
+    pub dynamic_fee_url: Option<String>,
+    pub dynamic_fee_strategy: Option<String>,
+    pub dynamic_fee_max: Option<u64>,
+    pub rpc_client: Arc<RpcClient>,
+    pub fee_payer_filepath: Option<String>,
+    pub output: OutputFormat,
+    events: broadcast::Sender<MiningEvent>,
+    cancel: CancellationToken,
+}
+
+impl Miner {
+    pub fn with_keypair(&self, keypair_filepath: String) -> Self {
+        Self {
+            rpc_client: self.rpc_client.clone(),
+            keypair_filepath: Some(keypair_filepath),
+            priority_fee: self.priority_fee,
+            dynamic_fee_url: self.dynamic_fee_url.clone(),
+            dynamic_fee_strategy: self.dynamic_fee_strategy.clone(),
+            dynamic_fee_max: self.dynamic_fee_max,
+            fee_payer_filepath: self.fee_payer_filepath.clone(),
+            output: self.output,
+            events: self.events.clone(),
+            cancel: self.cancel.clone(),
+        }
+    }
+
+    /// Returns a receiver of the events of every mining loop run by this miner, or by
+    /// miners made from it with [`Miner::with_keypair`].
+    pub fn subscribe(&self) -> broadcast::Receiver<MiningEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns the token that cancels the miner's long-running operations. Cancelling it
+    /// stops the mining loop, transaction retries, and fee lookups at their next await
+    /// point. A transaction that was already sent may still land.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Runs the future until it completes or the miner is cancelled.
+    pub(crate) async fn cancellable<F: Future>(&self, future: F) -> Result<F::Output, OreError> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(OreError::Cancelled),
+            output = future => Ok(output),
+        }
+    }
+
+    /// Sleeps unless the miner is cancelled first.
+    pub(crate) async fn sleep(&self, duration: Duration) -> Result<(), OreError> {
+        self.cancellable(tokio::time::sleep(duration)).await
+    }
+
+    pub(crate) fn emit(&self, event: MiningEvent) {
+        self.events.send(event).ok();
+    }
+
+    /// Returns the signer for `--keypair`, from the provider for its URI scheme.
+    pub fn signer(&self) -> Result<BoxedSigner, OreError> {
+        let path = self
+            .keypair_filepath
+            .clone()
+            .ok_or(OreError::MissingKeypair)?;
+        signer::from_uri(&path).map_err(|reason| OreError::Keypair {
+            path: keypair::describe_source(&path),
+            reason,
+        })
+    }
+
+    pub fn fee_payer(&self) -> Result<BoxedSigner, OreError> {
+        let path = self
+            .fee_payer_filepath
+            .clone()
+            .ok_or(OreError::MissingKeypair)?;
+        signer::from_uri(&path).map_err(|reason| OreError::Keypair {
+            path: keypair::describe_source(&path),
+            reason,
+        })
+    }
+}
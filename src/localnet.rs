@@ -0,0 +1,211 @@
+use std::{
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::native_token::sol_to_lamports;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{write_keypair_file, Keypair},
+};
+use tracing::error;
+
+use crate::{
+    args::LocalnetArgs, error::OreError, program, settings, timeout, utils::ore_dir, Miner,
+    MinerBuilder,
+};
+
+/// The Metaplex token metadata program, which the ORE program calls to create the mint
+/// metadata.
+const METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+/// The cluster the metadata program is cloned from, if no binary is given.
+const MAINNET_URL: &str = "https://api.mainnet-beta.solana.com";
+
+/// How long to wait for the validator to answer RPC calls.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The profile that points commands at the validator.
+const PROFILE: &str = "localnet";
+
+impl Miner {
+    pub async fn localnet(&self, args: LocalnetArgs) -> Result<(), OreError> {
+        let dir = ore_dir().join("localnet");
+        std::fs::create_dir_all(&dir).ok();
+        let ledger = args
+            .ledger
+            .clone()
+            .unwrap_or_else(|| dir.join("ledger").display().to_string());
+        let rpc_url = format!("http://127.0.0.1:{}", args.rpc_port);
+
+        // Start the validator with the program preloaded
+        let mut command = Command::new("solana-test-validator");
+        command
+            .args(["--ledger", &ledger])
+            .args(["--rpc-port", &args.rpc_port.to_string()])
+            .args([
+                "--bpf-program",
+                &program::program_id().to_string(),
+                &args.program,
+            ])
+            .arg("--quiet")
+            .stdout(Stdio::null());
+        match &args.metadata_program {
+            Some(path) => command.args(["--bpf-program", METADATA_PROGRAM_ID, path]),
+            None => command.args([
+                "--url",
+                MAINNET_URL,
+                "--clone-upgradeable-program",
+                METADATA_PROGRAM_ID,
+            ]),
+        };
+        if args.reset {
+            command.arg("--reset");
+        }
+        let mut validator = match command.spawn() {
+            Ok(validator) => validator,
+            Err(err) => {
+                error!(
+                    "Failed to start solana-test-validator: {}. Install the Solana CLI tools and make sure it is on your PATH.",
+                    err
+                );
+                return Ok(());
+            }
+        };
+        let result = self.setup_localnet(&args, &rpc_url, &mut validator).await;
+        if let Err(err) = result {
+            validator.kill().ok();
+            return Err(err);
+        }
+
+        // Run until interrupted
+        println!("Press Ctrl-C to stop the validator");
+        let cancel = self.cancellation_token();
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = cancel.cancelled() => {}
+        }
+        validator.kill().ok();
+        validator.wait().ok();
+        println!("Stopped the validator. Its ledger is kept in {}", ledger);
+        Ok(())
+    }
+
+    async fn setup_localnet(
+        &self,
+        args: &LocalnetArgs,
+        rpc_url: &str,
+        validator: &mut Child,
+    ) -> Result<(), OreError> {
+        // Wait for the validator to come up
+        let rpc_client = Arc::new(RpcClient::new_with_timeout_and_commitment(
+            rpc_url.to_string(),
+            timeout::rpc_timeout(),
+            CommitmentConfig::confirmed(),
+        ));
+        let started_at = Instant::now();
+        while rpc_client.get_health().await.is_err() {
+            if let Ok(Some(status)) = validator.try_wait() {
+                return Err(OreError::Localnet(format!(
+                    "solana-test-validator exited with {}",
+                    status
+                )));
+            }
+            if started_at.elapsed().gt(&STARTUP_TIMEOUT) {
+                return Err(OreError::Localnet(
+                    "solana-test-validator did not start in time".to_string(),
+                ));
+            }
+            self.sleep(Duration::from_secs(1)).await?;
+        }
+        println!("{} Validator running at {}", "OK".bold().green(), rpc_url);
+
+        // Fund the initializer
+        let admin = MinerBuilder::rpc(rpc_client.clone())
+            .keypair(
+                self.keypair_filepath
+                    .clone()
+                    .ok_or(OreError::MissingKeypair)?,
+            )
+            .build()
+            .map_err(|err| OreError::Localnet(err.to_string()))?;
+        let initializer = admin.signer()?.pubkey();
+        admin
+            .request_airdrop(initializer, sol_to_lamports(args.airdrop))
+            .await?;
+
+        // Initialize the program
+        admin.initialize().await?;
+        let config = program::config_address();
+        if rpc_client.get_account(&config).await.is_err() {
+            return Err(OreError::Localnet(format!(
+                "The program was not initialized. It only accepts its initializer, so build it with {} as the initializer.",
+                initializer
+            )));
+        }
+        println!("{} Initialized the program", "OK".bold().green());
+
+        // Fund a test keypair
+        let keypair_path = ore_dir().join("localnet").join("miner.json");
+        if !keypair_path.exists() {
+            if let Err(err) = write_keypair_file(&Keypair::new(), &keypair_path) {
+                return Err(OreError::Localnet(format!(
+                    "Failed to write {}: {}",
+                    keypair_path.display(),
+                    err
+                )));
+            }
+        }
+        let miner = MinerBuilder::rpc(rpc_client.clone())
+            .keypair(keypair_path.display().to_string())
+            .build()
+            .map_err(|err| OreError::Localnet(err.to_string()))?;
+        let miner_pubkey = miner.signer()?.pubkey();
+        miner
+            .request_airdrop(miner_pubkey, sol_to_lamports(args.airdrop))
+            .await?;
+        println!(
+            "{} Funded {} with {} SOL",
+            "OK".bold().green(),
+            miner_pubkey,
+            args.airdrop
+        );
+
+        // Point later commands at the validator
+        write_profile(rpc_url, &keypair_path)?;
+        println!(
+            "{} Set the default profile to {}. Run `ore profile set-default <NAME>` to switch back.",
+            "OK".bold().green(),
+            PROFILE
+        );
+        Ok(())
+    }
+}
+
+/// Writes the localnet profile and makes it the default.
+fn write_profile(rpc_url: &str, keypair_path: &Path) -> Result<(), OreError> {
+    let path = settings::path();
+    let contents = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut document = contents.parse::<toml_edit::DocumentMut>().map_err(|err| {
+        OreError::Localnet(format!("Could not parse {}: {}", path.display(), err))
+    })?;
+    if !document.contains_table("profiles") {
+        let mut profiles = toml_edit::Table::new();
+        profiles.set_implicit(true);
+        document["profiles"] = toml_edit::Item::Table(profiles);
+    }
+    let mut profile = toml_edit::Table::new();
+    profile["rpc"] = toml_edit::value(rpc_url);
+    profile["keypair"] = toml_edit::value(keypair_path.display().to_string());
+    if program::program_id().ne(&ore_api::ID) {
+        profile["program_id"] = toml_edit::value(program::program_id().to_string());
+    }
+    document["profiles"][PROFILE] = toml_edit::Item::Table(profile);
+    document["default_profile"] = toml_edit::value(PROFILE);
+    std::fs::write(&path, document.to_string())
+        .map_err(|err| OreError::Localnet(format!("Failed to write {}: {}", path.display(), err)))
+}
@@ -0,0 +1,259 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use clap::ValueEnum;
+use colored::*;
+use opentelemetry::trace::TracerProvider;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::{format::Writer, FmtContext, FormatEvent, FormatFields},
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::{
+    exit,
+    telemetry::{otlp_provider, TracingGuard},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl LogRotation {
+    fn period(&self, timestamp: i64) -> i64 {
+        match self {
+            LogRotation::Never => 0,
+            LogRotation::Hourly => timestamp / 3600,
+            LogRotation::Daily => timestamp / 86400,
+        }
+    }
+}
+
+/// A log file that rotates when it grows past a maximum size or when the rotation period
+/// ends. Rotated files are renamed to `<path>.1`, `<path>.2`, and so on, keeping at most
+/// `retention` of them.
+pub struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    period: i64,
+    max_size: u64,
+    rotation: LogRotation,
+    retention: usize,
+}
+
+/// The path of the log file given with `--log-file`, once it is opened.
+static LOG_FILE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Returns the path of the log file, if one is open.
+pub fn log_file_path() -> Option<&'static PathBuf> {
+    LOG_FILE_PATH.get()
+}
+
+impl LogFile {
+    pub fn open(
+        path: PathBuf,
+        max_size: u64,
+        rotation: LogRotation,
+        retention: usize,
+    ) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        LOG_FILE_PATH.set(path.clone()).ok();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_else(|| chrono::Utc::now().timestamp());
+        Ok(Self {
+            period: rotation.period(modified),
+            size: metadata.len(),
+            path,
+            file,
+            max_size,
+            rotation,
+            retention,
+        })
+    }
+
+    fn rotated_path(&self, i: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", i));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        fs::remove_file(self.rotated_path(self.retention)).ok();
+        for i in (1..self.retention).rev() {
+            fs::rename(self.rotated_path(i), self.rotated_path(i + 1)).ok();
+        }
+        if self.retention.gt(&0) {
+            fs::rename(&self.path, self.rotated_path(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let period = self.rotation.period(chrono::Utc::now().timestamp());
+        let full = self.max_size.gt(&0) && self.size + buf.len() as u64 > self.max_size;
+        if self.size.gt(&0) && (full || period.ne(&self.period)) {
+            self.rotate()?;
+        }
+        self.period = period;
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Formats log events the way the CLI has always printed them: bare messages for info,
+/// and a colored label for warnings and errors.
+struct Friendly;
+
+impl<S, N> FormatEvent<S, N> for Friendly
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let label = match *event.metadata().level() {
+            Level::ERROR => Some("ERROR".bold().red()),
+            Level::WARN => Some("WARNING".bold().yellow()),
+            Level::INFO => None,
+            Level::DEBUG => Some("DEBUG".dimmed()),
+            Level::TRACE => Some("TRACE".dimmed()),
+        };
+        if let Some(label) = label {
+            if writer.has_ansi_escapes() {
+                write!(writer, "{} ", label)?;
+            } else {
+                write!(writer, "{} ", label.clear())?;
+            }
+        }
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// Returns the default filter for the given verbosity. `RUST_LOG` overrides it.
+fn default_filter(verbose: u8, quiet: bool) -> &'static str {
+    match (quiet, verbose) {
+        (true, _) => "error",
+        (false, 0) => "warn,ore_miner=info",
+        (false, 1) => "warn,ore_miner=debug",
+        (false, _) => "info,ore_miner=trace",
+    }
+}
+
+/// Marks the command as failed whenever an error is logged.
+struct ExitCodeLayer;
+
+impl<S: Subscriber> Layer<S> for ExitCodeLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().level().eq(&Level::ERROR) {
+            exit::set(exit::ERROR);
+        }
+    }
+}
+
+/// Installs the global log subscriber. Logs are written to stderr unless `console` is
+/// off, so command output on stdout stays clean, and to the log file without colors, if
+/// one is given. Spans are also
+/// exported to an OTLP collector, if one is given.
+pub fn init(
+    console: bool,
+    color: bool,
+    format: LogFormat,
+    verbose: u8,
+    quiet: bool,
+    log_file: Option<LogFile>,
+    otlp_endpoint: Option<&str>,
+) -> Option<TracingGuard> {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(default_filter(verbose, quiet)));
+    let fmt_layer = console.then(|| match format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_ansi(color && std::io::stderr().is_terminal())
+            .event_format(Friendly)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .json()
+            .boxed(),
+    });
+    let file_layer = log_file.map(|log_file| {
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(Mutex::new(log_file))
+            .with_ansi(false);
+        match format {
+            LogFormat::Pretty => layer.boxed(),
+            LogFormat::Json => layer.json().boxed(),
+        }
+    });
+    let provider = otlp_endpoint.map(otlp_provider);
+    let otel_layer = match &provider {
+        Some(Ok(provider)) => {
+            Some(tracing_opentelemetry::layer().with_tracer(provider.tracer("ore-cli")))
+        }
+        _ => None,
+    };
+    // The filter only applies to the outputs, so errors set the exit code at any verbosity
+    tracing_subscriber::registry()
+        .with(
+            Layer::and_then(fmt_layer, file_layer)
+                .and_then(otel_layer)
+                .with_filter(filter),
+        )
+        .with(ExitCodeLayer.with_filter(LevelFilter::ERROR))
+        .init();
+    match provider {
+        Some(Ok(provider)) => Some(TracingGuard(provider)),
+        Some(Err(err)) => {
+            tracing::error!("Failed to initialize tracing: {}", err);
+            None
+        }
+        None => None,
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    healthcheck,
+    http::{self, Response},
+};
+
+/// The number of landed transactions kept for the dashboards.
+pub const RECENT_TRANSACTIONS: usize = 10;
+
+/// Process-wide mining metrics, shared by every metrics sink.
+pub struct Metrics {
+    pub hashes: AtomicU64,
+    pub hashrate: AtomicU64,
+    pub best_difficulty: AtomicU64,
+    pub rounds: AtomicU64,
+    pub landed: AtomicU64,
+    pub expired: AtomicU64,
+    pub fees: AtomicU64,
+    pub rewards: AtomicU64,
+    pub unclaimed_balance: AtomicU64,
+    pub rpc_errors: AtomicU64,
+    pub land_slot_delay_sum: AtomicU64,
+    pub land_slot_delay_count: AtomicU64,
+    pub clock_drift: AtomicI64,
+}
+
+static METRICS: Metrics = Metrics {
+    hashes: AtomicU64::new(0),
+    hashrate: AtomicU64::new(0),
+    best_difficulty: AtomicU64::new(0),
+    rounds: AtomicU64::new(0),
+    landed: AtomicU64::new(0),
+    expired: AtomicU64::new(0),
+    fees: AtomicU64::new(0),
+    rewards: AtomicU64::new(0),
+    unclaimed_balance: AtomicU64::new(0),
+    rpc_errors: AtomicU64::new(0),
+    land_slot_delay_sum: AtomicU64::new(0),
+    land_slot_delay_count: AtomicU64::new(0),
+    clock_drift: AtomicI64::new(0),
+};
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// The mining metrics of one wallet, so rigs that mine for several can tell them apart.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WalletMetrics {
+    pub hashes: u64,
+    pub rounds: u64,
+    pub landed: u64,
+    pub expired: u64,
+    pub fees: u64,
+    pub rewards: u64,
+    pub unclaimed_balance: u64,
+}
+
+/// A per-wallet Prometheus metric: its name, type, help, and value.
+type WalletMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&WalletMetrics) -> u64,
+);
+
+static WALLETS: Mutex<BTreeMap<String, WalletMetrics>> = Mutex::new(BTreeMap::new());
+
+/// Updates the metrics of the wallet with the authority.
+pub fn update_wallet(authority: &str, update: impl FnOnce(&mut WalletMetrics)) {
+    let mut wallets = WALLETS.lock().unwrap_or_else(|err| err.into_inner());
+    update(wallets.entry(authority.to_string()).or_default());
+}
+
+/// Returns the metrics of every wallet mined for this session, by authority.
+pub fn wallets() -> BTreeMap<String, WalletMetrics> {
+    WALLETS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+/// A mine transaction that landed during this session.
+#[derive(Clone)]
+pub struct LandedTransaction {
+    pub signature: String,
+    pub timestamp: i64,
+    pub difficulty: u32,
+    pub reward: u64,
+    pub fee: u64,
+}
+
+/// Live state of the current mining session, shown by the dashboards.
+pub struct Status {
+    pub round: u64,
+    pub challenge: [u8; 32],
+    pub cutoff_at: i64,
+    pub reset_at: i64,
+    pub session_best_difficulty: u32,
+    /// The best difficulty of the round being hashed, and the reward it would earn.
+    pub round_best_difficulty: u32,
+    pub round_expected_reward: u64,
+    pub recent: VecDeque<LandedTransaction>,
+    /// The number of rounds this session whose best hash reached each difficulty.
+    pub difficulties: BTreeMap<u32, u64>,
+    pub hashrate_sum: u64,
+}
+
+impl Status {
+    pub fn record_round(&mut self, difficulty: u32, hashrate: u64) {
+        self.session_best_difficulty = self.session_best_difficulty.max(difficulty);
+        *self.difficulties.entry(difficulty).or_default() += 1;
+        self.hashrate_sum += hashrate;
+    }
+
+    pub fn push_landed(&mut self, tx: LandedTransaction) {
+        self.recent.push_front(tx);
+        self.recent.truncate(RECENT_TRANSACTIONS);
+    }
+}
+
+static STATUS: Mutex<Status> = Mutex::new(Status {
+    round: 0,
+    challenge: [0; 32],
+    cutoff_at: 0,
+    reset_at: 0,
+    session_best_difficulty: 0,
+    round_best_difficulty: 0,
+    round_expected_reward: 0,
+    recent: VecDeque::new(),
+    difficulties: BTreeMap::new(),
+    hashrate_sum: 0,
+});
+
+pub fn status() -> MutexGuard<'static, Status> {
+    STATUS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Returns the session metrics and status as JSON, for the dashboards.
+pub fn status_json() -> Value {
+    let metrics = metrics();
+    let status = status();
+    json!({
+        "round": status.round,
+        "challenge": bs58::encode(status.challenge).into_string(),
+        "cutoff_at": status.cutoff_at,
+        "hashes": metrics.hashes.load(Ordering::Relaxed),
+        "hashrate": metrics.hashrate.load(Ordering::Relaxed),
+        "best_difficulty": metrics.best_difficulty.load(Ordering::Relaxed),
+        "session_best_difficulty": status.session_best_difficulty,
+        "landed": metrics.landed.load(Ordering::Relaxed),
+        "expired": metrics.expired.load(Ordering::Relaxed),
+        "fees": metrics.fees.load(Ordering::Relaxed),
+        "rewards": metrics.rewards.load(Ordering::Relaxed),
+        "unclaimed_balance": metrics.unclaimed_balance.load(Ordering::Relaxed),
+        "rpc_errors": metrics.rpc_errors.load(Ordering::Relaxed),
+        "land_slot_delay_sum": metrics.land_slot_delay_sum.load(Ordering::Relaxed),
+        "land_slot_delay_count": metrics.land_slot_delay_count.load(Ordering::Relaxed),
+        "clock_drift": metrics.clock_drift.load(Ordering::Relaxed),
+        "difficulty_histogram": status.difficulties.iter().map(|(difficulty, rounds)| json!({
+            "difficulty": difficulty,
+            "rounds": rounds,
+        })).collect::<Vec<_>>(),
+        "recent": status.recent.iter().map(|tx| json!({
+            "signature": tx.signature,
+            "timestamp": tx.timestamp,
+            "difficulty": tx.difficulty,
+            "reward": tx.reward,
+            "fee": tx.fee,
+        })).collect::<Vec<_>>(),
+        "wallets": wallets().iter().map(|(authority, wallet)| json!({
+            "authority": authority,
+            "hashes": wallet.hashes,
+            "rounds": wallet.rounds,
+            "landed": wallet.landed,
+            "expired": wallet.expired,
+            "fees": wallet.fees,
+            "rewards": wallet.rewards,
+            "unclaimed_balance": wallet.unclaimed_balance,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+impl Metrics {
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let metrics = [
+            (
+                "ore_hashes_total",
+                "counter",
+                "Hashes computed",
+                &self.hashes,
+            ),
+            (
+                "ore_hashrate",
+                "gauge",
+                "Hashes per second in the last round",
+                &self.hashrate,
+            ),
+            (
+                "ore_best_difficulty",
+                "gauge",
+                "Best difficulty found in the last round",
+                &self.best_difficulty,
+            ),
+            (
+                "ore_rounds_total",
+                "counter",
+                "Mining rounds attempted",
+                &self.rounds,
+            ),
+            (
+                "ore_transactions_landed_total",
+                "counter",
+                "Mine transactions that landed",
+                &self.landed,
+            ),
+            (
+                "ore_transactions_expired_total",
+                "counter",
+                "Mine transactions that failed to land",
+                &self.expired,
+            ),
+            (
+                "ore_fees_paid_lamports_total",
+                "counter",
+                "Transaction fees paid by landed mine transactions, in lamports",
+                &self.fees,
+            ),
+            (
+                "ore_rewards_grains_total",
+                "counter",
+                "Mining rewards earned, in grains",
+                &self.rewards,
+            ),
+            (
+                "ore_unclaimed_balance_grains",
+                "gauge",
+                "Unclaimed proof balance, in grains",
+                &self.unclaimed_balance,
+            ),
+            (
+                "ore_rpc_errors_total",
+                "counter",
+                "RPC requests that returned an error",
+                &self.rpc_errors,
+            ),
+            (
+                "ore_land_slot_delay_sum",
+                "counter",
+                "Slots between sending a slot-timed mine transaction and it landing, summed",
+                &self.land_slot_delay_sum,
+            ),
+            (
+                "ore_land_slot_delay_count",
+                "counter",
+                "Slot-timed mine transactions that landed",
+                &self.land_slot_delay_count,
+            ),
+        ];
+        let mut s = String::new();
+        for (name, kind, help, value) in metrics {
+            s.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} {}\n{} {}\n",
+                name,
+                help,
+                name,
+                kind,
+                name,
+                value.load(Ordering::Relaxed)
+            ));
+        }
+        s.push_str(&format!(
+            "# HELP ore_clock_drift_seconds Seconds the local clock is behind the cluster clock\n# TYPE ore_clock_drift_seconds gauge\nore_clock_drift_seconds {}\n",
+            self.clock_drift.load(Ordering::Relaxed)
+        ));
+
+        // Break the totals down by wallet
+        let wallets = wallets();
+        let wallet_metrics: [WalletMetric; 7] = [
+            (
+                "ore_wallet_hashes_total",
+                "counter",
+                "Hashes computed for each wallet",
+                |w| w.hashes,
+            ),
+            (
+                "ore_wallet_rounds_total",
+                "counter",
+                "Mining rounds attempted by each wallet",
+                |w| w.rounds,
+            ),
+            (
+                "ore_wallet_transactions_landed_total",
+                "counter",
+                "Mine transactions of each wallet that landed",
+                |w| w.landed,
+            ),
+            (
+                "ore_wallet_transactions_expired_total",
+                "counter",
+                "Mine transactions of each wallet that failed to land",
+                |w| w.expired,
+            ),
+            (
+                "ore_wallet_fees_paid_lamports_total",
+                "counter",
+                "Transaction fees paid by each wallet's landed mine transactions, in lamports",
+                |w| w.fees,
+            ),
+            (
+                "ore_wallet_rewards_grains_total",
+                "counter",
+                "Mining rewards earned by each wallet, in grains",
+                |w| w.rewards,
+            ),
+            (
+                "ore_wallet_unclaimed_balance_grains",
+                "gauge",
+                "Unclaimed proof balance of each wallet, in grains",
+                |w| w.unclaimed_balance,
+            ),
+        ];
+        if !wallets.is_empty() {
+            for (name, kind, help, value) in wallet_metrics {
+                s.push_str(&format!(
+                    "# HELP {} {}\n# TYPE {} {}\n",
+                    name, help, name, kind
+                ));
+                for (authority, wallet) in wallets.iter() {
+                    s.push_str(&format!(
+                        "{}{{authority=\"{}\"}} {}\n",
+                        name,
+                        authority,
+                        value(wallet)
+                    ));
+                }
+            }
+        }
+        s
+    }
+}
+
+/// Serves the metrics over HTTP on a background thread.
+pub fn serve(addr: &str) {
+    http::serve("metrics", addr, |request| match request.path.as_str() {
+        "/" | "/metrics" => Response::new(200, "text/plain; version=0.0.4", metrics().render()),
+        "/healthz" => healthcheck::response(),
+        _ => Response::not_found(),
+    });
+}
+
+/// Serves the web dashboard on a background thread.
+pub fn serve_dashboard(addr: &str) {
+    http::serve("dashboard", addr, |request| match request.path.as_str() {
+        "/" | "/index.html" => Response::new(200, "text/html; charset=utf-8", DASHBOARD_HTML),
+        "/api/status" => Response::json(200, status_json()),
+        _ => Response::not_found(),
+    });
+}
+
+const DASHBOARD_HTML: &str = include_str!("../assets/dashboard.html");
@@ -1,100 +1,499 @@
-use std::{sync::Arc, sync::RwLock, time::Instant};
+use std::{
+    panic::AssertUnwindSafe,
+    str::FromStr,
+    sync::{atomic::Ordering, Arc, RwLock},
+    time::{Duration, Instant},
+};
 
-use colored::*;
+use chrono::Utc;
 use drillx::{
     equix::{self},
     Hash, Solution,
 };
+use futures::FutureExt;
 use ore_api::{
-    consts::{BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION},
-    state::{Config, Proof},
+    consts::{BUS_COUNT, EPOCH_DURATION},
+    state::{Bus, Config, Proof},
 };
 use rand::Rng;
 use solana_program::pubkey::Pubkey;
-use solana_rpc_client::spinner;
-use solana_sdk::signer::Signer;
+use tracing::{debug, error, info, warn};
 
 use crate::{
     args::MineArgs,
+    clock_drift,
+    control::{self, control},
+    cu_limits::{CU_LIMIT_MINE_TX, CU_LIMIT_RESET_TX},
+    daemon::{default_pid_file, shutdown_signal, PidFile},
+    error::OreError,
+    events::{self, MiningEvent},
+    exit,
+    fee_escalation::escalation,
+    fingerprint, healthcheck,
+    instance_lock::InstanceLock,
+    ledger::{self, LedgerEntry, LedgerKind, RoundEntry},
+    metrics::{self, metrics, status, update_wallet, wallets},
+    notify::{self, Health},
+    output::{finish_progress, hide_progress, is_quiet, new_progress_bar},
+    profit::ProfitGuard,
+    program,
     send_and_confirm::ComputeBudget,
-    utils::{amount_u64_to_string, get_clock, get_config, get_proof_with_authority, proof_pubkey},
+    session, slots, stall,
+    telemetry::{push_statsd, InfluxSink, RoundReport},
+    tui,
+    utils::{
+        amount_u64_to_string, calculate_multiplier, get_mining_state, get_mining_state_with_proof,
+        get_proof_with_authority, proof_pubkey, MiningState,
+    },
     Miner,
 };
 
 impl Miner {
-    pub async fn mine(&self, args: MineArgs) {
+    pub async fn mine(&self, args: MineArgs) -> Result<(), OreError> {
+        // Record the pid while running
+        let pid_file = match (&args.pid_file, args.daemon) {
+            (Some(path), _) => Some(PidFile::create(path.clone())?),
+            (None, true) => Some(PidFile::create(default_pid_file())?),
+            (None, false) => None,
+        };
+
+        // Refuse to race another miner on the same proof
+        let _instance_lock = match args.force {
+            true => None,
+            false => Some(InstanceLock::acquire(proof_pubkey(
+                self.signer()?.pubkey(),
+            ))?),
+        };
+
         // Register, if needed.
-        let signer = self.signer();
-        self.open().await;
+        self.open().await?;
 
         // Check num threads
         self.check_num_cores(args.threads);
 
-        // Start mining loop
+        // Pick where in the nonce space to start
+        if let Some(offset) = args.nonce_offset {
+            fingerprint::set_nonce_offset(offset);
+        }
+        debug!("Starting nonces at offset {}", fingerprint::nonce_offset());
+
+        // Serve metrics
+        healthcheck::configure(args.health_file.clone(), args.health_max_age);
+        if let Some(addr) = &args.metrics_addr {
+            metrics::serve(addr);
+        }
+        if let Some(addr) = &args.dashboard_addr {
+            metrics::serve_dashboard(addr);
+        }
+        if let (Some(addr), Some(token)) = (&args.api_addr, &args.api_token) {
+            control::serve(addr, token.clone());
+        }
+
+        // Start dashboard
+        if args.tui {
+            hide_progress();
+            tui::spawn();
+        }
+
+        // Configure notifications
+        notify::init(&args.notify);
+
+        // Reload the config file on SIGHUP
+        #[cfg(unix)]
+        crate::reload::watch_sighup();
+
+        // Watch for rounds that stop landing
+        if let Some(minutes) = args.stall_alarm {
+            stall::start(
+                self.rpc_client.clone(),
+                self.signer()?.pubkey(),
+                minutes.saturating_mul(60),
+                args.stall_restart,
+            );
+        }
+
+        // Time submissions to slots
+        if args.slot_timing {
+            let ws_url = args.ws_url.clone().unwrap_or_else(|| {
+                solana_cli_config::Config::compute_websocket_url(&self.rpc_client.url())
+            });
+            slots::start(ws_url);
+        }
+
+        // Stop the mining loop on Ctrl-C, or when `ore stop` terminates the daemon
+        session::start(args.summary_file.clone(), self.is_json());
+        let cancel = self.cancellation_token();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            cancel.cancel();
+        });
+
+        // Render progress events
+        let (stop_renderer, renderer) = events::spawn_renderer(self.subscribe(), self.is_json());
+
+        // Tell systemd the miner is up, and keep its watchdog fed while mining progresses
+        #[cfg(target_os = "linux")]
+        crate::systemd::ready();
+
+        // Start mining loop, alerting if it panics
+        let result = AssertUnwindSafe(self.cancellable(self.mine_loop(&args)))
+            .catch_unwind()
+            .await
+            .map(|result| result.and_then(|result| result));
+        #[cfg(target_os = "linux")]
+        crate::systemd::stopping();
+        stop_renderer.send(()).ok();
+        renderer.await.ok();
+        drop(pid_file);
+        match result {
+            Ok(Ok(())) => session::finish(),
+            // Summarize the session and alert before exiting
+            Ok(Err(OreError::Cancelled)) => {
+                session::stop("Interrupted".to_string(), exit::INTERRUPTED).await
+            }
+            Ok(Err(err)) => {
+                error!("{}", err);
+                exit::set(err.exit_code());
+                session::stop(err.to_string(), exit::code()).await;
+            }
+            Err(err) => {
+                let reason = err
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| err.downcast_ref::<&str>().map(|s| s.to_string()))
+                    .unwrap_or_else(|| "Panicked".to_string());
+                exit::set(exit::ERROR);
+                session::stop(reason, exit::code()).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn mine_loop(&self, args: &MineArgs) -> Result<(), OreError> {
+        let signer = self.signer()?;
+        let mut health = Health::default();
+        let mut profit_guard = args
+            .min_profit_margin
+            .map(|margin| ProfitGuard::new(margin, args.unprofitable_rounds));
+        if let Some(step) = args.fee_escalation_step {
+            escalation().configure(step, args.fee_escalation_rounds);
+        }
+        let influx = args
+            .influx_url
+            .clone()
+            .map(|url| InfluxSink::spawn(url, args.influx_token.clone()));
+        let mut round = 0;
         loop {
+            // Wait while paused
+            if control().is_paused() {
+                info!("Mining paused");
+                stall::set_paused(true);
+                while control().is_paused() {
+                    healthcheck::beat();
+                    self.sleep(Duration::from_secs(1)).await?;
+                }
+                stall::set_paused(false);
+                info!("Mining resumed");
+            }
+
+            // Claim rewards, if requested
+            if control().take_claim_request() {
+                let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await?;
+                if proof.balance.gt(&0) {
+                    info!("Claiming {} ORE", amount_u64_to_string(proof.balance));
+                    if let Err(err) = self.claim_rewards(proof.balance).await {
+                        error!("Failed to claim rewards: {}", err);
+                    }
+                }
+            }
+
+            // Check fee payer balance
+            let balance = self
+                .rpc_client
+                .get_balance(&self.fee_payer()?.pubkey())
+                .await
+                .map_err(|err| err.to_string());
+            health.observe_balance(balance);
+
             // Fetch proof
-            let config = get_config(&self.rpc_client).await;
-            let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
-            println!(
-                "\nStake: {} ORE\n  Multiplier: {:12}x",
-                amount_u64_to_string(proof.balance),
-                calculate_multiplier(proof.balance, config.top_balance)
-            );
+            let (state, proof) =
+                get_mining_state_with_proof(&self.rpc_client, signer.pubkey()).await?;
+            let config = state.config;
+            if !self.is_json() && !args.tui && !is_quiet() {
+                println!(
+                    "\nStake: {} ORE\n  Multiplier: {:12}x",
+                    amount_u64_to_string(proof.balance),
+                    calculate_multiplier(proof.balance, config.top_balance)
+                );
+            }
 
             // Calc cutoff time
-            let cutoff_time = self.get_cutoff(proof, args.buffer_time).await;
+            let drift = clock_drift::observe(&state.clock);
+            debug!("The local clock is {}s behind the cluster clock", drift);
+            let cutoff_time = get_cutoff(proof, args.buffer_time);
+            debug!("Round {} has {}s until the cutoff", round, cutoff_time);
+            let reset_in = get_reset_countdown(config);
+            if !self.is_json() && !args.tui && !is_quiet() {
+                println!("  Next reset: in {} sec", reset_in);
+            }
+            self.emit(MiningEvent::RoundStarted {
+                round,
+                challenge: bs58::encode(proof.challenge).into_string(),
+                stake: proof.balance,
+                multiplier: calculate_multiplier(proof.balance, config.top_balance),
+                min_difficulty: config.min_difficulty,
+                cutoff_time,
+            });
+            {
+                let mut status = status();
+                status.round = round as u64;
+                status.challenge = proof.challenge;
+                status.cutoff_at = Utc::now().timestamp() + cutoff_time as i64;
+                status.reset_at = Utc::now().timestamp() + reset_in as i64;
+                status.round_best_difficulty = 0;
+                status.round_expected_reward = 0;
+            }
+
+            let authority = signer.pubkey().to_string();
+            metrics()
+                .unclaimed_balance
+                .store(proof.balance, Ordering::Relaxed);
+            update_wallet(&authority, |wallet| {
+                wallet.unclaimed_balance = proof.balance
+            });
 
             // Run drillx
-            let solution = Self::find_hash_par(
-                proof,
-                cutoff_time,
-                args.threads,
-                config.min_difficulty as u32,
-            )
-            .await;
+            let Some((solution, hashrate, hashes)) = self
+                .find_hash_par(
+                    round,
+                    proof,
+                    cutoff_time,
+                    reset_in,
+                    control().threads().unwrap_or(args.threads),
+                    config,
+                )
+                .await
+            else {
+                if self.cancellation_token().is_cancelled() {
+                    return Err(OreError::Cancelled);
+                }
+
+                // The stall alarm restarted the round, so start it over with a fresh proof
+                warn!("Abandoned round {} to restart the mining loop", round);
+                continue;
+            };
+            update_wallet(&authority, |wallet| wallet.hashes += hashes);
+            ledger::record_round(&RoundEntry {
+                timestamp: Utc::now().timestamp(),
+                authority: authority.clone(),
+                challenge: bs58::encode(proof.challenge).into_string(),
+                stake: proof.balance,
+                difficulty: solution.to_hash().difficulty(),
+                hashrate,
+            });
 
             // Submit most difficult hash
-            let mut compute_budget = 500_000;
+            let mut state = get_mining_state(&self.rpc_client).await?;
+            if self.wait_out_reset(&state, args.reset_guard).await? {
+                state = get_mining_state(&self.rpc_client).await?;
+            }
+            let config = state.config;
+            let mut compute_budget = CU_LIMIT_MINE_TX;
             let mut ixs = vec![ore_api::instruction::auth(proof_pubkey(signer.pubkey()))];
-            if self.should_reset(config).await && rand::thread_rng().gen_range(0..100).eq(&0) {
-                compute_budget += 100_000;
+            if should_reset(config) && rand::thread_rng().gen_range(0..100).eq(&0) {
+                compute_budget += CU_LIMIT_RESET_TX;
                 ixs.push(ore_api::instruction::reset(signer.pubkey()));
             }
+            let difficulty = solution.to_hash().difficulty();
+            metrics().hashrate.store(hashrate, Ordering::Relaxed);
+            metrics()
+                .best_difficulty
+                .store(difficulty as u64, Ordering::Relaxed);
+            status().record_round(difficulty, hashrate);
+            self.emit(MiningEvent::SolutionFound {
+                round,
+                difficulty,
+                hash: bs58::encode(solution.to_hash().h).into_string(),
+                hashrate,
+            });
+            let reward = estimate_reward(config, proof, difficulty);
+            let bus = find_bus(args.bus_strategy, &state.busses, reward, round);
             ixs.push(ore_api::instruction::mine(
                 signer.pubkey(),
                 signer.pubkey(),
-                find_bus(),
+                bus,
                 solution,
             ));
-            self.send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
+            let sent_slot = slots::wait_for_send_window().await;
+            self.emit(MiningEvent::TxSubmitted {
+                round,
+                difficulty,
+                bus: bus.to_string(),
+            });
+            let sig = match self
+                .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
                 .await
-                .ok();
+            {
+                Ok(sig) => {
+                    if let Some(slot) = sent_slot {
+                        slots::observe_landing(slot);
+                    }
+                    Some(sig)
+                }
+                Err(err @ OreError::InsufficientFunds { .. }) => return Err(err),
+                Err(_) => None,
+            };
+            self.emit(MiningEvent::TxConfirmed {
+                round,
+                difficulty,
+                bus: bus.to_string(),
+                signature: sig.map(|sig| sig.to_string()),
+            });
+            // Rewards and fees are accounted for once they're found on-chain, off the loop
+            let on_settled = {
+                let authority = authority.clone();
+                let signer_pubkey = signer.pubkey();
+                let events = self.events.clone();
+                let csv_log = args.csv_log.clone();
+                let influx = influx.clone();
+                let statsd_addr = args.statsd_addr.clone();
+                let dogstatsd = args.dogstatsd;
+                move |entry: &LedgerEntry| {
+                    if let Some(path) = &csv_log {
+                        ledger::append_csv(path, entry);
+                    }
+                    if entry.landed {
+                        update_wallet(&authority, |wallet| {
+                            wallet.fees += entry.fee;
+                            wallet.rewards += entry.amount;
+                        });
+                        metrics().fees.fetch_add(entry.fee, Ordering::Relaxed);
+                        metrics().rewards.fetch_add(entry.amount, Ordering::Relaxed);
+                        events
+                            .send(MiningEvent::RewardReceived {
+                                round,
+                                signature: entry.signature.clone().unwrap_or_default(),
+                                timestamp: entry.timestamp,
+                                difficulty,
+                                reward: entry.amount,
+                                fee: entry.fee,
+                            })
+                            .ok();
+                    }
+
+                    // Push telemetry
+                    let report = RoundReport {
+                        authority: signer_pubkey,
+                        hashrate,
+                        difficulty,
+                        reward: entry.amount,
+                        fee: entry.fee,
+                        landed: entry.landed,
+                    };
+                    if let Some(addr) = &statsd_addr {
+                        push_statsd(addr, dogstatsd, &report);
+                    }
+                    if let Some(influx) = &influx {
+                        influx.push(report);
+                    }
+                }
+            };
+            let entry = self.record_transaction_then(
+                LedgerKind::Mine,
+                signer.pubkey(),
+                sig,
+                0,
+                Some(difficulty as u64),
+                on_settled,
+            );
+            metrics().rounds.fetch_add(1, Ordering::Relaxed);
+            update_wallet(&authority, |wallet| {
+                wallet.rounds += 1;
+                if entry.landed {
+                    wallet.landed += 1;
+                } else {
+                    wallet.expired += 1;
+                }
+            });
+            if entry.landed {
+                healthcheck::beat();
+                metrics().landed.fetch_add(1, Ordering::Relaxed);
+            } else {
+                metrics().expired.fetch_add(1, Ordering::Relaxed);
+            }
+            health.observe_round(entry.landed);
+            if let Some(bump) = escalation().observe(entry.landed, self.max_dynamic_fee()) {
+                info!("Adding {} microlamports to the priority fee", bump);
+            }
+
+            // Pause while mining is unprofitable
+            if let Some(guard) = profit_guard.as_mut() {
+                let wallet = wallets().get(&authority).copied().unwrap_or_default();
+                let fee = wallet.fees / wallet.rounds.max(1);
+                if let Some(margin) = guard.margin(config, proof, fee).await {
+                    debug!("Round {} has an expected margin of {:.1}%", round, margin);
+                    if guard.observe(margin) {
+                        warn!(
+                            "Mining paused, expected margin of {:.1}% is below the minimum",
+                            margin
+                        );
+                        stall::set_paused(true);
+                        self.wait_until_profitable(guard, signer.pubkey(), fee)
+                            .await?;
+                        stall::set_paused(false);
+                    }
+                }
+            }
+            round += 1;
+
+            // Stop after the requested number of rounds
+            if args.rounds.is_some_and(|rounds| round as u64 >= rounds) {
+                break;
+            }
         }
+        Ok(())
     }
 
+    /// Hashes until the cutoff, and returns the best solution with the hashrate and the
+    /// number of hashes. Returns `None` if the miner was cancelled, or the stall alarm
+    /// abandoned the round.
     async fn find_hash_par(
+        &self,
+        round: usize,
         proof: Proof,
         cutoff_time: u64,
+        reset_in: u64,
         threads: u64,
-        min_difficulty: u32,
-    ) -> Solution {
+        config: Config,
+    ) -> Option<(Solution, u64, u64)> {
         // Dispatch job to each thread
-        let progress_bar = Arc::new(spinner::new_progress_bar());
+        let events = self.events.clone();
+        let cancel = stall::start_round(&self.cancellation_token());
+        let nonce_offset = fingerprint::nonce_offset();
+        let timer = Instant::now();
+        let min_difficulty = config.min_difficulty as u32;
+        let progress_bar = Arc::new(new_progress_bar());
         progress_bar.set_message("Mining...");
         let global_best_difficulty = Arc::new(RwLock::new(0u32));
         let handles: Vec<_> = (0..threads)
             .map(|i| {
                 let global_best_difficulty = Arc::clone(&global_best_difficulty);
                 std::thread::spawn({
-                    let proof = proof.clone();
                     let progress_bar = progress_bar.clone();
+                    let events = events.clone();
+                    let cancel = cancel.clone();
                     let mut memory = equix::SolverMemory::new();
                     move || {
                         let timer = Instant::now();
-                        let mut nonce = u64::MAX.saturating_div(threads).saturating_mul(i);
+                        let first_nonce = u64::MAX
+                            .saturating_div(threads)
+                            .saturating_mul(i)
+                            .wrapping_add(nonce_offset);
+                        let mut nonce = first_nonce;
                         let mut best_nonce = nonce;
                         let mut best_difficulty = 0;
                         let mut best_hash = Hash::default();
+                        let mut last_sample = Instant::now();
                         loop {
                             // Create hash
                             if let Ok(hx) = drillx::hash_with_memory(
@@ -107,7 +506,8 @@ impl Miner {
                                     best_nonce = nonce;
                                     best_difficulty = difficulty;
                                     best_hash = hx;
-                                    if best_difficulty.gt(&*global_best_difficulty.read().unwrap()) {
+                                    if best_difficulty.gt(&*global_best_difficulty.read().unwrap())
+                                    {
                                         *global_best_difficulty.write().unwrap() = best_difficulty;
                                     }
                                 }
@@ -115,13 +515,48 @@ impl Miner {
 
                             // Exit if time has elapsed
                             if nonce % 100 == 0 {
-                                let global_best_difficulty = *global_best_difficulty.read().unwrap();
+                                metrics().hashes.fetch_add(100, Ordering::Relaxed);
+                                let global_best_difficulty =
+                                    *global_best_difficulty.read().unwrap();
+                                if cancel.is_cancelled() {
+                                    break;
+                                }
+                                // Value the best hash so far on the reporting thread
+                                let expected_reward = (i == 0
+                                    && global_best_difficulty.ge(&min_difficulty))
+                                .then(|| estimate_reward(config, proof, global_best_difficulty));
+                                let reward_str = expected_reward
+                                    .map(|r| format!(", {} ORE", amount_u64_to_string(r)))
+                                    .unwrap_or_default();
+                                if i == 0 && last_sample.elapsed().as_secs().ge(&1) {
+                                    // Extrapolate the hashrate of all threads from this one
+                                    last_sample = Instant::now();
+                                    let hashes =
+                                        nonce.wrapping_sub(first_nonce).saturating_mul(threads);
+                                    events
+                                        .send(MiningEvent::HashrateSample {
+                                            round,
+                                            hashrate: (hashes as f64
+                                                / timer.elapsed().as_secs_f64())
+                                                as u64,
+                                            best_difficulty: global_best_difficulty,
+                                            expected_reward: expected_reward.unwrap_or(0),
+                                        })
+                                        .ok();
+                                }
+                                let reset_str =
+                                    match reset_in.saturating_sub(timer.elapsed().as_secs()) {
+                                        0 => "reset due".to_string(),
+                                        secs => format!("reset in {} sec", secs),
+                                    };
                                 if timer.elapsed().as_secs().ge(&cutoff_time) {
                                     if i == 0 {
                                         progress_bar.set_message(format!(
-                                            "Mining... ({} / {} difficulty)",
+                                            "Mining... ({} / {} difficulty{}, {})",
                                             global_best_difficulty,
                                             min_difficulty,
+                                            reward_str,
+                                            reset_str,
                                         ));
                                     }
                                     if global_best_difficulty.ge(&min_difficulty) {
@@ -130,20 +565,27 @@ impl Miner {
                                     }
                                 } else if i == 0 {
                                     progress_bar.set_message(format!(
-                                        "Mining... ({} / {} difficulty, {} sec remaining)",
+                                        "Mining... ({} / {} difficulty{}, {} sec remaining, {})",
                                         global_best_difficulty,
                                         min_difficulty,
+                                        reward_str,
                                         cutoff_time.saturating_sub(timer.elapsed().as_secs()),
+                                        reset_str,
                                     ));
                                 }
                             }
 
                             // Increment nonce
-                            nonce += 1;
+                            nonce = nonce.wrapping_add(1);
                         }
 
                         // Return the best nonce
-                        (best_nonce, best_difficulty, best_hash)
+                        (
+                            best_nonce,
+                            best_difficulty,
+                            best_hash,
+                            nonce.wrapping_sub(first_nonce),
+                        )
                     }
                 })
             })
@@ -153,8 +595,10 @@ impl Miner {
         let mut best_nonce = 0;
         let mut best_difficulty = 0;
         let mut best_hash = Hash::default();
+        let mut total_hashes = 0;
         for h in handles {
-            if let Ok((nonce, difficulty, hash)) = h.join() {
+            if let Ok((nonce, difficulty, hash, hashes)) = h.join() {
+                total_hashes += hashes;
                 if difficulty > best_difficulty {
                     best_difficulty = difficulty;
                     best_nonce = nonce;
@@ -164,54 +608,166 @@ impl Miner {
         }
 
         // Update log
-        progress_bar.finish_with_message(format!(
-            "Best hash: {} (difficulty: {})",
-            bs58::encode(best_hash.h).into_string(),
-            best_difficulty
-        ));
+        finish_progress(
+            &progress_bar,
+            format!(
+                "Best hash: {} (difficulty: {})",
+                bs58::encode(best_hash.h).into_string(),
+                best_difficulty
+            ),
+        );
+
+        if cancel.is_cancelled() {
+            return None;
+        }
 
-        Solution::new(best_hash.d, best_nonce.to_le_bytes())
+        // Calculate hashrate
+        let hashrate = (total_hashes as f64 / timer.elapsed().as_secs_f64()) as u64;
+        Some((
+            Solution::new(best_hash.d, best_nonce.to_le_bytes()),
+            hashrate,
+            total_hashes,
+        ))
     }
 
     pub fn check_num_cores(&self, threads: u64) {
         // Check num threads
         let num_cores = num_cpus::get() as u64;
         if threads.gt(&num_cores) {
-            println!(
-                "{} Number of threads ({}) exceeds available cores ({})",
-                "WARNING".bold().yellow(),
-                threads,
-                num_cores
+            warn!(
+                "Number of threads ({}) exceeds available cores ({})",
+                threads, num_cores
             );
         }
     }
 
-    async fn should_reset(&self, config: Config) -> bool {
-        let clock = get_clock(&self.rpc_client).await;
-        config
-            .last_reset_at
-            .saturating_add(EPOCH_DURATION)
-            .saturating_sub(5) // Buffer
-            .le(&clock.unix_timestamp)
+    /// Waits out the seconds around an epoch reset, when submissions often fail, and
+    /// returns whether it waited, since the state is stale after the wait.
+    pub async fn wait_out_reset(&self, state: &MiningState, guard: u64) -> Result<bool, OreError> {
+        if guard.eq(&0) {
+            return Ok(false);
+        }
+        let reset_at = state.config.last_reset_at.saturating_add(EPOCH_DURATION);
+        let guard = guard as i64;
+        let now = clock_drift::cluster_now();
+        if now.lt(&reset_at.saturating_sub(guard)) || now.ge(&reset_at.saturating_add(guard)) {
+            return Ok(false);
+        }
+        let wait = reset_at.saturating_add(guard).saturating_sub(now) as u64;
+        debug!("Waiting {}s for the epoch reset before submitting", wait);
+        self.sleep(Duration::from_secs(wait)).await?;
+        Ok(true)
     }
+}
+
+pub fn should_reset(config: Config) -> bool {
+    config
+        .last_reset_at
+        .saturating_add(EPOCH_DURATION)
+        .saturating_sub(5) // Buffer
+        .le(&clock_drift::cluster_now())
+}
+
+/// Returns the number of seconds until the epoch resets and the busses are replenished.
+fn get_reset_countdown(config: Config) -> u64 {
+    config
+        .last_reset_at
+        .saturating_add(EPOCH_DURATION)
+        .saturating_sub(clock_drift::cluster_now())
+        .max(0) as u64
+}
+
+fn get_cutoff(proof: Proof, buffer_time: u64) -> u64 {
+    proof
+        .last_hash_at
+        .saturating_add(60)
+        .saturating_sub(buffer_time as i64)
+        .saturating_sub(clock_drift::cluster_now())
+        .max(0) as u64
+}
+
+/// Strategy for picking the bus to submit a hash to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BusStrategy {
+    Random,
+    MaxBalance,
+    RoundRobin,
+    Fixed(usize),
+}
 
-    async fn get_cutoff(&self, proof: Proof, buffer_time: u64) -> u64 {
-        let clock = get_clock(&self.rpc_client).await;
-        proof
-            .last_hash_at
-            .saturating_add(60)
-            .saturating_sub(buffer_time as i64)
-            .saturating_sub(clock.unix_timestamp)
-            .max(0) as u64
+impl FromStr for BusStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Self::Random),
+            "max-balance" => Ok(Self::MaxBalance),
+            "round-robin" => Ok(Self::RoundRobin),
+            _ => {
+                let Some(id) = s.strip_prefix("fixed:") else {
+                    return Err(format!("Unknown bus strategy: {}", s));
+                };
+                match id.parse::<usize>() {
+                    Ok(id) if id < BUS_COUNT => Ok(Self::Fixed(id)),
+                    _ => Err(format!("Bus id must be between 0 and {}", BUS_COUNT - 1)),
+                }
+            }
+        }
     }
 }
 
-fn calculate_multiplier(balance: u64, top_balance: u64) -> f64 {
-    1.0 + (balance as f64 / top_balance as f64).min(1.0f64)
+/// Estimates the payout of a hash at the given difficulty, including the stake multiplier.
+pub fn estimate_reward(config: Config, proof: Proof, difficulty: u32) -> u64 {
+    let base = config.base_reward_rate.saturating_mul(
+        2u64.saturating_pow(difficulty.saturating_sub(config.min_difficulty as u32)),
+    );
+    (base as f64 * calculate_multiplier(proof.balance, config.top_balance)) as u64
 }
 
-// TODO Pick a better strategy (avoid draining bus)
-fn find_bus() -> Pubkey {
-    let i = rand::thread_rng().gen_range(0..BUS_COUNT);
-    BUS_ADDRESSES[i]
+/// Picks a bus according to the strategy. If the chosen bus cannot cover the reward,
+/// falls back to another bus that can, or the bus with the largest balance.
+pub fn find_bus(strategy: BusStrategy, busses: &[Bus], reward: u64, round: usize) -> Pubkey {
+    let can_cover = |id: usize| {
+        busses
+            .iter()
+            .any(|b| b.id == id as u64 && b.rewards >= reward)
+    };
+    let max_balance = busses
+        .iter()
+        .max_by_key(|b| b.rewards)
+        .map(|b| b.id as usize)
+        .unwrap_or(0);
+    let chosen = match strategy {
+        BusStrategy::Random => rand::thread_rng().gen_range(0..BUS_COUNT),
+        BusStrategy::MaxBalance => max_balance,
+        BusStrategy::RoundRobin => round % BUS_COUNT,
+        BusStrategy::Fixed(id) => id,
+    };
+    if can_cover(chosen) || busses.is_empty() {
+        return program::bus_addresses()[chosen];
+    }
+
+    // Retry with a different bus
+    let retry = match strategy {
+        BusStrategy::Random => {
+            let candidates: Vec<usize> = (0..BUS_COUNT).filter(|id| can_cover(*id)).collect();
+            if candidates.is_empty() {
+                max_balance
+            } else {
+                candidates[rand::thread_rng().gen_range(0..candidates.len())]
+            }
+        }
+        BusStrategy::RoundRobin => (1..BUS_COUNT)
+            .map(|i| (chosen + i) % BUS_COUNT)
+            .find(|id| can_cover(*id))
+            .unwrap_or(max_balance),
+        BusStrategy::MaxBalance | BusStrategy::Fixed(_) => max_balance,
+    };
+    warn!(
+        "Bus {} cannot cover the reward of {} ORE, using bus {}",
+        chosen,
+        amount_u64_to_string(reward),
+        retry
+    );
+    program::bus_addresses()[retry]
 }
@@ -0,0 +1,671 @@
+use std::{
+    sync::{atomic::Ordering, RwLock},
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use solana_program::native_token::{lamports_to_sol, sol_to_lamports};
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+use crate::{
+    args::NotifyArgs,
+    control::control,
+    metrics::{metrics, status},
+    price::fetch_usd_prices,
+    smtp::Smtp,
+    timeout::http_timeout,
+    utils::amount_u64_to_string,
+};
+
+/// How often to check the ORE price for price alerts.
+const PRICE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The kinds of events that can trigger a notification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EventKind {
+    Landed,
+    Claim,
+    LowSol,
+    RpcDown,
+    Stalled,
+    Stopped,
+    Unprofitable,
+    Price,
+}
+
+/// A notable event during mining.
+#[derive(Clone, Debug)]
+pub enum Event {
+    Landed {
+        signature: String,
+        difficulty: u32,
+        reward: u64,
+    },
+    Claim {
+        signature: String,
+        amount: u64,
+    },
+    LowSol {
+        balance: u64,
+    },
+    RpcDown {
+        error: String,
+    },
+    Stalled {
+        rounds: u64,
+    },
+    NoLanding {
+        minutes: u64,
+    },
+    Stopped {
+        reason: String,
+    },
+    Unprofitable {
+        margin: f64,
+        rounds: u64,
+    },
+    PriceAbove {
+        price: f64,
+        threshold: f64,
+    },
+    PriceBelow {
+        price: f64,
+        threshold: f64,
+    },
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Landed { .. } => EventKind::Landed,
+            Event::Claim { .. } => EventKind::Claim,
+            Event::LowSol { .. } => EventKind::LowSol,
+            Event::RpcDown { .. } => EventKind::RpcDown,
+            Event::Stalled { .. } | Event::NoLanding { .. } => EventKind::Stalled,
+            Event::Stopped { .. } => EventKind::Stopped,
+            Event::Unprofitable { .. } => EventKind::Unprofitable,
+            Event::PriceAbove { .. } | Event::PriceBelow { .. } => EventKind::Price,
+        }
+    }
+
+    /// Returns the event name used in payloads, e.g. `low-sol`.
+    pub fn name(&self) -> String {
+        self.kind()
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Event::Landed { .. } => "Transaction landed",
+            Event::Claim { .. } => "Claim completed",
+            Event::LowSol { .. } => "Low SOL balance",
+            Event::RpcDown { .. } => "RPC unavailable",
+            Event::Stalled { .. } => "Miner stalled",
+            Event::NoLanding { .. } => "No transaction landed",
+            Event::Stopped { .. } => "Miner stopped",
+            Event::Unprofitable { .. } => "Mining paused as unprofitable",
+            Event::PriceAbove { .. } => "ORE price above threshold",
+            Event::PriceBelow { .. } => "ORE price below threshold",
+        }
+    }
+
+    /// Returns whether the event needs the operator's attention.
+    pub fn is_alert(&self) -> bool {
+        matches!(
+            self,
+            Event::LowSol { .. }
+                | Event::RpcDown { .. }
+                | Event::Stalled { .. }
+                | Event::NoLanding { .. }
+                | Event::Stopped { .. }
+                | Event::Unprofitable { .. }
+        )
+    }
+
+    /// Returns whether the event is severe enough to alert by email.
+    pub fn is_critical(&self) -> bool {
+        matches!(
+            self,
+            Event::LowSol { .. }
+                | Event::Stalled { .. }
+                | Event::NoLanding { .. }
+                | Event::Stopped { .. }
+        )
+    }
+
+    /// Returns the human readable details of the event.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Event::Landed {
+                signature,
+                difficulty,
+                reward,
+            } => vec![
+                ("Difficulty", difficulty.to_string()),
+                ("Reward", format!("{} ORE", amount_u64_to_string(*reward))),
+                ("Signature", signature.clone()),
+            ],
+            Event::Claim { signature, amount } => vec![
+                ("Amount", format!("{} ORE", amount_u64_to_string(*amount))),
+                ("Signature", signature.clone()),
+            ],
+            Event::LowSol { balance } => {
+                vec![("Balance", format!("{} SOL", lamports_to_sol(*balance)))]
+            }
+            Event::RpcDown { error } => vec![("Error", error.clone())],
+            Event::Stalled { rounds } => {
+                vec![("Rounds without a landed transaction", rounds.to_string())]
+            }
+            Event::NoLanding { minutes } => {
+                vec![("Minutes since a transaction landed", minutes.to_string())]
+            }
+            Event::Stopped { reason } => vec![("Reason", reason.clone())],
+            Event::Unprofitable { margin, rounds } => vec![
+                ("Expected margin", format!("{:.1}%", margin)),
+                ("Rounds below the minimum", rounds.to_string()),
+            ],
+            Event::PriceAbove { price, threshold } | Event::PriceBelow { price, threshold } => {
+                vec![
+                    ("Price", format!("${:.4}", price)),
+                    ("Threshold", format!("${:.4}", threshold)),
+                ]
+            }
+        }
+    }
+
+    /// Returns the details of the event on a single line.
+    pub fn message(&self) -> String {
+        self.fields()
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Returns the raw event data, with amounts in grains and lamports.
+    pub fn data(&self) -> Value {
+        match self {
+            Event::Landed {
+                signature,
+                difficulty,
+                reward,
+            } => json!({ "signature": signature, "difficulty": difficulty, "reward": reward }),
+            Event::Claim { signature, amount } => {
+                json!({ "signature": signature, "amount": amount })
+            }
+            Event::LowSol { balance } => json!({ "balance": balance }),
+            Event::RpcDown { error } => json!({ "error": error }),
+            Event::Stalled { rounds } => json!({ "rounds": rounds }),
+            Event::NoLanding { minutes } => json!({ "minutes": minutes }),
+            Event::Stopped { reason } => json!({ "reason": reason }),
+            Event::Unprofitable { margin, rounds } => {
+                json!({ "margin": margin, "rounds": rounds })
+            }
+            Event::PriceAbove { price, threshold } | Event::PriceBelow { price, threshold } => {
+                json!({ "price": price, "threshold": threshold })
+            }
+        }
+    }
+
+    /// Returns the fields available to payload templates.
+    fn template_fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+        fields.insert("event".to_string(), self.name().into());
+        fields.insert("title".to_string(), self.title().into());
+        fields.insert("message".to_string(), self.message().into());
+        fields.insert("url".to_string(), self.url().into());
+        fields.insert(
+            "timestamp".to_string(),
+            chrono::Utc::now().timestamp().into(),
+        );
+        if let Value::Object(data) = self.data() {
+            fields.extend(data);
+        }
+        fields.insert("data".to_string(), self.data());
+        fields
+    }
+
+    /// Returns a block explorer link for events with a transaction.
+    pub fn url(&self) -> Option<String> {
+        match self {
+            Event::Landed { signature, .. } | Event::Claim { signature, .. } => {
+                Some(format!("https://solscan.io/tx/{}", signature))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Sends events to the configured notification channels.
+pub struct Notifier {
+    events: Vec<EventKind>,
+    min_difficulty: u32,
+    discord: Option<String>,
+    telegram: Option<Telegram>,
+    webhooks: Vec<String>,
+    template: Option<Value>,
+    smtp: Option<Smtp>,
+    /// The fee payer balance below which to alert, in lamports.
+    min_sol_balance: u64,
+    stall_rounds: u64,
+    price_above: Option<f64>,
+    price_below: Option<f64>,
+}
+
+/// A Telegram bot and the chat it talks to.
+#[derive(Clone)]
+struct Telegram {
+    token: String,
+    chat_id: String,
+}
+
+static NOTIFIER: RwLock<Option<Notifier>> = RwLock::new(None);
+
+/// Configures notifications for the rest of the process, and starts watching the price
+/// and answering bot commands.
+pub fn init(args: &NotifyArgs) {
+    configure(args);
+
+    // Watch the ORE price
+    tokio::spawn(async move { watch_price().await });
+
+    // Answer bot commands
+    if args.telegram_commands {
+        let telegram = NOTIFIER
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|n| n.telegram.clone());
+        if let Some(telegram) = telegram {
+            tokio::spawn(async move { answer_telegram_commands(telegram).await });
+        }
+    }
+}
+
+/// Replaces the notification settings, e.g. when the config file is reloaded.
+pub fn configure(args: &NotifyArgs) {
+    let template = match args.notify_template.as_deref().map(load_template) {
+        Some(Ok(template)) => Some(template),
+        Some(Err(err)) => {
+            error!("Invalid notification template: {}", err);
+            None
+        }
+        None => None,
+    };
+    *NOTIFIER.write().unwrap() = Some(Notifier {
+        events: args.notify_events.clone(),
+        min_difficulty: args.notify_min_difficulty,
+        discord: args.notify_discord.clone(),
+        telegram: args
+            .telegram_token
+            .clone()
+            .zip(args.telegram_chat_id.clone())
+            .map(|(token, chat_id)| Telegram { token, chat_id }),
+        webhooks: args.notify_url.clone(),
+        template,
+        smtp: args.smtp_host.clone().map(|host| Smtp {
+            host,
+            port: args.smtp_port,
+            username: args.smtp_username.clone(),
+            password: args.smtp_password.clone(),
+            from: args.smtp_from.clone().unwrap_or_default(),
+            to: args.smtp_to.clone(),
+        }),
+        min_sol_balance: sol_to_lamports(args.notify_low_sol),
+        stall_rounds: args.notify_stall_rounds,
+        price_above: args.alert_price_above,
+        price_below: args.alert_price_below,
+    });
+}
+
+/// Sends the event to every notification channel in the background, if it passes the
+/// event filters.
+pub fn notify(event: Event) {
+    dispatch(event);
+}
+
+/// Sends the event like [`notify`], and waits for the notifications to be delivered.
+pub async fn notify_and_wait(event: Event) {
+    let handles = dispatch(event);
+    tokio::time::timeout(Duration::from_secs(30), futures::future::join_all(handles))
+        .await
+        .ok();
+}
+
+fn dispatch(event: Event) -> Vec<JoinHandle<()>> {
+    let mut handles = vec![];
+    let notifier = NOTIFIER.read().unwrap();
+    let Some(notifier) = notifier.as_ref() else {
+        return handles;
+    };
+    if !notifier.events.contains(&event.kind()) {
+        return handles;
+    }
+    if let Event::Landed { difficulty, .. } = event {
+        if difficulty.lt(&notifier.min_difficulty) {
+            return handles;
+        }
+    }
+    if let Some(url) = notifier.discord.clone() {
+        let event = event.clone();
+        handles.push(tokio::spawn(
+            async move { send_discord(&url, &event).await },
+        ));
+    }
+    if let Some(telegram) = notifier.telegram.clone() {
+        let text = telegram_text(&event);
+        handles.push(tokio::spawn(async move {
+            send_telegram(&telegram, &text).await
+        }));
+    }
+    if let Some(smtp) = notifier.smtp.clone().filter(|_| event.is_critical()) {
+        let subject = format!("[ORE] {}", event.title());
+        let body = event
+            .fields()
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("\n");
+        handles.push(tokio::task::spawn_blocking(move || {
+            if let Err(err) = smtp.send(&subject, &body) {
+                warn!("Failed to send email notification: {}", err);
+            }
+        }));
+    }
+    if !notifier.webhooks.is_empty() {
+        let body = match &notifier.template {
+            Some(template) => render_template(template, &event.template_fields()),
+            None => json!({
+                "event": event.name(),
+                "title": event.title(),
+                "message": event.message(),
+                "url": event.url(),
+                "timestamp": chrono::Utc::now().timestamp(),
+                "data": event.data(),
+            }),
+        };
+        for url in notifier.webhooks.clone() {
+            let body = body.clone();
+            handles.push(tokio::spawn(async move {
+                post_json("webhook", &url, &body).await
+            }));
+        }
+    }
+    handles
+}
+
+/// Loads a JSON payload template, either inline or from a file when prefixed with `@`.
+fn load_template(template: &str) -> Result<Value, String> {
+    let template = match template.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?,
+        None => template.to_string(),
+    };
+    serde_json::from_str(&template).map_err(|err| err.to_string())
+}
+
+/// Replaces `{{field}}` placeholders in every string of the template. A string that is
+/// only a placeholder is replaced by the raw value, so numbers stay numbers.
+fn render_template(template: &Value, fields: &Map<String, Value>) -> Value {
+    match template {
+        Value::String(s) => {
+            let trimmed = s.trim();
+            if let Some(name) = trimmed
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+            {
+                if let Some(value) = fields.get(name.trim()) {
+                    return value.clone();
+                }
+            }
+            let mut rendered = s.clone();
+            for (name, value) in fields {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => String::new(),
+                    value => value.to_string(),
+                };
+                rendered = rendered.replace(&format!("{{{{{}}}}}", name), &value);
+            }
+            Value::String(rendered)
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| render_template(value, fields))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), render_template(value, fields)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+async fn post_json(channel: &str, url: &str, body: &Value) {
+    let result = reqwest::Client::new()
+        .post(url)
+        .timeout(http_timeout())
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = result {
+        warn!("Failed to send {} notification: {}", channel, err);
+    }
+}
+
+async fn send_discord(url: &str, event: &Event) {
+    let color = if event.is_alert() { 0xe74c3c } else { 0x2ecc71 };
+    let fields: Vec<Value> = event
+        .fields()
+        .into_iter()
+        .map(
+            |(name, value)| json!({ "name": name, "value": value, "inline": name.ne("Signature") }),
+        )
+        .collect();
+    let body = json!({
+        "embeds": [{
+            "title": event.title(),
+            "url": event.url(),
+            "color": color,
+            "fields": fields,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }]
+    });
+    post_json("Discord", url, &body).await;
+}
+
+fn telegram_text(event: &Event) -> String {
+    let mut text = format!("<b>{}</b>", event.title());
+    for (name, value) in event.fields() {
+        text.push_str(&format!("\n{}: {}", name, html_escape(&value)));
+    }
+    if let Some(url) = event.url() {
+        text.push_str(&format!("\n<a href=\"{}\">View on Solscan</a>", url));
+    }
+    text
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+async fn send_telegram(telegram: &Telegram, text: &str) {
+    let url = format!("https://api.telegram.org/bot{}/sendMessage", telegram.token);
+    let body = json!({
+        "chat_id": telegram.chat_id,
+        "text": text,
+        "parse_mode": "HTML",
+        "disable_web_page_preview": true,
+    });
+    post_json("Telegram", &url, &body).await;
+}
+
+/// Polls the ORE price and notifies when it crosses above or below the thresholds. Each
+/// alert fires again only after the price has crossed back.
+async fn watch_price() {
+    let (mut is_above, mut is_below) = (false, false);
+    loop {
+        let (above, below) = NOTIFIER
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|n| (n.price_above, n.price_below))
+            .unwrap_or_default();
+        if above.is_none() && below.is_none() {
+            tokio::time::sleep(PRICE_CHECK_INTERVAL).await;
+            continue;
+        }
+        if let Some(prices) = fetch_usd_prices().await {
+            if let Some(threshold) = above {
+                if prices.ore.gt(&threshold) && !is_above {
+                    notify(Event::PriceAbove {
+                        price: prices.ore,
+                        threshold,
+                    });
+                }
+                is_above = prices.ore.gt(&threshold);
+            }
+            if let Some(threshold) = below {
+                if prices.ore.lt(&threshold) && !is_below {
+                    notify(Event::PriceBelow {
+                        price: prices.ore,
+                        threshold,
+                    });
+                }
+                is_below = prices.ore.lt(&threshold);
+            }
+        }
+        tokio::time::sleep(PRICE_CHECK_INTERVAL).await;
+    }
+}
+
+/// Long-polls the bot for `/status` and `/claim` commands from the configured chat.
+async fn answer_telegram_commands(telegram: Telegram) {
+    let client = reqwest::Client::new();
+    let url = format!("https://api.telegram.org/bot{}/getUpdates", telegram.token);
+    let mut offset = 0;
+    loop {
+        let response = client
+            .get(&url)
+            .query(&[
+                ("offset", offset.to_string()),
+                ("timeout", "30".to_string()),
+            ])
+            .timeout(Duration::from_secs(40))
+            .send()
+            .await;
+        let updates = match response {
+            Ok(response) => response.json::<Value>().await.unwrap_or_default(),
+            Err(err) => {
+                warn!("Failed to fetch Telegram updates: {}", err);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            }
+        };
+        for update in updates["result"].as_array().into_iter().flatten() {
+            offset = offset.max(update["update_id"].as_i64().unwrap_or(0) + 1);
+            let message = &update["message"];
+            if message["chat"]["id"].to_string().ne(&telegram.chat_id) {
+                continue;
+            }
+            let reply = match message["text"]
+                .as_str()
+                .unwrap_or_default()
+                .split_whitespace()
+                .next()
+            {
+                Some(command) if command.starts_with("/status") => status_text(),
+                Some(command) if command.starts_with("/claim") => {
+                    control().request_claim();
+                    "Claiming rewards before the next round".to_string()
+                }
+                _ => continue,
+            };
+            send_telegram(&telegram, &reply).await;
+        }
+    }
+}
+
+fn status_text() -> String {
+    let metrics = metrics();
+    let status = status();
+    format!(
+        "<b>Status</b>\nRound: {}{}\nHashrate: {} H/sec\nBest difficulty: {} ({} this session)\nTransactions: {} landed, {} expired\nRewards: {} ORE\nUnclaimed: {} ORE\nFees: {} SOL",
+        status.round,
+        if control().is_paused() { " (paused)" } else { "" },
+        metrics.hashrate.load(Ordering::Relaxed),
+        metrics.best_difficulty.load(Ordering::Relaxed),
+        status.session_best_difficulty,
+        metrics.landed.load(Ordering::Relaxed),
+        metrics.expired.load(Ordering::Relaxed),
+        amount_u64_to_string(metrics.rewards.load(Ordering::Relaxed)),
+        amount_u64_to_string(metrics.unclaimed_balance.load(Ordering::Relaxed)),
+        lamports_to_sol(metrics.fees.load(Ordering::Relaxed)),
+    )
+}
+
+/// Tracks the health of the miner across rounds, so each alert fires once per incident.
+#[derive(Default)]
+pub struct Health {
+    low_sol: bool,
+    rpc_down: bool,
+    failed_rounds: u64,
+}
+
+impl Health {
+    /// Records the fee payer balance, or the RPC error from fetching it.
+    pub fn observe_balance(&mut self, balance: Result<u64, String>) {
+        let min_balance = NOTIFIER
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |n| n.min_sol_balance);
+        match balance {
+            Ok(balance) => {
+                self.rpc_down = false;
+                if balance.lt(&min_balance) {
+                    if !self.low_sol {
+                        notify(Event::LowSol { balance });
+                    }
+                    self.low_sol = true;
+                } else {
+                    self.low_sol = false;
+                }
+            }
+            Err(error) => {
+                if !self.rpc_down {
+                    notify(Event::RpcDown { error });
+                }
+                self.rpc_down = true;
+            }
+        }
+    }
+
+    /// Records whether the round's transaction landed.
+    pub fn observe_round(&mut self, landed: bool) {
+        let stall_rounds = NOTIFIER
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |n| n.stall_rounds);
+        if landed {
+            self.failed_rounds = 0;
+            return;
+        }
+        self.failed_rounds += 1;
+        if self.failed_rounds.eq(&stall_rounds) {
+            notify(Event::Stalled {
+                rounds: self.failed_rounds,
+            });
+        }
+    }
+}
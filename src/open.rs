@@ -1,22 +1,89 @@
-use solana_sdk::signature::Signer;
+use std::str::FromStr;
 
-use crate::{send_and_confirm::ComputeBudget, utils::proof_pubkey, Miner};
+use colored::*;
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    args::OpenArgs,
+    error::OreError,
+    exit,
+    output::{finish_batch, new_batch_progress, print_status},
+    send_and_confirm::ComputeBudget,
+    utils::{keypair_files, proof_pubkey},
+    Miner,
+};
 
 impl Miner {
-    pub async fn open(&self) {
+    pub async fn open(&self) -> Result<(), OreError> {
         // Return early if miner is already registered
-        let signer = self.signer();
-        let fee_payer = self.fee_payer();
+        let signer = self.signer()?;
+        let proof_address = proof_pubkey(signer.pubkey());
+        if self.rpc_client.get_account(&proof_address).await.is_ok() {
+            return Ok(());
+        }
+        self.open_with_miner(signer.pubkey()).await
+    }
+
+    pub async fn open_proofs(&self, args: OpenArgs) -> Result<(), OreError> {
+        // Open a proof for each keypair in the directory
+        if let Some(dir) = args.keypair_dir {
+            let filepaths = keypair_files(&dir).map_err(|err| OreError::ReadDir {
+                path: dir.clone(),
+                reason: err.to_string(),
+            })?;
+            if filepaths.is_empty() {
+                println!("No keypairs found in {}", dir);
+                return Ok(());
+            }
+            let progress_bar = new_batch_progress(filepaths.len());
+            for filepath in filepaths {
+                let miner = self.with_keypair(filepath.clone());
+                let pubkey = miner.signer()?.pubkey();
+                progress_bar.set_message(format!("{} ({})", pubkey, filepath));
+                if let Err(err) = miner.open().await {
+                    print_status(format!(
+                        "{} Failed to open a proof for {}: {}",
+                        "ERROR".bold().red(),
+                        pubkey,
+                        err
+                    ));
+                    exit::set(err.exit_code());
+                }
+                progress_bar.inc(1);
+            }
+            finish_batch(&progress_bar);
+            return Ok(());
+        }
+
+        // Open a proof for the signer
+        let miner = match args.miner {
+            Some(address) => {
+                Pubkey::from_str(&address).map_err(|_| OreError::InvalidAddress(address))?
+            }
+            None => self.signer()?.pubkey(),
+        };
+        self.open_with_miner(miner).await
+    }
+
+    async fn open_with_miner(&self, miner: Pubkey) -> Result<(), OreError> {
+        // Return early if proof already exists
+        let signer = self.signer()?;
+        let fee_payer = self.fee_payer()?;
         let proof_address = proof_pubkey(signer.pubkey());
         if self.rpc_client.get_account(&proof_address).await.is_ok() {
-            return;
+            print_status(format!(
+                "{} Proof {} already exists",
+                "OK".bold().green(),
+                proof_address
+            ));
+            return Ok(());
         }
 
         // Sign and send transaction.
-        println!("Generating challenge...");
-        let ix = ore_api::instruction::open(signer.pubkey(), signer.pubkey(), fee_payer.pubkey());
+        print_status("Generating challenge...".to_string());
+        let ix = ore_api::instruction::open(signer.pubkey(), miner, fee_payer.pubkey());
         self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
-            .await
-            .ok();
+            .await?;
+        Ok(())
     }
 }
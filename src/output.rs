@@ -0,0 +1,127 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use clap::ValueEnum;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use solana_rpc_client::spinner;
+
+use crate::schema::{Document, Envelope, SCHEMA_VERSION};
+
+static HIDE_PROGRESS: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether stdout is reserved for JSON documents, set by `--output json`.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// The running batch operation, if any. Spinners are drawn above its progress bar.
+static BATCH: Mutex<Option<(MultiProgress, ProgressBar)>> = Mutex::new(None);
+
+/// How command results are written to stdout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Prints a single-line JSON document tagged with its type and the schema version.
+pub fn print_json<T: Document>(document: &T) {
+    let envelope = Envelope {
+        schema_version: SCHEMA_VERSION,
+        kind: T::KIND,
+        document,
+    };
+    println!("{}", serde_json::to_string(&envelope).unwrap_or_default());
+}
+
+/// Hides progress spinners for the rest of the process, e.g. while a dashboard owns the
+/// terminal.
+pub fn hide_progress() {
+    HIDE_PROGRESS.store(true, Ordering::Relaxed);
+}
+
+/// Hides progress spinners, so only their final results are printed.
+pub fn set_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Reserves stdout for JSON documents, so status lines go to stderr.
+pub fn set_json() {
+    JSON.store(true, Ordering::Relaxed);
+}
+
+/// Prints a status line to stdout, or to stderr when stdout holds JSON documents.
+fn print_line(line: &str) {
+    match JSON.load(Ordering::Relaxed) {
+        true => eprintln!("{}", line),
+        false => println!("{}", line),
+    }
+}
+
+/// Returns a new progress spinner, unless spinners are hidden.
+pub fn new_progress_bar() -> ProgressBar {
+    let progress_bar = spinner::new_progress_bar();
+    if HIDE_PROGRESS.load(Ordering::Relaxed) || QUIET.load(Ordering::Relaxed) {
+        progress_bar.set_draw_target(ProgressDrawTarget::hidden());
+    } else if let Some((multi, batch)) = BATCH.lock().unwrap().as_ref() {
+        return multi.insert_before(batch, progress_bar);
+    }
+    progress_bar
+}
+
+/// Starts a progress bar with an ETA for a batch of items. Spinners for each item are
+/// drawn above it until [`finish_batch`] is called.
+pub fn new_batch_progress(len: usize) -> ProgressBar {
+    let progress_bar = ProgressBar::new(len as u64).with_style(
+        ProgressStyle::with_template("[{bar:30.cyan/blue}] {pos}/{len} (ETA {eta}) {msg}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    if HIDE_PROGRESS.load(Ordering::Relaxed) || QUIET.load(Ordering::Relaxed) {
+        progress_bar.set_draw_target(ProgressDrawTarget::hidden());
+        return progress_bar;
+    }
+    let multi = MultiProgress::new();
+    let progress_bar = multi.add(progress_bar);
+    *BATCH.lock().unwrap() = Some((multi, progress_bar.clone()));
+    progress_bar
+}
+
+/// Prints a line of status, above the batch progress bar if one is drawn.
+pub fn print_status(line: String) {
+    match BATCH.lock().unwrap().as_ref() {
+        Some((_, batch)) if !batch.is_hidden() => batch.println(line),
+        _ => print_line(&line),
+    }
+}
+
+/// Removes the batch progress bar.
+pub fn finish_batch(progress_bar: &ProgressBar) {
+    progress_bar.finish_and_clear();
+    BATCH.lock().unwrap().take();
+}
+
+/// Finishes a progress spinner with its final result. If the spinner is not drawn, e.g. in
+/// quiet mode or when stderr is not a terminal, the result is printed to stdout instead, or
+/// to stderr with `--output json`.
+pub fn finish_progress(progress_bar: &ProgressBar, message: String) {
+    if let Some((multi, batch)) = BATCH.lock().unwrap().as_ref() {
+        // Keep finished spinners from piling up above the batch progress bar
+        progress_bar.finish_and_clear();
+        multi.remove(progress_bar);
+        match batch.is_hidden() {
+            true => print_line(&message),
+            false => batch.println(message),
+        }
+        return;
+    }
+    if progress_bar.is_hidden() && !HIDE_PROGRESS.load(Ordering::Relaxed) {
+        print_line(&message);
+    }
+    progress_bar.finish_with_message(message);
+}
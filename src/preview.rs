@@ -0,0 +1,54 @@
+use std::fmt::Display;
+
+use colored::*;
+use solana_program::native_token::lamports_to_sol;
+
+use crate::utils::ask_confirm;
+
+/// A summary of the accounts and amounts a transaction will touch, shown before it is
+/// sent.
+pub struct Preview {
+    title: String,
+    rows: Vec<(String, String)>,
+}
+
+impl Preview {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            rows: vec![],
+        }
+    }
+
+    pub fn row(mut self, label: &str, value: impl Display) -> Self {
+        self.rows.push((label.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn fee(self, lamports: u64) -> Self {
+        self.row(
+            "Estimated fee",
+            format!("{} SOL", lamports_to_sol(lamports)),
+        )
+    }
+
+    /// Prints the preview to stderr, keeping stdout parseable.
+    pub fn print(&self) {
+        let width = self.rows.iter().map(|(label, _)| label.len()).max();
+        eprintln!("\n{}", self.title.bold());
+        for (label, value) in self.rows.iter() {
+            eprintln!(
+                "  {:width$}  {}",
+                format!("{}:", label),
+                value,
+                width = width.unwrap_or(0) + 1
+            );
+        }
+    }
+
+    /// Prints the preview and asks to continue. Always true under `--yes`.
+    pub fn confirm(&self) -> bool {
+        self.print();
+        ask_confirm("\nAre you sure you want to continue? [Y/n]")
+    }
+}
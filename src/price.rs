@@ -0,0 +1,67 @@
+use serde_json::Value;
+
+use crate::timeout::http_client;
+
+const COINGECKO_API_URL: &str = "https://api.coingecko.com/api/v3";
+const COINGECKO_ORE_ID: &str = "ore";
+const COINGECKO_SOL_ID: &str = "solana";
+
+/// Current USD prices of ORE and SOL.
+#[derive(Clone, Copy, Debug)]
+pub struct Prices {
+    pub ore: f64,
+    pub sol: f64,
+}
+
+/// Fetches the current USD prices of ORE and SOL.
+pub async fn fetch_usd_prices() -> Option<Prices> {
+    let response: Value = http_client()
+        .get(format!("{}/simple/price", COINGECKO_API_URL))
+        .query(&[
+            (
+                "ids",
+                format!("{},{}", COINGECKO_ORE_ID, COINGECKO_SOL_ID).as_str(),
+            ),
+            ("vs_currencies", "usd"),
+        ])
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    Some(Prices {
+        ore: response[COINGECKO_ORE_ID]["usd"].as_f64()?,
+        sol: response[COINGECKO_SOL_ID]["usd"].as_f64()?,
+    })
+}
+
+/// Returns the given prices, filling in the current market price for any that are missing.
+pub async fn resolve_prices(ore_price: Option<f64>, sol_price: Option<f64>) -> Option<Prices> {
+    let market = if ore_price.is_none() || sol_price.is_none() {
+        fetch_usd_prices().await
+    } else {
+        None
+    };
+    Some(Prices {
+        ore: ore_price.or(market.map(|p| p.ore))?,
+        sol: sol_price.or(market.map(|p| p.sol))?,
+    })
+}
+
+/// Fetches the USD price of ORE on a date formatted as `dd-mm-yyyy`.
+pub async fn fetch_usd_price_at(date: &str) -> Option<f64> {
+    let response: Value = http_client()
+        .get(format!(
+            "{}/coins/{}/history",
+            COINGECKO_API_URL, COINGECKO_ORE_ID
+        ))
+        .query(&[("date", date), ("localization", "false")])
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    response["market_data"]["current_price"]["usd"].as_f64()
+}
@@ -0,0 +1,62 @@
+use tracing::error;
+
+use crate::{
+    args::{ProfileArgs, ProfileCommand},
+    output::print_json,
+    schema::ProfilesDocument,
+    settings, Miner,
+};
+
+impl Miner {
+    pub async fn profile(&self, args: ProfileArgs) {
+        let table = settings::load().unwrap_or_default();
+        let profiles = settings::profiles(&table);
+        let default = settings::default_profile(&table);
+        match args.command {
+            ProfileCommand::List => {
+                if self.is_json() {
+                    print_json(&ProfilesDocument { profiles, default });
+                    return;
+                }
+                if profiles.is_empty() {
+                    println!("No profiles found in {}", settings::path().display());
+                    return;
+                }
+                for profile in profiles.iter() {
+                    let marker = if default.as_ref().eq(&Some(profile)) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{} {}", marker, profile);
+                }
+            }
+            ProfileCommand::SetDefault(args) => {
+                if !profiles.contains(&args.name) {
+                    error!(
+                        "Unknown profile {:?} in {}",
+                        args.name,
+                        settings::path().display()
+                    );
+                    return;
+                }
+
+                // Edit the file in place to keep comments and formatting
+                let path = settings::path();
+                let contents = std::fs::read_to_string(&path).unwrap_or_default();
+                let mut document = match contents.parse::<toml_edit::DocumentMut>() {
+                    Ok(document) => document,
+                    Err(err) => {
+                        error!("Could not parse {}: {}", path.display(), err);
+                        return;
+                    }
+                };
+                document["default_profile"] = toml_edit::value(args.name.clone());
+                match std::fs::write(&path, document.to_string()) {
+                    Ok(_) => println!("Default profile set to {}", args.name),
+                    Err(err) => error!("Failed to write {}: {}", path.display(), err),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use ore_api::state::{Config, Proof};
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use tracing::{info, warn};
+
+use crate::{
+    error::OreError,
+    healthcheck, ledger,
+    mine::estimate_reward,
+    notify::{notify, Event},
+    price::{fetch_usd_prices, Prices},
+    utils::{amount_u64_to_f64, get_config, get_proof_with_authority},
+    Miner,
+};
+
+/// How often to check whether mining became profitable again while paused.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Pauses mining when the expected value of a round doesn't cover its fees.
+pub struct ProfitGuard {
+    min_margin: f64,
+    max_rounds: u64,
+    rounds_below: u64,
+    prices: Option<Prices>,
+}
+
+impl ProfitGuard {
+    /// Trips after `max_rounds` consecutive rounds with an expected margin below
+    /// `min_margin` percent.
+    pub fn new(min_margin: f64, max_rounds: u64) -> Self {
+        Self {
+            min_margin,
+            max_rounds,
+            rounds_below: 0,
+            prices: None,
+        }
+    }
+
+    /// Returns the expected profit margin of a round in percent, as the share of the
+    /// expected reward left after fees. The reward is weighted by how often past rounds
+    /// reached each difficulty. None if prices or past rounds are unavailable.
+    pub async fn margin(&mut self, config: Config, proof: Proof, fee: u64) -> Option<f64> {
+        match fetch_usd_prices().await {
+            Some(prices) => self.prices = Some(prices),
+            None if self.prices.is_some() => warn!("Failed to fetch prices, using the last ones"),
+            None => {
+                warn!("Failed to fetch prices, skipping the profitability check");
+                return None;
+            }
+        }
+        let prices = self.prices?;
+        let histogram = ledger::difficulty_histogram(Some(&proof.authority.to_string()));
+        let rounds = histogram.iter().map(|(_, count)| count).sum::<u64>();
+        if rounds.eq(&0) {
+            return None;
+        }
+        let expected_reward = histogram
+            .iter()
+            .map(|(difficulty, count)| {
+                amount_u64_to_f64(estimate_reward(config, proof, *difficulty)) * *count as f64
+            })
+            .sum::<f64>()
+            / rounds as f64;
+        let value = expected_reward * prices.ore;
+        let cost = lamports_to_sol(fee) * prices.sol;
+        if value.le(&0.0) {
+            return None;
+        }
+        Some((value - cost) / value * 100.0)
+    }
+
+    /// Records the margin of a round and returns whether mining should pause. Alerts when
+    /// it trips.
+    pub fn observe(&mut self, margin: f64) -> bool {
+        if margin.ge(&self.min_margin) {
+            self.rounds_below = 0;
+            return false;
+        }
+        self.rounds_below += 1;
+        if self.rounds_below.eq(&self.max_rounds) {
+            notify(Event::Unprofitable {
+                margin,
+                rounds: self.rounds_below,
+            });
+        }
+        self.rounds_below.ge(&self.max_rounds)
+    }
+
+    /// Returns whether a margin is high enough to resume mining, and if so starts counting
+    /// rounds afresh.
+    fn try_resume(&mut self, margin: f64) -> bool {
+        if margin.lt(&self.min_margin) {
+            return false;
+        }
+        self.rounds_below = 0;
+        true
+    }
+}
+
+impl Miner {
+    /// Waits until the expected margin of a round at the given fee recovers.
+    pub(crate) async fn wait_until_profitable(
+        &self,
+        guard: &mut ProfitGuard,
+        authority: Pubkey,
+        fee: u64,
+    ) -> Result<(), OreError> {
+        loop {
+            healthcheck::beat();
+            self.sleep(CHECK_INTERVAL).await?;
+            let config = get_config(&self.rpc_client).await?;
+            let proof = get_proof_with_authority(&self.rpc_client, authority).await?;
+            if let Some(margin) = guard.margin(config, proof, fee).await {
+                if guard.try_resume(margin) {
+                    info!("Mining resumed, expected margin is {:.1}%", margin);
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! The addresses of the ORE program and its accounts. They default to the mainnet
+//! deployment, and `--program-id` and `--mint` point them at a fork or test deployment.
+
+use std::sync::OnceLock;
+
+use ore_api::consts::{
+    BUS, BUS_ADDRESSES, BUS_COUNT, CONFIG, CONFIG_ADDRESS, MINT, MINT_ADDRESS, MINT_NOISE, PROOF,
+    TREASURY, TREASURY_ADDRESS, TREASURY_TOKENS_ADDRESS,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+
+static PROGRAM_ID: OnceLock<Pubkey> = OnceLock::new();
+static MINT_OVERRIDE: OnceLock<Pubkey> = OnceLock::new();
+static ADDRESSES: OnceLock<Addresses> = OnceLock::new();
+
+/// The accounts of a deployment of the program.
+struct Addresses {
+    program_id: Pubkey,
+    mint: Pubkey,
+    config: Pubkey,
+    busses: [Pubkey; BUS_COUNT],
+    treasury: Pubkey,
+    treasury_tokens: Pubkey,
+}
+
+/// Targets the program deployed at the address. Must be called before any address is read.
+pub fn set_program_id(program_id: Pubkey) {
+    PROGRAM_ID.set(program_id).ok();
+}
+
+/// Uses the mint instead of the one derived from the program id. Must be called before
+/// any address is read.
+pub fn set_mint(mint: Pubkey) {
+    MINT_OVERRIDE.set(mint).ok();
+}
+
+fn addresses() -> &'static Addresses {
+    ADDRESSES.get_or_init(|| {
+        let Some(program_id) = PROGRAM_ID.get().copied() else {
+            return Addresses {
+                program_id: ore_api::ID,
+                mint: MINT_OVERRIDE.get().copied().unwrap_or(MINT_ADDRESS),
+                config: CONFIG_ADDRESS,
+                busses: BUS_ADDRESSES,
+                treasury: TREASURY_ADDRESS,
+                treasury_tokens: TREASURY_TOKENS_ADDRESS,
+            };
+        };
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &program_id).0;
+        let mint = MINT_OVERRIDE
+            .get()
+            .copied()
+            .unwrap_or_else(|| pda(&[MINT, &MINT_NOISE]));
+        let treasury = pda(&[TREASURY]);
+        Addresses {
+            program_id,
+            mint,
+            config: pda(&[CONFIG]),
+            busses: std::array::from_fn(|i| pda(&[BUS, &[i as u8]])),
+            treasury,
+            treasury_tokens: get_associated_token_address(&treasury, &mint),
+        }
+    })
+}
+
+/// Whether the addresses differ from the mainnet deployment.
+fn is_overridden() -> bool {
+    PROGRAM_ID.get().is_some() || MINT_OVERRIDE.get().is_some()
+}
+
+pub fn program_id() -> Pubkey {
+    addresses().program_id
+}
+
+pub fn mint() -> Pubkey {
+    addresses().mint
+}
+
+pub fn config_address() -> Pubkey {
+    addresses().config
+}
+
+pub fn bus_addresses() -> [Pubkey; BUS_COUNT] {
+    addresses().busses
+}
+
+pub fn treasury_address() -> Pubkey {
+    addresses().treasury
+}
+
+pub fn treasury_tokens_address() -> Pubkey {
+    addresses().treasury_tokens
+}
+
+/// Rewrites an instruction built by `ore_api` for the mainnet deployment to target the
+/// configured one. Proof accounts are rewritten for the signers of the instruction.
+pub fn retarget(mut ix: Instruction) -> Instruction {
+    if !is_overridden() || ix.program_id.ne(&ore_api::ID) {
+        return ix;
+    }
+    let addresses = addresses();
+    let mut replacements = vec![
+        (MINT_ADDRESS, addresses.mint),
+        (CONFIG_ADDRESS, addresses.config),
+        (TREASURY_ADDRESS, addresses.treasury),
+        (TREASURY_TOKENS_ADDRESS, addresses.treasury_tokens),
+    ];
+    replacements.extend(BUS_ADDRESSES.into_iter().zip(addresses.busses));
+    for signer in ix.accounts.iter().filter(|meta| meta.is_signer) {
+        let seeds: &[&[u8]] = &[PROOF, signer.pubkey.as_ref()];
+        replacements.push((
+            Pubkey::find_program_address(seeds, &ore_api::ID).0,
+            Pubkey::find_program_address(seeds, &addresses.program_id).0,
+        ));
+    }
+    ix.program_id = addresses.program_id;
+    for meta in ix.accounts.iter_mut() {
+        if let Some((_, replacement)) = replacements.iter().find(|(from, _)| from.eq(&meta.pubkey))
+        {
+            meta.pubkey = *replacement;
+        }
+    }
+    ix
+}
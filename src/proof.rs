@@ -0,0 +1,82 @@
+use std::str::FromStr;
+
+use colored::*;
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    args::ProofArgs,
+    error::OreError,
+    output::print_json,
+    schema::ProofDocument,
+    utils::{amount_u64_to_string, format_timestamp, get_proof_with_authority, proof_pubkey},
+    Miner,
+};
+
+impl Miner {
+    pub async fn proof(&self, args: ProofArgs) -> Result<(), OreError> {
+        // Parse authority
+        let authority = if let Some(address) = args.address {
+            Pubkey::from_str(&address).map_err(|_| OreError::InvalidAddress(address))?
+        } else {
+            self.signer()?.pubkey()
+        };
+
+        // Fetch proof
+        let proof_address = proof_pubkey(authority);
+        let proof = get_proof_with_authority(&self.rpc_client, authority).await?;
+
+        // Print as json
+        if args.json || self.is_json() {
+            print_json(&ProofDocument {
+                address: proof_address.to_string(),
+                authority: proof.authority.to_string(),
+                miner: proof.miner.to_string(),
+                balance: proof.balance,
+                challenge: bs58::encode(proof.challenge).into_string(),
+                last_hash: bs58::encode(proof.last_hash).into_string(),
+                last_hash_at: proof.last_hash_at,
+                last_stake_at: proof.last_stake_at,
+                total_hashes: proof.total_hashes,
+                total_rewards: proof.total_rewards,
+            });
+            return Ok(());
+        }
+
+        // Print human readable
+        println!("{}: {}", "Address".bold(), proof_address);
+        println!("{}: {}", "Authority".bold(), proof.authority);
+        println!("{}: {}", "Miner".bold(), proof.miner);
+        println!(
+            "{}: {} ORE",
+            "Balance".bold(),
+            amount_u64_to_string(proof.balance)
+        );
+        println!(
+            "{}: {}",
+            "Challenge".bold(),
+            bs58::encode(proof.challenge).into_string()
+        );
+        println!(
+            "{}: {}",
+            "Last hash".bold(),
+            bs58::encode(proof.last_hash).into_string()
+        );
+        println!(
+            "{}: {}",
+            "Last hash at".bold(),
+            format_timestamp(proof.last_hash_at)
+        );
+        println!(
+            "{}: {}",
+            "Last stake at".bold(),
+            format_timestamp(proof.last_stake_at)
+        );
+        println!("{}: {}", "Total hashes".bold(), proof.total_hashes);
+        println!(
+            "{}: {} ORE",
+            "Total rewards".bold(),
+            amount_u64_to_string(proof.total_rewards)
+        );
+        Ok(())
+    }
+}
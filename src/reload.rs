@@ -0,0 +1,57 @@
+//! Reloads the config file into a running miner, on SIGHUP or `POST /reload` to the
+//! control API. The command line is parsed again, so flags given on it still override the
+//! file. Fees and notifications change from the next transaction, and the thread count
+//! from the next round, so the current round is never dropped.
+
+use tracing::{info, warn};
+
+use crate::{args::NotifyArgs, control::control, dynamic_fee::fee_strategy, notify, settings};
+
+/// The settings of `mine` that can change while it runs.
+pub struct Settings {
+    pub priority_fee: Option<u64>,
+    pub dynamic_fee_max: Option<u64>,
+    pub dynamic_fee_strategy: Option<String>,
+    pub threads: u64,
+    pub notify: NotifyArgs,
+}
+
+/// Reloads the config file, keeping the current settings if it is invalid.
+pub fn reload() -> Result<(), String> {
+    let result = crate::cli::reload_settings().and_then(apply);
+    match &result {
+        Ok(()) => info!("Reloaded {}", settings::path().display()),
+        Err(err) => warn!("Failed to reload {}: {}", settings::path().display(), err),
+    }
+    result
+}
+
+fn apply(settings: Settings) -> Result<(), String> {
+    if let Some(strategy) = &settings.dynamic_fee_strategy {
+        if fee_strategy(strategy).is_none() {
+            return Err(format!("Unknown dynamic fee strategy: {:?}", strategy));
+        }
+    }
+    control().reload(
+        settings.priority_fee,
+        settings.dynamic_fee_max,
+        settings.dynamic_fee_strategy,
+        settings.threads,
+    );
+    notify::configure(&settings.notify);
+    Ok(())
+}
+
+/// Reloads the config file on every SIGHUP.
+#[cfg(unix)]
+pub fn watch_sighup() {
+    use tokio::signal::unix::{signal, SignalKind};
+    tokio::spawn(async {
+        let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+            return;
+        };
+        while hangup.recv().await.is_some() {
+            reload().ok();
+        }
+    });
+}
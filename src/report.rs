@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use colored::*;
+use solana_program::native_token::lamports_to_sol;
+use tracing::warn;
+
+use crate::{
+    args::ReportArgs,
+    ledger::{self, LedgerEntry, LedgerKind},
+    output::print_json,
+    price::{resolve_prices, Prices},
+    schema::{ReportDailyDocument, ReportDocument, ReportSummaryDocument, ReportWalletDocument},
+    utils::{amount_u64_to_f64, amount_u64_to_string},
+    Miner,
+};
+
+#[derive(Default)]
+struct Budget {
+    earned: u64,
+    fees: u64,
+}
+
+impl Budget {
+    fn add(&mut self, entry: &LedgerEntry) {
+        self.fees += entry.fee;
+        if entry.kind.eq(&LedgerKind::Mine) && entry.landed {
+            self.earned += entry.amount;
+        }
+    }
+
+    fn document(&self, prices: Option<Prices>) -> ReportSummaryDocument {
+        let earned_usd = prices.map(|p| amount_u64_to_f64(self.earned) * p.ore);
+        let fees_usd = prices.map(|p| lamports_to_sol(self.fees) * p.sol);
+        ReportSummaryDocument {
+            earned: self.earned,
+            fees: self.fees,
+            earned_usd,
+            fees_usd,
+            net_usd: earned_usd.zip(fees_usd).map(|(e, f)| e - f),
+        }
+    }
+
+    fn describe(&self, prices: Option<Prices>) -> String {
+        let s = format!(
+            "{} ORE earned, {} SOL fees",
+            amount_u64_to_string(self.earned),
+            lamports_to_sol(self.fees)
+        );
+        let summary = self.document(prices);
+        let (Some(earned_usd), Some(fees_usd), Some(net_usd)) =
+            (summary.earned_usd, summary.fees_usd, summary.net_usd)
+        else {
+            return s;
+        };
+        let net = format!("${:.4} net", net_usd);
+        format!(
+            "{} (${:.4} - ${:.4} = {})",
+            s,
+            earned_usd,
+            fees_usd,
+            if net_usd.ge(&0.0) {
+                net.green()
+            } else {
+                net.red()
+            }
+        )
+    }
+}
+
+impl Miner {
+    pub async fn report(&self, args: ReportArgs) {
+        // Select the entries in the window
+        let since = Utc::now().timestamp().saturating_sub(args.since as i64);
+        let entries: Vec<LedgerEntry> = ledger::load()
+            .into_iter()
+            .filter(|entry| entry.timestamp.ge(&since))
+            .collect();
+
+        // Value at the current prices
+        let prices = resolve_prices(args.ore_price, args.sol_price).await;
+        if prices.is_none() {
+            warn!("Could not fetch ORE and SOL prices. Pass --ore-price and --sol-price for USD values.");
+        }
+
+        // Summarize by day and wallet
+        let mut total = Budget::default();
+        let mut daily: BTreeMap<String, Budget> = BTreeMap::new();
+        let mut wallets: BTreeMap<String, Budget> = BTreeMap::new();
+        for entry in entries.iter() {
+            total.add(entry);
+            let date = DateTime::<Utc>::from_timestamp(entry.timestamp, 0)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            daily.entry(date).or_default().add(entry);
+            wallets
+                .entry(entry.authority.clone())
+                .or_default()
+                .add(entry);
+        }
+
+        // Print as json
+        if self.is_json() {
+            print_json(&ReportDocument {
+                since,
+                ore_price: prices.map(|p| p.ore),
+                sol_price: prices.map(|p| p.sol),
+                total: total.document(prices),
+                daily: daily
+                    .iter()
+                    .map(|(date, budget)| ReportDailyDocument {
+                        date: date.clone(),
+                        summary: budget.document(prices),
+                    })
+                    .collect(),
+                wallets: wallets
+                    .iter()
+                    .map(|(authority, budget)| ReportWalletDocument {
+                        authority: authority.clone(),
+                        summary: budget.document(prices),
+                    })
+                    .collect(),
+            });
+            return;
+        }
+
+        // Print
+        if entries.is_empty() {
+            println!("No mining activity has been recorded in this period");
+            return;
+        }
+        if let Some(prices) = prices {
+            println!(
+                "{}: ORE ${:.4}, SOL ${:.2}",
+                "Prices".bold(),
+                prices.ore,
+                prices.sol
+            );
+        }
+        println!("{}: {}", "Total".bold(), total.describe(prices));
+        println!("\n{}", "Daily".bold());
+        for (date, budget) in daily.iter().rev() {
+            println!("  {}: {}", date, budget.describe(prices));
+        }
+        if wallets.len().gt(&1) {
+            println!("\n{}", "Wallets".bold());
+            for (authority, budget) in wallets.iter() {
+                println!("  {}: {}", authority, budget.describe(prices));
+            }
+        }
+    }
+}
@@ -1,28 +1,151 @@
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
 use crate::{
-    utils::{amount_u64_to_string, get_config},
+    args::RewardsArgs,
+    error::OreError,
+    output::print_json,
+    schema::{RewardRateDocument, RewardsDocument},
+    utils::{amount_u64_to_f64, amount_u64_to_string, get_config, ore_dir},
     Miner,
 };
 
+const REWARDS_SNAPSHOT_FILE: &str = "rewards.json";
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+struct RewardsSnapshot {
+    base_reward_rate: u64,
+    min_difficulty: u64,
+    last_reset_at: i64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RewardsHistory {
+    current: Option<RewardsSnapshot>,
+    previous: Option<RewardsSnapshot>,
+}
+
 impl Miner {
-    pub async fn rewards(&self) {
-        let config = get_config(&self.rpc_client).await;
-        let base_reward_rate = config.base_reward_rate;
-
-        let mut s = format!(
-            "{}: {} ORE",
-            config.min_difficulty,
-            amount_u64_to_string(base_reward_rate)
-        )
-        .to_string();
-        for i in 1..32 {
-            let reward_rate = base_reward_rate.saturating_mul(2u64.saturating_pow(i));
-            s = format!(
-                "{}\n{}: {} ORE",
-                s,
-                config.min_difficulty as u32 + i,
-                amount_u64_to_string(reward_rate)
-            );
+    pub async fn rewards(&self, args: RewardsArgs) -> Result<(), OreError> {
+        let config = get_config(&self.rpc_client).await?;
+        let snapshot = RewardsSnapshot {
+            base_reward_rate: config.base_reward_rate,
+            min_difficulty: config.min_difficulty,
+            last_reset_at: config.last_reset_at,
+        };
+        let previous = update_rewards_history(snapshot);
+
+        // Build the reward curve
+        let curve: Vec<(u64, u64, Option<u64>)> = (0..32u64)
+            .map(|i| {
+                let difficulty = config.min_difficulty + i;
+                let reward_rate = calculate_reward_rate(snapshot, difficulty);
+                let previous_rate =
+                    previous.map(|previous| calculate_reward_rate(previous, difficulty));
+                (difficulty, reward_rate, previous_rate)
+            })
+            .collect();
+
+        // Export to csv
+        if let Some(filepath) = args.csv {
+            let mut s = "difficulty,reward_rate,reward_rate_ore,previous_reward_rate".to_string();
+            for (difficulty, reward_rate, previous_rate) in curve.iter() {
+                s = format!(
+                    "{}\n{},{},{},{}",
+                    s,
+                    difficulty,
+                    reward_rate,
+                    amount_u64_to_string(*reward_rate),
+                    previous_rate.map(|r| r.to_string()).unwrap_or_default()
+                );
+            }
+            if let Err(err) = std::fs::write(&filepath, format!("{}\n", s)) {
+                error!("Failed to write {}: {}", filepath, err);
+            } else {
+                println!("Exported reward curve to {}", filepath);
+            }
+            return Ok(());
+        }
+
+        // Print as json
+        if args.json || self.is_json() {
+            print_json(&RewardsDocument {
+                base_reward_rate: config.base_reward_rate,
+                min_difficulty: config.min_difficulty,
+                last_reset_at: config.last_reset_at,
+                previous_base_reward_rate: previous.map(|p| p.base_reward_rate),
+                rewards: curve
+                    .iter()
+                    .map(
+                        |(difficulty, reward_rate, previous_rate)| RewardRateDocument {
+                            difficulty: *difficulty,
+                            reward_rate: *reward_rate,
+                            reward_rate_ore: amount_u64_to_f64(*reward_rate),
+                            previous_reward_rate: *previous_rate,
+                            change_pct: previous_rate.map(|p| change_pct(p, *reward_rate)),
+                        },
+                    )
+                    .collect(),
+            });
+            return Ok(());
+        }
+
+        // Print human readable
+        let lines: Vec<String> = curve
+            .iter()
+            .map(|(difficulty, reward_rate, previous_rate)| {
+                let change = match previous_rate {
+                    Some(previous_rate) => {
+                        format!(" ({:+.2}%)", change_pct(*previous_rate, *reward_rate))
+                    }
+                    None => String::new(),
+                };
+                format!(
+                    "{}: {} ORE{}",
+                    difficulty,
+                    amount_u64_to_string(*reward_rate),
+                    change
+                )
+            })
+            .collect();
+        println!("{}", lines.join("\n"));
+        Ok(())
+    }
+}
+
+/// Returns the reward paid for a hash of the given difficulty.
+fn calculate_reward_rate(snapshot: RewardsSnapshot, difficulty: u64) -> u64 {
+    if difficulty.lt(&snapshot.min_difficulty) {
+        return 0;
+    }
+    let i = difficulty.saturating_sub(snapshot.min_difficulty) as u32;
+    snapshot
+        .base_reward_rate
+        .saturating_mul(2u64.saturating_pow(i))
+}
+
+fn change_pct(previous: u64, current: u64) -> f64 {
+    if previous.eq(&0) {
+        return 0.0;
+    }
+    (current as f64 - previous as f64) / previous as f64 * 100.0
+}
+
+/// Records the latest config snapshot and returns the snapshot of the last observed epoch.
+fn update_rewards_history(snapshot: RewardsSnapshot) -> Option<RewardsSnapshot> {
+    let path = ore_dir().join(REWARDS_SNAPSHOT_FILE);
+    let mut history: RewardsHistory = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    if let Some(current) = history.current {
+        if current.last_reset_at.ne(&snapshot.last_reset_at) {
+            history.previous = Some(current);
         }
-        println!("{}", s);
     }
+    history.current = Some(snapshot);
+    if let Ok(s) = serde_json::to_string(&history) {
+        std::fs::write(&path, s).ok();
+    }
+    history.previous
 }
@@ -0,0 +1,561 @@
+//! The documents printed with `--output json`. Each is tagged with its `type` and the
+//! [`SCHEMA_VERSION`]. Fields may be added within a version, but removing a field or
+//! changing its meaning bumps the version.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The version of the JSON output schema. Bump on breaking changes to any document.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// A JSON output document.
+pub trait Document: Serialize {
+    /// The `type` field of the document.
+    const KIND: &'static str;
+}
+
+/// A document with its `schema_version` and `type` fields, as printed.
+#[derive(Serialize)]
+pub(crate) struct Envelope<'a, T: Document> {
+    pub schema_version: u64,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub document: &'a T,
+}
+
+macro_rules! document {
+    ($name:ident, $kind:literal) => {
+        impl Document for $name {
+            const KIND: &'static str = $kind;
+        }
+    };
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AccountDocument {
+    pub address: String,
+    /// The ORE token balance, in grains.
+    pub balance: u64,
+    /// The SOL balance, in lamports.
+    pub sol_balance: u64,
+    pub stake: u64,
+    pub multiplier: f64,
+    pub last_hash_at: i64,
+    pub total_rewards: u64,
+    pub busses: Vec<BusRewards>,
+}
+document!(AccountDocument, "account");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BusRewards {
+    pub id: u64,
+    pub rewards: u64,
+}
+
+/// The reconciliation of the on-chain history of an authority with the local database.
+/// Rewards, claims, and fees are totals of the on-chain transactions.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AuditDocument {
+    pub authority: String,
+    pub transactions: usize,
+    pub recorded_transactions: usize,
+    pub rewards: u64,
+    pub claimed: u64,
+    pub fees: u64,
+    pub recorded_fees: u64,
+    pub issues: Vec<AuditIssueDocument>,
+}
+document!(AuditDocument, "audit");
+
+/// A transaction that doesn't match between the chain and the local database. `issue` is
+/// one of `never_landed`, `missing_on_chain`, `amount_mismatch`, or `not_recorded`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AuditIssueDocument {
+    pub issue: String,
+    pub kind: String,
+    pub timestamp: i64,
+    pub signature: Option<String>,
+    /// The on-chain amount.
+    pub amount: u64,
+    pub recorded_amount: Option<u64>,
+    pub fee: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BalanceDocument {
+    pub address: String,
+    pub balance: u64,
+    pub stake: u64,
+}
+document!(BalanceDocument, "balance");
+
+/// The hashrate measured at each thread count, and what it is expected to earn.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BenchmarkDocument {
+    /// The seed of the challenge and nonces hashed, to repeat the benchmark with `--seed`.
+    pub seed: u64,
+    /// How long each thread count was benchmarked, in seconds.
+    pub duration: u64,
+    pub results: Vec<BenchmarkResult>,
+    /// The thread count with the highest hashrate.
+    pub best_threads: u64,
+    /// The expected best difficulty of a round at the best hashrate.
+    pub expected_difficulty: f64,
+    /// The expected reward per round in grains, before the stake multiplier. `None` if the
+    /// program config could not be fetched.
+    pub expected_reward: Option<u64>,
+    /// The daily earnings and costs, with `--watts` and `--kwh-price`.
+    pub profitability: Option<BenchmarkProfitability>,
+    /// The name of the baseline compared against, with `--compare`.
+    pub baseline: Option<String>,
+}
+document!(BenchmarkDocument, "benchmark");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BenchmarkResult {
+    pub threads: u64,
+    pub hashes: u64,
+    pub hashes_per_sec: u64,
+    /// The hashrate of the same thread count in the baseline, if it has one.
+    pub baseline_hashes_per_sec: Option<u64>,
+}
+
+/// Amounts are in USD per day unless noted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BenchmarkProfitability {
+    pub ore_price: f64,
+    pub sol_price: f64,
+    /// The expected ORE mined per day, in grains.
+    pub ore_per_day: u64,
+    pub revenue: f64,
+    pub electricity: f64,
+    pub base_fees: f64,
+    /// The priority fee at which profit reaches zero, in microlamports per compute unit.
+    /// `None` if mining is unprofitable at any fee.
+    pub break_even_fee: Option<u64>,
+}
+
+/// The hashrate of one pinned thread on each physical core, from `benchmark --per-core`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CoreBenchmarkDocument {
+    pub seed: u64,
+    pub duration: u64,
+    /// The median hashrate across cores.
+    pub median: u64,
+    pub cores: Vec<CoreBenchmarkResult>,
+}
+document!(CoreBenchmarkDocument, "benchmark.cores");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CoreBenchmarkResult {
+    pub core: usize,
+    pub hashes_per_sec: u64,
+    /// Whether the core is significantly slower than the median.
+    pub slow: bool,
+}
+
+/// The outcome of `benchmark --stress`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StressTestDocument {
+    pub seed: u64,
+    pub threads: u64,
+    pub duration: u64,
+    /// The average hashrate over the whole test.
+    pub hashes_per_sec: u64,
+    /// How many times the kernel reported throttling or the clock speed dropped.
+    pub throttle_events: u64,
+    /// The highest temperature read, in degrees Celsius. `None` if it can't be read.
+    pub max_temperature: Option<f64>,
+}
+document!(StressTestDocument, "benchmark.stress");
+
+/// An estimate of daily mining profit. Amounts are in USD per day unless noted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BreakevenDocument {
+    pub hashrate: u64,
+    pub multiplier: f64,
+    pub ore_price: f64,
+    pub sol_price: f64,
+    /// The expected ORE mined per day, in grains.
+    pub ore_per_day: u64,
+    pub revenue: f64,
+    pub electricity: f64,
+    pub base_fees: f64,
+    /// The current priority fee, in microlamports per compute unit.
+    pub priority_fee: u64,
+    pub priority_fees: f64,
+    pub profit: f64,
+    /// The priority fee at which profit reaches zero, in microlamports per compute unit.
+    /// `None` if mining is unprofitable at any fee.
+    pub break_even_fee: Option<u64>,
+}
+document!(BreakevenDocument, "breakeven");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BussesDocument {
+    pub busses: Vec<BusDocument>,
+    /// The address of the bus a miner would select with `--bus-strategy`, in watch mode.
+    pub selected: Option<String>,
+}
+document!(BussesDocument, "busses");
+
+/// The work for external hashing software: hash `challenge` to at least `min_difficulty`
+/// and submit the best solution found by `cutoff_at`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChallengeDocument {
+    pub authority: String,
+    pub proof: String,
+    /// The challenge, hex encoded.
+    pub challenge: String,
+    pub min_difficulty: u64,
+    pub last_hash_at: i64,
+    /// When to stop hashing and submit, in cluster time.
+    pub cutoff_at: i64,
+    /// Seconds until `cutoff_at`, by the local clock corrected for drift.
+    pub cutoff_in: u64,
+    /// When the epoch resets and the busses are replenished.
+    pub reset_at: i64,
+}
+document!(ChallengeDocument, "challenge");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BusDocument {
+    pub id: u64,
+    pub address: String,
+    pub rewards: u64,
+    pub theoretical_rewards: u64,
+    pub top_balance: u64,
+}
+
+/// A claim. `beneficiary` is set for claims by the signer, and `authority` for each
+/// claim from a keypair directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClaimDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beneficiary: Option<String>,
+    pub amount: u64,
+    pub signature: Option<String>,
+    pub landed: bool,
+}
+document!(ClaimDocument, "claim");
+
+/// The program config account, with the treasury and the mint it pays rewards from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConfigDocument {
+    pub last_reset_at: i64,
+    pub min_difficulty: u64,
+    /// The reward for a hash of the minimum difficulty, in grains. Each extra level of
+    /// difficulty doubles it.
+    pub base_reward_rate: u64,
+    /// The largest stake on the network in the last epoch, in grains.
+    pub top_balance: u64,
+    pub address: String,
+    /// When the epoch resets and the busses are replenished.
+    pub next_reset_at: i64,
+    pub treasury: TreasuryDocument,
+    pub mint: MintDocument,
+}
+document!(ConfigDocument, "config");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TreasuryDocument {
+    pub address: String,
+    pub token_account: String,
+    /// The ORE held by the treasury token account, in grains.
+    pub balance: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MintDocument {
+    pub address: String,
+    /// The total supply, in grains.
+    pub supply: u64,
+    pub decimals: u8,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConfigValidationDocument {
+    pub path: String,
+    pub exists: bool,
+    pub profile: Option<String>,
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub settings: Vec<SettingDocument>,
+}
+document!(ConfigValidationDocument, "config_validation");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SettingDocument {
+    pub section: Option<String>,
+    pub key: String,
+    pub value: Value,
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GlobalDocument {
+    pub treasury_balance: u64,
+    /// The sum of the balances of all proof accounts.
+    pub total_staked: u64,
+    /// The stake that earns the max multiplier.
+    pub top_balance: u64,
+    pub total_hashes: u64,
+    pub miners: usize,
+    /// The length of an epoch, in seconds.
+    pub epoch_duration: i64,
+    pub last_reset_at: i64,
+    pub next_reset_at: i64,
+}
+document!(GlobalDocument, "global");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HistoryDocument {
+    pub transactions: Vec<TransactionDocument>,
+}
+document!(HistoryDocument, "history");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TransactionDocument {
+    /// `mine`, `claim`, or `swap`. The amount of a swap is the ORE sold.
+    pub kind: String,
+    pub timestamp: i64,
+    pub authority: String,
+    pub signature: Option<String>,
+    pub landed: bool,
+    pub amount: u64,
+    pub difficulty: Option<u64>,
+    pub fee: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KeygenDocument {
+    pub address: String,
+    pub outfile: String,
+}
+document!(KeygenDocument, "keygen");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MineRoundDocument {
+    pub round: usize,
+    pub challenge: String,
+    pub stake: u64,
+    pub multiplier: f64,
+    pub min_difficulty: u64,
+}
+document!(MineRoundDocument, "mine.round");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MineSolutionDocument {
+    pub round: usize,
+    pub difficulty: u32,
+    pub hash: String,
+}
+document!(MineSolutionDocument, "mine.solution");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MineSubmissionDocument {
+    pub round: usize,
+    pub difficulty: u32,
+    pub bus: String,
+    pub signature: Option<String>,
+    pub landed: bool,
+}
+document!(MineSubmissionDocument, "mine.submission");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MineSummaryDocument {
+    /// The runtime of the session, in seconds.
+    pub runtime: u64,
+    pub rounds: u64,
+    pub landed: u64,
+    pub rewards: u64,
+    pub fees: u64,
+    pub average_difficulty: f64,
+    pub average_hashrate: u64,
+}
+document!(MineSummaryDocument, "mine.summary");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProfilesDocument {
+    pub profiles: Vec<String>,
+    pub default: Option<String>,
+}
+document!(ProfilesDocument, "profiles");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProofDocument {
+    pub address: String,
+    pub authority: String,
+    pub miner: String,
+    pub balance: u64,
+    pub challenge: String,
+    pub last_hash: String,
+    pub last_hash_at: i64,
+    pub last_stake_at: i64,
+    pub total_hashes: u64,
+    pub total_rewards: u64,
+}
+document!(ProofDocument, "proof");
+
+/// ORE earned and SOL spent on fees since `since`. USD values are at the current prices,
+/// and are `None` if the prices are unknown.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReportDocument {
+    pub since: i64,
+    pub ore_price: Option<f64>,
+    pub sol_price: Option<f64>,
+    pub total: ReportSummaryDocument,
+    pub daily: Vec<ReportDailyDocument>,
+    pub wallets: Vec<ReportWalletDocument>,
+}
+document!(ReportDocument, "report");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReportSummaryDocument {
+    /// The ORE mined by landed submissions, in grains.
+    pub earned: u64,
+    /// The fees of all submissions and claims, in lamports.
+    pub fees: u64,
+    pub earned_usd: Option<f64>,
+    pub fees_usd: Option<f64>,
+    pub net_usd: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReportDailyDocument {
+    pub date: String,
+    #[serde(flatten)]
+    pub summary: ReportSummaryDocument,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReportWalletDocument {
+    pub authority: String,
+    #[serde(flatten)]
+    pub summary: ReportSummaryDocument,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RewardsDocument {
+    pub base_reward_rate: u64,
+    pub min_difficulty: u64,
+    pub last_reset_at: i64,
+    pub previous_base_reward_rate: Option<u64>,
+    pub rewards: Vec<RewardRateDocument>,
+}
+document!(RewardsDocument, "rewards");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RewardRateDocument {
+    pub difficulty: u64,
+    pub reward_rate: u64,
+    pub reward_rate_ore: f64,
+    pub previous_reward_rate: Option<u64>,
+    pub change_pct: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SelfUpdateDocument {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+}
+document!(SelfUpdateDocument, "self_update");
+
+/// A submission of an externally computed solution. `reward` is the ORE paid out, in grains.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SubmitDocument {
+    pub authority: String,
+    pub difficulty: u32,
+    pub bus: String,
+    pub signature: Option<String>,
+    pub landed: bool,
+    pub reward: u64,
+}
+document!(SubmitDocument, "submit");
+
+/// A Jupiter swap, or its quote if `signature` is `None`. Amounts are in the smallest
+/// unit of each token.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SwapDocument {
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub min_out_amount: u64,
+    pub price_impact_pct: f64,
+    pub signature: Option<String>,
+}
+document!(SwapDocument, "swap");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TopDocument {
+    pub miners: Vec<TopMinerDocument>,
+    /// The number of proof accounts ranked.
+    pub total_miners: usize,
+}
+document!(TopDocument, "top");
+
+/// A solution checked offline. `difficulty` and `hash` are only set if it is valid.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VerifyDocument {
+    pub valid: bool,
+    pub difficulty: Option<u32>,
+    pub hash: Option<String>,
+}
+document!(VerifyDocument, "verify");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TopMinerDocument {
+    pub rank: usize,
+    pub authority: String,
+    pub proof: String,
+    pub balance: u64,
+    pub total_rewards: u64,
+    pub total_hashes: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StatsDocument {
+    pub lifetime: StatsSummaryDocument,
+    pub rounds: u64,
+    pub average_hashrate: u64,
+    pub difficulty_histogram: Vec<DifficultyCountDocument>,
+    pub daily: Vec<DailyStatsDocument>,
+    pub wallets: Vec<WalletStatsDocument>,
+}
+document!(StatsDocument, "stats");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StatsSummaryDocument {
+    pub submissions: u64,
+    pub landed: u64,
+    pub mined: u64,
+    pub claimed: u64,
+    pub fees: u64,
+    pub average_difficulty: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DifficultyCountDocument {
+    pub difficulty: u32,
+    pub rounds: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DailyStatsDocument {
+    pub date: String,
+    #[serde(flatten)]
+    pub summary: StatsSummaryDocument,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WalletStatsDocument {
+    pub authority: String,
+    #[serde(flatten)]
+    pub summary: StatsSummaryDocument,
+}
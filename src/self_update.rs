@@ -0,0 +1,244 @@
+use std::{fmt::Write, path::PathBuf, str::FromStr};
+
+use colored::*;
+use serde_json::Value;
+use solana_sdk::{hash::hash, pubkey::Pubkey, signature::Signature};
+use tracing::error;
+
+use crate::{
+    args::SelfUpdateArgs,
+    output::{finish_progress, new_progress_bar, print_json},
+    schema::SelfUpdateDocument,
+    timeout::http_timeout,
+    utils::ask_confirm,
+    Miner,
+};
+
+/// The GitHub API endpoint of the latest ore-cli release.
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/regolith-labs/ore-cli/releases/latest";
+
+/// The public key release binaries are signed with, pinned when the release is built.
+const RELEASE_SIGNER: Option<&str> = option_env!("ORE_RELEASE_SIGNER");
+
+impl Miner {
+    pub async fn self_update(&self, args: SelfUpdateArgs) {
+        // Fetch latest release
+        let client = match reqwest::Client::builder()
+            .user_agent(format!("ore-cli/{}", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(http_timeout())
+            .build()
+        {
+            Ok(client) => client,
+            Err(err) => {
+                error!("Failed to build HTTP client: {}", err);
+                return;
+            }
+        };
+        let release: Value = match fetch(&client, LATEST_RELEASE_URL).await {
+            Ok(response) => match response.json().await {
+                Ok(release) => release,
+                Err(err) => {
+                    error!("Failed to parse release: {}", err);
+                    return;
+                }
+            },
+            Err(err) => {
+                error!("Failed to fetch latest release: {}", err);
+                return;
+            }
+        };
+        let current = env!("CARGO_PKG_VERSION");
+        let latest = release["tag_name"]
+            .as_str()
+            .unwrap_or_default()
+            .trim_start_matches('v')
+            .to_string();
+        let update_available = is_newer(&latest, current);
+        if self.is_json() {
+            print_json(&SelfUpdateDocument {
+                current_version: current.to_string(),
+                latest_version: latest.clone(),
+                update_available,
+            });
+        } else if update_available {
+            println!("ore {} is available (current: {})", latest, current);
+        } else {
+            println!("ore {} is the latest release", current);
+        }
+        if args.check_only || (!update_available && !args.force) {
+            return;
+        }
+
+        // Parse signer, defaulting to the pinned release key
+        let Some(signer) = args.signer.as_deref().or(RELEASE_SIGNER) else {
+            error!("This build has no pinned release key. Pass --signer <ADDRESS> to verify the update with.");
+            return;
+        };
+        let Ok(signer) = Pubkey::from_str(signer) else {
+            error!("Invalid address: {:?}", signer);
+            return;
+        };
+
+        // Find the binary built for this platform
+        let name = asset_name();
+        let assets = release["assets"].as_array().cloned().unwrap_or_default();
+        let asset_url = |name: &str| {
+            assets
+                .iter()
+                .find(|asset| asset["name"].as_str().eq(&Some(name)))
+                .and_then(|asset| asset["browser_download_url"].as_str())
+                .map(|url| url.to_string())
+        };
+        let Some(binary_url) = asset_url(&name) else {
+            error!("Release {} has no binary named {}", latest, name);
+            return;
+        };
+        let Some(checksum_url) = asset_url(&format!("{}.sha256", name)) else {
+            error!("Release {} has no checksum for {}", latest, name);
+            return;
+        };
+        let Some(signature_url) = asset_url(&format!("{}.sig", name)) else {
+            error!("Release {} has no signature for {}", latest, name);
+            return;
+        };
+        if !ask_confirm(
+            format!(
+                "\nYou are about to replace ore {} with {}.\n\nAre you sure you want to continue? [Y/n]",
+                current, latest
+            )
+            .as_str(),
+        ) {
+            return;
+        }
+
+        // Download binary
+        let progress_bar = new_progress_bar();
+        progress_bar.set_message(format!("Downloading {}...", name));
+        let binary = match download(&client, &binary_url).await {
+            Ok(binary) => binary,
+            Err(err) => {
+                progress_bar.finish_and_clear();
+                error!("Failed to download {}: {}", name, err);
+                return;
+            }
+        };
+
+        // Verify checksum
+        let checksum = match download(&client, &checksum_url).await {
+            Ok(checksum) => String::from_utf8_lossy(&checksum)
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase(),
+            Err(err) => {
+                progress_bar.finish_and_clear();
+                error!("Failed to download checksum: {}", err);
+                return;
+            }
+        };
+        if checksum.ne(&sha256_hex(&binary)) {
+            progress_bar.finish_and_clear();
+            error!("Checksum of {} does not match the release", name);
+            return;
+        }
+
+        // Verify signature
+        let signature = match download(&client, &signature_url).await {
+            Ok(signature) => String::from_utf8_lossy(&signature).trim().to_string(),
+            Err(err) => {
+                progress_bar.finish_and_clear();
+                error!("Failed to download signature: {}", err);
+                return;
+            }
+        };
+        let valid = Signature::from_str(&signature)
+            .map(|signature| signature.verify(signer.as_ref(), &binary))
+            .unwrap_or(false);
+        if !valid {
+            progress_bar.finish_and_clear();
+            error!("{} is not signed by {}", name, signer);
+            return;
+        }
+
+        // Replace the running binary
+        match replace_binary(&binary) {
+            Ok(path) => finish_progress(
+                &progress_bar,
+                format!(
+                    "{} Updated {} to ore {}",
+                    "OK".bold().green(),
+                    path.display(),
+                    latest
+                ),
+            ),
+            Err(err) => {
+                progress_bar.finish_and_clear();
+                error!("Failed to replace binary: {}", err);
+            }
+        }
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> reqwest::Result<reqwest::Response> {
+    client.get(url).send().await?.error_for_status()
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> reqwest::Result<Vec<u8>> {
+    Ok(fetch(client, url).await?.bytes().await?.to_vec())
+}
+
+/// The name of the release binary for this platform, e.g. `ore-x86_64-linux`.
+fn asset_name() -> String {
+    format!(
+        "ore-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hash(bytes)
+        .to_bytes()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            write!(hex, "{:02x}", byte).ok();
+            hex
+        })
+}
+
+/// Returns true if the version is greater than the current one, comparing numeric parts.
+fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(version).gt(&parse(current))
+}
+
+/// Writes the binary next to the running one and moves it into place.
+fn replace_binary(binary: &[u8]) -> std::io::Result<PathBuf> {
+    let path = std::env::current_exe()?;
+    let tmp = path.with_extension("new");
+    std::fs::write(&tmp, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows cannot overwrite a running executable, but it can rename it
+    #[cfg(windows)]
+    {
+        let old = path.with_extension("old");
+        std::fs::remove_file(&old).ok();
+        std::fs::rename(&path, &old)?;
+    }
+    if let Err(err) = std::fs::rename(&tmp, &path) {
+        std::fs::remove_file(&tmp).ok();
+        return Err(err);
+    }
+    Ok(path)
+}
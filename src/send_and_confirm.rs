@@ -1,15 +1,19 @@
-use std::time::Duration;
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
 
 use colored::*;
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
 use solana_client::{
-    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    client_error::{ClientError, ClientErrorKind},
     rpc_config::RpcSendTransactionConfig,
 };
 use solana_program::{
     instruction::Instruction,
     native_token::{lamports_to_sol, sol_to_lamports},
 };
-use solana_rpc_client::spinner;
 use solana_sdk::{
     commitment_config::CommitmentLevel,
     compute_budget::ComputeBudgetInstruction,
@@ -17,11 +21,25 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
+use tracing::{debug, info_span, Instrument, Span};
 
-use crate::Miner;
+use crate::{
+    error::OreError,
+    metrics::metrics,
+    output::{finish_progress, new_progress_bar},
+    program, slots,
+    timeout::confirm_timeout,
+    Miner,
+};
 
 const MIN_SOL_BALANCE: f64 = 0.005;
 
+/// The base fee of a transaction per signature, in lamports.
+const BASE_FEE_LAMPORTS: u64 = 5000;
+
+/// The compute unit limit of transactions with a dynamic budget.
+const DYNAMIC_CU_LIMIT: u32 = 1_400_000;
+
 const RPC_RETRIES: usize = 0;
 const _SIMULATION_RETRIES: usize = 4;
 const GATEWAY_RETRIES: usize = 150;
@@ -30,32 +48,123 @@ const CONFIRM_RETRIES: usize = 1;
 const CONFIRM_DELAY: u64 = 0;
 const GATEWAY_DELAY: u64 = 300;
 
+/// How long a signed transaction is resent before checking whether its blockhash expired,
+/// a little under the blockhash lifetime of about a minute.
+const RESIGN_INTERVAL: Duration = Duration::from_secs(45);
+
+/// How often to check whether the blockhash expired, once the resign interval passed.
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait before the final status check of every signature sent.
+const RECONCILE_DELAY: Duration = Duration::from_secs(2);
+
+/// Where the signatures sent for a transaction stand.
+enum Landing {
+    Confirmed(Signature),
+    Failed(String),
+    Pending,
+}
+
+/// A signature sent for a transaction, and the last block height it can land at.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SentSignature {
+    pub signature: String,
+    pub last_valid_block_height: u64,
+}
+
+/// Called with each signature of a transaction and the last block height its blockhash is
+/// valid for, before the signature is first sent. An error stops the send.
+pub type OnSigned<'a> = &'a (dyn Fn(Signature, u64) -> Result<(), OreError> + Sync);
+
 pub enum ComputeBudget {
     Dynamic,
     Fixed(u32),
 }
 
 impl Miner {
+    /// Estimates the fee of a transaction in lamports, including the priority fee.
+    pub async fn estimate_fee(&self, compute_budget: &ComputeBudget) -> Result<u64, OreError> {
+        let signatures = match self.signer()?.pubkey().eq(&self.fee_payer()?.pubkey()) {
+            true => 1,
+            false => 2,
+        };
+        let compute_units = match compute_budget {
+            ComputeBudget::Dynamic => DYNAMIC_CU_LIMIT,
+            ComputeBudget::Fixed(cus) => *cus,
+        };
+        let priority_fee = self.priority_fee().await;
+        Ok(BASE_FEE_LAMPORTS * signatures
+            + priority_fee.saturating_mul(compute_units as u64) / 1_000_000)
+    }
+
+    /// Sends the instructions in a transaction and waits for it to land, retrying until
+    /// the confirm timeout. Returns [`OreError::Cancelled`] as soon as the miner is
+    /// cancelled.
     pub async fn send_and_confirm(
         &self,
         ixs: &[Instruction],
         compute_budget: ComputeBudget,
         skip_confirm: bool,
-    ) -> ClientResult<Signature> {
-        let progress_bar = spinner::new_progress_bar();
-        let signer = self.signer();
+    ) -> Result<Signature, OreError> {
+        self.send_and_confirm_tracked(ixs, compute_budget, skip_confirm, &|_, _| Ok(()))
+            .await
+    }
+
+    /// Like [`Miner::send_and_confirm`], handing each signature to `on_signed` before it
+    /// is sent, so callers can persist it and later check whether it landed.
+    #[tracing::instrument(skip_all, fields(attempts, signature))]
+    pub async fn send_and_confirm_tracked(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+        on_signed: OnSigned<'_>,
+    ) -> Result<Signature, OreError> {
+        let progress_bar = new_progress_bar();
+        let result = self
+            .cancellable(self.try_send_and_confirm(
+                ixs,
+                compute_budget,
+                skip_confirm,
+                on_signed,
+                &progress_bar,
+            ))
+            .await
+            .and_then(|result| result);
+        if let Err(OreError::Cancelled) = result {
+            finish_progress(
+                &progress_bar,
+                format!("{}: Cancelled", "ERROR".bold().red()),
+            );
+        }
+        result
+    }
+
+    async fn try_send_and_confirm(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+        on_signed: OnSigned<'_>,
+        progress_bar: &ProgressBar,
+    ) -> Result<Signature, OreError> {
+        let signer = self.signer()?;
         let client = self.rpc_client.clone();
-        let fee_payer = self.fee_payer();
+        let fee_payer = self.fee_payer()?;
 
         // Return error, if balance is zero
-        if let Ok(balance) = client.get_balance(&fee_payer.pubkey()).await {
+        if let Ok(balance) = client
+            .get_balance(&fee_payer.pubkey())
+            .instrument(info_span!("rpc", method = "getBalance"))
+            .await
+        {
             if balance <= sol_to_lamports(MIN_SOL_BALANCE) {
-                panic!(
-                    "{} Insufficient balance: {} SOL\nPlease top up with at least {} SOL",
-                    "ERROR".bold().red(),
-                    lamports_to_sol(balance),
-                    MIN_SOL_BALANCE
-                );
+                progress_bar.finish_and_clear();
+                return Err(OreError::InsufficientFunds {
+                    balance: lamports_to_sol(balance),
+                    required: MIN_SOL_BALANCE,
+                    token: "SOL",
+                });
             }
         }
 
@@ -64,24 +173,22 @@ impl Miner {
         match compute_budget {
             ComputeBudget::Dynamic => {
                 // TODO simulate
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(1_400_000))
+                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(
+                    DYNAMIC_CU_LIMIT,
+                ))
             }
             ComputeBudget::Fixed(cus) => {
                 final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus))
             }
         }
 
-        let priority_fee = match &self.dynamic_fee_url {
-            Some(_) => {
-                self.dynamic_fee().await
-            }
-            None => {
-                self.priority_fee.unwrap_or(0)
-            }
-        };
+        let priority_fee = self.priority_fee().await;
 
-        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee));
-        final_ixs.extend_from_slice(ixs);
+        debug!("Using a priority fee of {} microlamports", priority_fee);
+        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
+            priority_fee,
+        ));
+        final_ixs.extend(ixs.iter().cloned().map(program::retarget));
 
         // Build tx
         let send_cfg = RpcSendTransactionConfig {
@@ -94,79 +201,138 @@ impl Miner {
         let mut tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
 
         // Sign tx
-        let (hash, _slot) = client
+        let signers: Vec<&dyn Signer> = match signer.pubkey() == fee_payer.pubkey() {
+            true => vec![signer.as_ref()],
+            false => vec![signer.as_ref(), fee_payer.as_ref()],
+        };
+        let (hash, mut last_valid_block_height) = client
             .get_latest_blockhash_with_commitment(self.rpc_client.commitment())
-            .await
-            .unwrap();
+            .instrument(info_span!("rpc", method = "getLatestBlockhash"))
+            .await?;
+        tx.sign(&signers, hash);
+        on_signed(tx.signatures[0], last_valid_block_height)?;
+        let mut resign_at = Instant::now() + RESIGN_INTERVAL;
 
-        
-        if signer.pubkey() == fee_payer.pubkey() {
-            tx.sign(&[&signer], hash);
-        } else {
-            tx.sign(&[&signer, &fee_payer], hash);
-        }
-
-        // Submit tx
+        // Submit tx, keeping every signature sent, since any of them may land. Signatures
+        // before `expired` were signed with a blockhash that expired, so they can no longer
+        // land.
+        let deadline = Instant::now() + confirm_timeout();
         let mut attempts = 0;
+        let mut signatures: Vec<Signature> = vec![];
+        let mut expired = 0;
         loop {
+            // Sign again once the blockhash expired. Until then the signature sent may still
+            // land, and a second copy of the transaction could land beside it.
+            if Instant::now().gt(&resign_at) {
+                resign_at = Instant::now() + EXPIRY_CHECK_INTERVAL;
+                if self.blockhash_expired(last_valid_block_height).await {
+                    // The last signature may have landed just before its blockhash expired
+                    match self.check_signatures(&signatures, signatures.len()).await {
+                        Ok(Landing::Confirmed(sig)) => {
+                            finish_progress(
+                                progress_bar,
+                                format!("{} {}", "OK".bold().green(), sig),
+                            );
+                            return Ok(sig);
+                        }
+                        Ok(Landing::Failed(err)) => {
+                            finish_progress(
+                                progress_bar,
+                                format!("{}: {}", "ERROR".bold().red(), err),
+                            );
+                            return Err(OreError::TransactionFailed(err));
+                        }
+                        Ok(Landing::Pending) => {
+                            match client
+                                .get_latest_blockhash_with_commitment(self.rpc_client.commitment())
+                                .instrument(info_span!("rpc", method = "getLatestBlockhash"))
+                                .await
+                            {
+                                Ok((hash, height)) => {
+                                    tx.sign(&signers, hash);
+                                    last_valid_block_height = height;
+                                    on_signed(tx.signatures[0], last_valid_block_height)?;
+                                    expired = signatures.len();
+                                    resign_at = Instant::now() + RESIGN_INTERVAL;
+                                    debug!("Signed the transaction again with blockhash {}", hash);
+                                }
+                                Err(err) => {
+                                    debug!("Failed to fetch a new blockhash: {}", err);
+                                    metrics().rpc_errors.fetch_add(1, Ordering::Relaxed);
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            debug!("Failed to check {} signatures: {}", signatures.len(), err);
+                            metrics().rpc_errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
 
             let message = match &self.dynamic_fee_url {
-                Some(_) => format!("Submitting transaction... (attempt {} with dynamic priority fee of {} via {})", attempts, priority_fee, self.dynamic_fee_strategy.as_ref().unwrap()),
-                None => format!("Submitting transaction... (attempt {} with static priority fee of {})", attempts, priority_fee),
+                Some(_) => format!(
+                    "Submitting transaction... (attempt {} with dynamic priority fee of {} via {})",
+                    attempts,
+                    priority_fee,
+                    self.fee_strategy().unwrap_or_default()
+                ),
+                None => format!(
+                    "Submitting transaction... (attempt {} with static priority fee of {})",
+                    attempts, priority_fee
+                ),
             };
 
             progress_bar.set_message(message);
 
-            match client.send_transaction_with_config(&tx, send_cfg).await {
+            Span::current().record("attempts", attempts);
+            match client
+                .send_transaction_with_config(&tx, send_cfg)
+                .instrument(info_span!(
+                    "rpc",
+                    method = "sendTransaction",
+                    attempt = attempts
+                ))
+                .await
+            {
                 Ok(sig) => {
+                    if !signatures.contains(&sig) {
+                        signatures.push(sig);
+                    }
+                    Span::current().record("signature", sig.to_string());
                     // Skip confirmation
                     if skip_confirm {
-                        progress_bar.finish_with_message(format!("Sent: {}", sig));
+                        finish_progress(progress_bar, format!("Sent: {}", sig));
                         return Ok(sig);
                     }
 
-                    // Confirm the tx landed
+                    // Confirm one of the signatures landed
                     for _ in 0..CONFIRM_RETRIES {
-                        std::thread::sleep(Duration::from_millis(CONFIRM_DELAY));
-                        match client.get_signature_statuses(&[sig]).await {
-                            Ok(signature_statuses) => {
-                                for status in signature_statuses.value {
-                                    if let Some(status) = status {
-                                        if let Some(err) = status.err {
-                                            progress_bar.finish_with_message(format!(
-                                                "{}: {}",
-                                                "ERROR".bold().red(),
-                                                err
-                                            ));
-                                            return Err(ClientError {
-                                                request: None,
-                                                kind: ClientErrorKind::Custom(err.to_string()),
-                                            });
-                                        }
-                                        if let Some(confirmation) = status.confirmation_status {
-                                            match confirmation {
-                                                TransactionConfirmationStatus::Processed => {}
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    progress_bar.finish_with_message(format!(
-                                                        "{} {}",
-                                                        "OK".bold().green(),
-                                                        sig
-                                                    ));
-                                                    return Ok(sig);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
+                        tokio::time::sleep(Duration::from_millis(CONFIRM_DELAY)).await;
+                        match self.check_signatures(&signatures, expired).await {
+                            Ok(Landing::Confirmed(sig)) => {
+                                finish_progress(
+                                    progress_bar,
+                                    format!("{} {}", "OK".bold().green(), sig),
+                                );
+                                return Ok(sig);
+                            }
+                            Ok(Landing::Failed(err)) => {
+                                finish_progress(
+                                    progress_bar,
+                                    format!("{}: {}", "ERROR".bold().red(), err),
+                                );
+                                return Err(OreError::TransactionFailed(err));
                             }
+                            Ok(Landing::Pending) => {}
 
                             // Handle confirmation errors
                             Err(err) => {
+                                metrics().rpc_errors.fetch_add(1, Ordering::Relaxed);
                                 progress_bar.set_message(format!(
                                     "{}: {}",
                                     "ERROR".bold().red(),
-                                    err.kind().to_string()
+                                    err.kind()
                                 ));
                             }
                         }
@@ -175,27 +341,126 @@ impl Miner {
 
                 // Handle submit errors
                 Err(err) => {
-                    progress_bar.set_message(format!(
-                        "{}: {}",
-                        "ERROR".bold().red(),
-                        err.kind().to_string()
-                    ));
+                    debug!("Failed to submit transaction: {}", err);
+                    metrics().rpc_errors.fetch_add(1, Ordering::Relaxed);
+                    progress_bar.set_message(format!("{}: {}", "ERROR".bold().red(), err.kind()));
                 }
             }
 
             // Retry
-            std::thread::sleep(Duration::from_millis(GATEWAY_DELAY));
+            tokio::time::sleep(Duration::from_millis(GATEWAY_DELAY)).await;
             attempts += 1;
-            if attempts > GATEWAY_RETRIES {
-                progress_bar.finish_with_message(format!("{}: Max retries", "ERROR".bold().red()));
-                return Err(ClientError {
-                    request: None,
-                    kind: ClientErrorKind::Custom("Max retries".into()),
+            let timed_out = Instant::now().gt(&deadline);
+            if attempts > GATEWAY_RETRIES || timed_out {
+                let reason = match timed_out {
+                    true => "Timed out",
+                    false => "Max retries",
+                };
+
+                // An earlier signature may have landed after its own check
+                if !signatures.is_empty() {
+                    tokio::time::sleep(RECONCILE_DELAY).await;
+                    match self.check_signatures(&signatures, expired).await {
+                        Ok(Landing::Confirmed(sig)) => {
+                            finish_progress(
+                                progress_bar,
+                                format!("{} {}", "OK".bold().green(), sig),
+                            );
+                            return Ok(sig);
+                        }
+                        Ok(Landing::Failed(err)) => {
+                            finish_progress(
+                                progress_bar,
+                                format!("{}: {}", "ERROR".bold().red(), err),
+                            );
+                            return Err(OreError::TransactionFailed(err));
+                        }
+                        Ok(Landing::Pending) => {}
+                        Err(err) => {
+                            debug!("Failed to check {} signatures: {}", signatures.len(), err);
+                            metrics().rpc_errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+                finish_progress(
+                    progress_bar,
+                    format!("{}: {}", "ERROR".bold().red(), reason),
+                );
+                // Sent transactions expired, otherwise the RPC rejected every attempt
+                return Err(match signatures.is_empty() {
+                    false => OreError::TransactionExpired,
+                    true => OreError::Rpc(ClientError {
+                        request: None,
+                        kind: ClientErrorKind::Custom(reason.into()),
+                    }),
                 });
             }
         }
     }
 
+    /// Whether the chain passed the last block height a blockhash is valid for. Counts as
+    /// not expired when the block height can't be fetched.
+    async fn blockhash_expired(&self, last_valid_block_height: u64) -> bool {
+        match self
+            .rpc_client
+            .get_block_height_with_commitment(self.rpc_client.commitment())
+            .instrument(info_span!("rpc", method = "getBlockHeight"))
+            .await
+        {
+            Ok(height) => height.gt(&last_valid_block_height),
+            Err(err) => {
+                debug!("Failed to fetch the block height: {}", err);
+                metrics().rpc_errors.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    /// Fetches the statuses of every signature sent for a transaction in one request. The
+    /// signatures before `expired` can no longer land. A confirmed signature wins, and a
+    /// failed one only counts once no other signature can still land.
+    async fn check_signatures(
+        &self,
+        signatures: &[Signature],
+        expired: usize,
+    ) -> Result<Landing, ClientError> {
+        let statuses = self
+            .rpc_client
+            .get_signature_statuses(signatures)
+            .instrument(info_span!(
+                "rpc",
+                method = "getSignatureStatuses",
+                count = signatures.len()
+            ))
+            .await?;
+        let mut failed = None;
+        let mut pending = false;
+        for (i, (sig, status)) in signatures.iter().zip(statuses.value).enumerate() {
+            let Some(status) = status else {
+                pending |= i.ge(&expired);
+                continue;
+            };
+            if let Some(err) = status.err {
+                failed = Some(err.to_string());
+                continue;
+            }
+            if let Some(
+                TransactionConfirmationStatus::Confirmed | TransactionConfirmationStatus::Finalized,
+            ) = status.confirmation_status
+            {
+                slots::record_landing(status.slot);
+                return Ok(Landing::Confirmed(*sig));
+            }
+
+            // Processed, but not confirmed yet
+            pending = true;
+        }
+        Ok(match failed {
+            Some(err) if !pending => Landing::Failed(err),
+            _ => Landing::Pending,
+        })
+    }
+
     // TODO
     fn _simulate(&self) {
 
@@ -0,0 +1,341 @@
+//! Runs the miner as a Windows service. The service is `ore service run`, which reports to
+//! the service control manager and supervises `ore mine` as a child process, so Windows
+//! restarts the miner after reboots and crashes.
+
+use std::{
+    ffi::OsString,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc,
+    time::Duration,
+};
+
+use colored::*;
+use windows_service::{
+    define_windows_service,
+    service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceControl, ServiceControlAccept,
+        ServiceErrorControl, ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    },
+    service_control_handler::{self, ServiceControlHandlerResult},
+    service_dispatcher,
+    service_manager::{ServiceManager, ServiceManagerAccess},
+};
+
+use crate::{
+    args::{ServiceArgs, ServiceCommand, ServiceInstallArgs, ServiceNameArgs},
+    daemon::terminate,
+    error::OreError,
+    utils::ask_confirm,
+    Miner,
+};
+
+/// How long the service waits for the miner to exit before killing it.
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long Windows waits before restarting a miner that exited with an error.
+const RESTART_DELAY: Duration = Duration::from_secs(10);
+
+/// How long without failures before Windows resets the restart count.
+const RESET_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl Miner {
+    pub async fn service(&self, args: ServiceArgs) -> Result<(), OreError> {
+        match args.command {
+            ServiceCommand::Install(args) => self.install_service(args),
+            ServiceCommand::Uninstall(args) => self.uninstall_service(args),
+            ServiceCommand::Start(args) => self.start_service(args),
+            ServiceCommand::Stop(args) => self.stop_service(args),
+            ServiceCommand::Run(args) => run(args.name),
+        }
+    }
+
+    fn install_service(&self, args: ServiceInstallArgs) -> Result<(), OreError> {
+        // Run the command line as given, with mine in place of this command. The parsed
+        // arguments include the config file defaults, which the service reads itself.
+        let exe = std::env::current_exe()
+            .map_err(|err| OreError::Service(format!("Could not find the ore binary: {}", err)))?;
+        let argv: Vec<OsString> = std::env::args_os().skip(1).collect();
+        let command = argv
+            .iter()
+            .position(|arg| arg.eq("service"))
+            .unwrap_or_default();
+        let mine_args = argv[command..]
+            .iter()
+            .position(|arg| arg.eq("--"))
+            .map(|i| &argv[command + i + 1..])
+            .unwrap_or_default();
+        let home = std::env::var_os("USERPROFILE").unwrap_or_default();
+        let launch_arguments: Vec<OsString> = ["service", "run", "--name"]
+            .into_iter()
+            .map(OsString::from)
+            .chain([OsString::from(&args.name), OsString::from("--home"), home])
+            .chain(std::iter::once(OsString::from("--")))
+            .chain(argv[..command].iter().cloned())
+            .chain(std::iter::once(OsString::from("mine")))
+            .chain(mine_args.iter().cloned())
+            .collect();
+        println!(
+            "{}: {} {}",
+            "Command".bold(),
+            exe.display(),
+            launch_arguments
+                .iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        println!(
+            "{}: {}",
+            "Account".bold(),
+            args.account.as_deref().unwrap_or("LocalSystem")
+        );
+        if !ask_confirm(&format!("Install the {} service? [Y/n]", args.name)) {
+            return Ok(());
+        }
+
+        // Built-in and virtual service accounts have no password
+        let account_password = match args.account.as_deref() {
+            Some(account) if !is_service_account(account) => Some(OsString::from(
+                rpassword::prompt_password(format!("Password for {}: ", account)).map_err(
+                    |err| OreError::Service(format!("Could not read the password: {}", err)),
+                )?,
+            )),
+            _ => None,
+        };
+
+        // Register the service to start at boot
+        let manager =
+            manager(ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE)?;
+        let info = ServiceInfo {
+            name: OsString::from(&args.name),
+            display_name: OsString::from(format!("ORE miner ({})", args.name)),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: exe,
+            launch_arguments,
+            dependencies: vec![],
+            account_name: args.account.as_ref().map(OsString::from),
+            account_password,
+        };
+        let service = manager
+            .create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)
+            .map_err(|err| service_error("create", &args.name, err))?;
+        service
+            .set_description("Mines ORE with ore-cli")
+            .map_err(|err| service_error("configure", &args.name, err))?;
+
+        // Restart the miner when it crashes or exits with an error
+        service
+            .update_failure_actions(ServiceFailureActions {
+                reset_period: ServiceFailureResetPeriod::After(RESET_PERIOD),
+                reboot_msg: None,
+                command: None,
+                actions: Some(
+                    (0..3)
+                        .map(|_| ServiceAction {
+                            action_type: ServiceActionType::Restart,
+                            delay: RESTART_DELAY,
+                        })
+                        .collect(),
+                ),
+            })
+            .and_then(|_| service.set_failure_actions_on_non_crash_failures(true))
+            .map_err(|err| service_error("configure recovery for", &args.name, err))?;
+        println!(
+            "{} Installed the {} service",
+            "OK".bold().green(),
+            args.name
+        );
+
+        // Start it now
+        if args.start {
+            service
+                .start::<OsString>(&[])
+                .map_err(|err| service_error("start", &args.name, err))?;
+            println!("{} Started the {} service", "OK".bold().green(), args.name);
+        }
+        Ok(())
+    }
+
+    fn uninstall_service(&self, args: ServiceNameArgs) -> Result<(), OreError> {
+        let manager = manager(ServiceManagerAccess::CONNECT)?;
+        let service = manager
+            .open_service(
+                &args.name,
+                ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+            )
+            .map_err(|err| service_error("open", &args.name, err))?;
+        if let Ok(status) = service.query_status() {
+            if status.current_state.ne(&ServiceState::Stopped) {
+                service.stop().ok();
+            }
+        }
+        service
+            .delete()
+            .map_err(|err| service_error("delete", &args.name, err))?;
+        println!(
+            "{} Uninstalled the {} service",
+            "OK".bold().green(),
+            args.name
+        );
+        Ok(())
+    }
+
+    fn start_service(&self, args: ServiceNameArgs) -> Result<(), OreError> {
+        let manager = manager(ServiceManagerAccess::CONNECT)?;
+        manager
+            .open_service(&args.name, ServiceAccess::START)
+            .and_then(|service| service.start::<OsString>(&[]))
+            .map_err(|err| service_error("start", &args.name, err))?;
+        println!("{} Started the {} service", "OK".bold().green(), args.name);
+        Ok(())
+    }
+
+    fn stop_service(&self, args: ServiceNameArgs) -> Result<(), OreError> {
+        let manager = manager(ServiceManagerAccess::CONNECT)?;
+        manager
+            .open_service(&args.name, ServiceAccess::STOP)
+            .and_then(|service| service.stop())
+            .map_err(|err| service_error("stop", &args.name, err))?;
+        println!("{} Stopped the {} service", "OK".bold().green(), args.name);
+        Ok(())
+    }
+}
+
+/// Whether the account is a built-in or virtual service account, which has no password.
+fn is_service_account(account: &str) -> bool {
+    let account = account.to_uppercase();
+    ["NT AUTHORITY\\", "NT SERVICE\\"]
+        .iter()
+        .any(|prefix| account.starts_with(prefix))
+        || account.eq("LOCALSYSTEM")
+}
+
+fn manager(access: ServiceManagerAccess) -> Result<ServiceManager, OreError> {
+    ServiceManager::local_computer(None::<&str>, access).map_err(|err| {
+        OreError::Service(format!(
+            "Could not connect to the service manager: {}. Run from an administrator terminal.",
+            err
+        ))
+    })
+}
+
+fn service_error(action: &str, name: &str, err: windows_service::Error) -> OreError {
+    OreError::Service(format!(
+        "Failed to {} the {} service: {}",
+        action, name, err
+    ))
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Hands this process to the service control manager, which calls `service_main`.
+fn run(name: String) -> Result<(), OreError> {
+    service_dispatcher::start(&name, ffi_service_main).map_err(|err| {
+        OreError::Service(format!(
+            "Failed to start the {} service: {}. `ore service run` is started by Windows; use `ore service start`.",
+            name, err
+        ))
+    })
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    // The service's own arguments are `service run --name NAME --home HOME -- COMMAND`
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    let flag = |name: &str| {
+        argv.iter()
+            .position(|arg| arg.eq(name))
+            .and_then(|i| argv.get(i + 1))
+            .cloned()
+    };
+    let name = flag("--name").unwrap_or_else(|| OsString::from("ore"));
+    let home = flag("--home").map(PathBuf::from);
+    let command = argv
+        .iter()
+        .position(|arg| arg.eq("--"))
+        .map(|i| argv[i + 1..].to_vec())
+        .unwrap_or_default();
+    supervise(&name.to_string_lossy(), home, command);
+}
+
+/// Runs the miner until it exits or the service is stopped, and reports its exit code.
+/// Windows restarts the service when the code is not zero.
+fn supervise(name: &str, home: Option<PathBuf>, command: Vec<OsString>) {
+    // Handle stop requests
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let handler = move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            stop_tx.send(()).ok();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let Ok(status_handle) = service_control_handler::register(name, handler) else {
+        return;
+    };
+    let report = |state: ServiceState, code: u32| {
+        status_handle
+            .set_service_status(ServiceStatus {
+                service_type: ServiceType::OWN_PROCESS,
+                current_state: state,
+                controls_accepted: match state {
+                    ServiceState::Running => {
+                        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+                    }
+                    _ => ServiceControlAccept::empty(),
+                },
+                exit_code: ServiceExitCode::Win32(code),
+                checkpoint: 0,
+                wait_hint: match state {
+                    ServiceState::StopPending => STOP_TIMEOUT,
+                    _ => Duration::default(),
+                },
+                process_id: None,
+            })
+            .ok();
+    };
+
+    // Start the miner, with the config and keypairs of the user who installed the service
+    let mut child = Command::new(std::env::current_exe().unwrap_or_default());
+    child
+        .args(&command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(home) = home {
+        child.env("USERPROFILE", &home).env("HOME", &home);
+    }
+    let mut child = match child.spawn() {
+        Ok(child) => child,
+        Err(_) => {
+            report(ServiceState::Stopped, 1);
+            return;
+        }
+    };
+    report(ServiceState::Running, 0);
+
+    // Wait for the miner to exit, or for a stop request
+    let code = loop {
+        if stop_rx.recv_timeout(Duration::from_secs(1)).is_ok() {
+            report(ServiceState::StopPending, 0);
+            terminate(child.id()).ok();
+            let started_at = std::time::Instant::now();
+            while matches!(child.try_wait(), Ok(None)) && started_at.elapsed().lt(&STOP_TIMEOUT) {
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            child.kill().ok();
+            child.wait().ok();
+            break 0;
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break status.code().unwrap_or(1) as u32,
+            Ok(None) => {}
+            Err(_) => break 1,
+        }
+    };
+    report(ServiceState::Stopped, code);
+}
@@ -0,0 +1,100 @@
+use std::{
+    path::PathBuf,
+    sync::{atomic::Ordering, OnceLock},
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use solana_program::native_token::lamports_to_sol;
+use tracing::error;
+
+use crate::{
+    metrics::{metrics, status},
+    notify::{notify_and_wait, Event},
+    output::print_json,
+    schema::MineSummaryDocument,
+    utils::amount_u64_to_string,
+};
+
+static SESSION: OnceLock<Session> = OnceLock::new();
+
+/// A mining session, summarized when the miner exits.
+struct Session {
+    started_at: Instant,
+    summary_file: Option<PathBuf>,
+    json: bool,
+}
+
+/// Starts timing the mining session.
+pub fn start(summary_file: Option<PathBuf>, json: bool) {
+    SESSION
+        .set(Session {
+            started_at: Instant::now(),
+            summary_file,
+            json,
+        })
+        .ok();
+}
+
+/// Prints the session summary and writes it to the summary file, if configured.
+pub fn finish() {
+    let Some(session) = SESSION.get() else {
+        return;
+    };
+    let summary = summary(session.started_at.elapsed());
+    if let Some(path) = &session.summary_file {
+        let contents = serde_json::to_string_pretty(&summary).unwrap_or_default();
+        if let Err(err) = std::fs::write(path, contents) {
+            error!("Failed to write {:?}: {}", path, err);
+        }
+    }
+    if session.json {
+        print_json(&summary);
+        return;
+    }
+    println!("\n{}", "Session summary".bold());
+    println!(
+        "  Runtime: {}",
+        format_runtime(session.started_at.elapsed())
+    );
+    println!(
+        "  Rounds: {} attempted, {} landed",
+        summary.rounds, summary.landed
+    );
+    println!("  Earned: {} ORE", amount_u64_to_string(summary.rewards));
+    println!("  Fees: {} SOL", lamports_to_sol(summary.fees));
+    println!("  Average difficulty: {:.2}", summary.average_difficulty);
+    println!("  Average hashrate: {} H/sec", summary.average_hashrate);
+}
+
+/// Prints the session summary, alerts that the miner stopped, and exits.
+pub async fn stop(reason: String, code: i32) -> ! {
+    finish();
+    notify_and_wait(Event::Stopped { reason }).await;
+    std::process::exit(code);
+}
+
+fn summary(runtime: Duration) -> MineSummaryDocument {
+    let metrics = metrics();
+    let status = status();
+    let rounds = status.difficulties.values().sum::<u64>();
+    let difficulty_sum = status
+        .difficulties
+        .iter()
+        .map(|(difficulty, count)| *difficulty as u64 * count)
+        .sum::<u64>();
+    MineSummaryDocument {
+        runtime: runtime.as_secs(),
+        rounds,
+        landed: metrics.landed.load(Ordering::Relaxed),
+        rewards: metrics.rewards.load(Ordering::Relaxed),
+        fees: metrics.fees.load(Ordering::Relaxed),
+        average_difficulty: difficulty_sum as f64 / rounds.max(1) as f64,
+        average_hashrate: status.hashrate_sum / rounds.max(1),
+    }
+}
+
+fn format_runtime(runtime: Duration) -> String {
+    let secs = runtime.as_secs();
+    format!("{}h {}m {}s", secs / 3600, secs % 3600 / 60, secs % 60)
+}
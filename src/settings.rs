@@ -0,0 +1,603 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use clap::{builder::ArgAction, Arg, Command};
+use toml::{Table, Value};
+
+use crate::utils::ore_dir;
+
+const SETTINGS_FILE: &str = "config.toml";
+
+/// Returns the path of the ore-cli config file.
+pub fn path() -> PathBuf {
+    ore_dir().join(SETTINGS_FILE)
+}
+
+/// Loads the ore-cli config file, if it exists.
+pub fn load() -> Option<Table> {
+    try_load().unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Loads the ore-cli config file like [`load`], returning parse errors.
+fn try_load() -> Result<Option<Table>, String> {
+    let path = path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    contents
+        .parse::<Table>()
+        .map(Some)
+        .map_err(|err| format!("Could not parse {}: {}", path.display(), err))
+}
+
+/// Returns the named profiles in the config file.
+pub fn profiles(table: &Table) -> Vec<String> {
+    match table.get("profiles") {
+        Some(Value::Table(profiles)) => profiles.keys().cloned().collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns the profile to use by default, if set.
+pub fn default_profile(table: &Table) -> Option<String> {
+    table
+        .get("default_profile")
+        .and_then(|value| value.as_str())
+        .map(|profile| profile.to_string())
+}
+
+/// Adds flag defaults from the config file and `ORE_*` environment variables to the
+/// command line arguments. Flags on the command line override the config file, which
+/// overrides the environment.
+pub fn with_defaults(command: &Command, args: Vec<OsString>) -> Vec<OsString> {
+    try_with_defaults(command, args).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Adds flag defaults like [`with_defaults`], returning errors in the config file instead
+/// of exiting, e.g. to reload it into a running miner.
+pub fn try_with_defaults(command: &Command, args: Vec<OsString>) -> Result<Vec<OsString>, String> {
+    load_env_file(&args)?;
+    let subcommand = args
+        .iter()
+        .skip(1)
+        .filter_map(|arg| arg.to_str())
+        .find_map(|arg| command.find_subcommand(arg));
+
+    // Validating the config file must not fail on the mistakes it reports
+    if subcommand.is_some_and(|s| s.get_name().eq("config"))
+        && args.iter().any(|arg| arg.eq("validate"))
+    {
+        return Ok(args);
+    }
+    let args = match try_load()? {
+        Some(table) => with_file_defaults(&table, command, subcommand, args)?,
+        None => args,
+    };
+    Ok(with_env_defaults(command, subcommand, args))
+}
+
+/// Loads environment variables from the `--env-file`, or from `.env` in the working
+/// directory if it exists. Variables already set in the environment are kept.
+fn load_env_file(args: &[OsString]) -> Result<(), String> {
+    match flag_value(args, "--env-file") {
+        Some(path) => dotenvy::from_path(&path)
+            .map(|_| ())
+            .map_err(|err| format!("Could not load {}: {}", path, err)),
+        None => match dotenvy::dotenv() {
+            Err(err) if !err.not_found() => Err(format!("Could not load .env: {}", err)),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Returns the environment variable for a flag, e.g. `ORE_PRIORITY_FEE` for
+/// `--priority-fee`.
+fn env_var(long: &str) -> String {
+    format!("ORE_{}", long.to_uppercase().replace('-', "_"))
+}
+
+/// Flags that are never read from `ORE_*` environment variables. A keypair source like
+/// `--keypair env:ORE_KEYPAIR_SECRET` names a variable holding the key itself, which must
+/// not become the flag value.
+const ENV_EXCLUDED: &[&str] = &["keypair", "fee-payer-filepath"];
+
+/// Adds the flags set in `ORE_*` environment variables, unless they are already given.
+fn with_env_defaults(
+    command: &Command,
+    subcommand: Option<&Command>,
+    mut args: Vec<OsString>,
+) -> Vec<OsString> {
+    let mut defaults = vec![];
+    for arg in command
+        .get_arguments()
+        .chain(subcommand.into_iter().flat_map(|s| s.get_arguments()))
+    {
+        let Some(long) = arg.get_long().filter(|long| !ENV_EXCLUDED.contains(long)) else {
+            continue;
+        };
+        let Ok(value) = std::env::var(env_var(long)) else {
+            continue;
+        };
+        if is_present(arg, &args) {
+            continue;
+        }
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => Value::Boolean(matches!(
+                value.to_lowercase().as_str(),
+                "1" | "true" | "yes"
+            )),
+            ArgAction::Count => Value::Integer(value.parse().unwrap_or(0)),
+            _ => Value::String(value),
+        };
+        push_flag(arg, &value, &mut defaults);
+    }
+    args.extend(defaults);
+    args
+}
+
+/// Adds the flags set in the config file, unless they are already given. Top-level keys
+/// apply to every command, and a section named after a command, e.g. `[mine]`, overrides
+/// them for that command. The selected profile, e.g. `[profiles.devnet]`, overrides
+/// both, and may have command sections of its own.
+fn with_file_defaults(
+    table: &Table,
+    command: &Command,
+    subcommand: Option<&Command>,
+    args: Vec<OsString>,
+) -> Result<Vec<OsString>, String> {
+    // Find the profile
+    let profile = match flag_value(&args, "--profile")
+        .or_else(|| std::env::var(env_var("profile")).ok())
+        .or_else(|| default_profile(table))
+    {
+        Some(name) => match table.get("profiles").and_then(|p| p.get(&name)) {
+            Some(Value::Table(profile)) => Some(profile),
+            _ => {
+                return Err(format!(
+                    "Unknown profile {:?} in {}",
+                    name,
+                    path().display()
+                ))
+            }
+        },
+        None => None,
+    };
+
+    // Collect settings, letting later layers override earlier ones
+    let mut sections = vec![];
+    for layer in std::iter::once(table).chain(profile) {
+        sections.push((layer, false));
+        if let Some(Value::Table(section)) = subcommand.and_then(|s| layer.get(s.get_name())) {
+            sections.push((section, true));
+        }
+    }
+    let mut settings: Vec<(&String, &Value, bool)> = vec![];
+    for (section, in_section) in sections {
+        for (key, value) in section.iter().filter(|(_, value)| !value.is_table()) {
+            settings.retain(|(k, _, _)| k.ne(&key));
+            settings.push((key, value, in_section));
+        }
+    }
+
+    // Append flags that are not on the command line
+    let mut args = args;
+    let mut defaults = vec![];
+    for (key, value, in_section) in settings {
+        let long = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .chain(subcommand.into_iter().flat_map(|s| s.get_arguments()))
+            .find(|arg| arg.get_long().eq(&Some(long.as_str())));
+        // Top-level settings may be meant for other commands
+        let Some(arg) = arg else {
+            if in_section {
+                eprintln!("warning: Unknown setting {:?} in {}", key, path().display());
+            }
+            continue;
+        };
+        if is_present(arg, &args) {
+            continue;
+        }
+        push_flag(arg, value, &mut defaults);
+    }
+    args.extend(defaults);
+    Ok(args)
+}
+
+/// Returns the value of a flag on the command line, given as `--flag value` or
+/// `--flag=value`.
+fn flag_value(args: &[OsString], flag: &str) -> Option<String> {
+    let args: Vec<&str> = args.iter().filter_map(|arg| arg.to_str()).collect();
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg.eq(&flag) {
+            args.get(i + 1).map(|value| value.to_string())
+        } else {
+            arg.strip_prefix(&format!("{}=", flag))
+                .map(|value| value.to_string())
+        }
+    })
+}
+
+/// Returns whether the flag is given on the command line.
+fn is_present(arg: &Arg, args: &[OsString]) -> bool {
+    let long = arg.get_long().map(|long| format!("--{}", long));
+    let short = arg.get_short().map(|short| format!("-{}", short));
+    args.iter().filter_map(|arg| arg.to_str()).any(|arg| {
+        long.as_ref()
+            .is_some_and(|long| arg.eq(long) || arg.starts_with(&format!("{}=", long)))
+            || short
+                .as_ref()
+                .is_some_and(|short| !arg.starts_with("--") && arg.starts_with(short))
+    })
+}
+
+/// Converts a setting to command line arguments for the flag.
+fn push_flag(arg: &Arg, value: &Value, args: &mut Vec<OsString>) {
+    let flag = OsString::from(format!("--{}", arg.get_long().unwrap_or_default()));
+    match (arg.get_action(), value) {
+        (ArgAction::SetTrue, Value::Boolean(true)) => args.push(flag),
+        (ArgAction::SetTrue, Value::Boolean(false)) => {}
+        (ArgAction::Count, Value::Integer(count)) => {
+            args.extend((0..*count).map(|_| flag.clone()));
+        }
+        (_, Value::Array(values)) => {
+            for value in values {
+                args.push(flag.clone());
+                args.push(to_arg(value));
+            }
+        }
+        (_, value) => {
+            args.push(flag);
+            args.push(to_arg(value));
+        }
+    }
+}
+
+fn to_arg(value: &Value) -> OsString {
+    match value {
+        Value::String(s) => s.into(),
+        value => value.to_string().into(),
+    }
+}
+
+/// A setting in effect for a section of the config file.
+pub struct Setting {
+    /// The command the setting applies to, or none if it applies to every command.
+    pub section: Option<String>,
+    pub key: String,
+    pub value: Value,
+    /// Where the setting comes from, e.g. `config file` or `profile devnet`.
+    pub source: String,
+}
+
+/// The problems found in the config file and the settings it resolves to.
+pub struct Validation {
+    pub profile: Option<String>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub settings: Vec<Setting>,
+}
+
+/// Checks the config file against the flags of the command, reporting unknown keys,
+/// invalid values, and settings that conflict or have no effect.
+pub fn validate(command: &Command, args: &[OsString]) -> Validation {
+    let mut validation = Validation {
+        profile: None,
+        errors: vec![],
+        warnings: vec![],
+        settings: vec![],
+    };
+    let table = match std::fs::read_to_string(path()) {
+        Ok(contents) => match contents.parse::<Table>() {
+            Ok(table) => table,
+            Err(err) => {
+                validation.errors.push(format!("Could not parse: {}", err));
+                return validation;
+            }
+        },
+        Err(_) => Table::new(),
+    };
+
+    // Check every layer of the file
+    check_layer(command, &table, "", &mut validation.errors);
+    if let Some(value) = table.get("default_profile") {
+        match value.as_str() {
+            Some(name) if profiles(&table).contains(&name.to_string()) => {}
+            Some(name) => validation
+                .errors
+                .push(format!("default_profile {:?} is not a profile", name)),
+            None => validation
+                .errors
+                .push("default_profile must be a string".to_string()),
+        }
+    }
+    match table.get("profiles") {
+        Some(Value::Table(profiles)) => {
+            for (name, profile) in profiles {
+                match profile {
+                    Value::Table(profile) => check_layer(
+                        command,
+                        profile,
+                        &format!("profiles.{}.", name),
+                        &mut validation.errors,
+                    ),
+                    _ => validation
+                        .errors
+                        .push(format!("[profiles.{}] must be a table", name)),
+                }
+            }
+        }
+        Some(_) => validation
+            .errors
+            .push("[profiles] must be a table".to_string()),
+        None => {}
+    }
+
+    // Find the profile, as with_file_defaults would
+    let name = flag_value(args, "--profile")
+        .or_else(|| std::env::var(env_var("profile")).ok())
+        .or_else(|| default_profile(&table));
+    let profile = match &name {
+        Some(name) => match table.get("profiles").and_then(|p| p.get(name)) {
+            Some(Value::Table(profile)) => Some(profile),
+            _ => {
+                validation
+                    .errors
+                    .push(format!("Profile {:?} does not exist", name));
+                None
+            }
+        },
+        None => None,
+    };
+    validation.profile = name;
+
+    // Resolve the settings of each section, letting later layers override earlier ones
+    let mut layers = vec![(&table, "config file".to_string())];
+    if let (Some(profile), Some(name)) = (profile, &validation.profile) {
+        layers.push((profile, format!("profile {}", name)));
+    }
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long().filter(|long| !ENV_EXCLUDED.contains(long)) else {
+            continue;
+        };
+        if let Ok(value) = std::env::var(env_var(long)) {
+            resolve(
+                &mut validation.settings,
+                None,
+                long.replace('-', "_"),
+                Value::String(value),
+                env_var(long),
+            );
+        }
+    }
+    for (layer, source) in layers.iter() {
+        for (key, value) in layer.iter().filter(|(key, _)| is_setting(key)) {
+            let known = find_arg(command.get_arguments(), key).is_some()
+                || command
+                    .get_subcommands()
+                    .any(|subcommand| find_arg(subcommand.get_arguments(), key).is_some());
+            if known && !value.is_table() {
+                resolve(
+                    &mut validation.settings,
+                    None,
+                    key.clone(),
+                    value.clone(),
+                    source.clone(),
+                );
+            }
+        }
+    }
+    for (layer, source) in layers.iter() {
+        for (key, section) in layer.iter().filter(|(key, _)| is_setting(key)) {
+            let (Value::Table(section), Some(subcommand)) = (section, command.find_subcommand(key))
+            else {
+                continue;
+            };
+            for (k, value) in section.iter().filter(|(k, value)| {
+                !value.is_table()
+                    && find_arg(command.get_arguments(), k)
+                        .or_else(|| find_arg(subcommand.get_arguments(), k))
+                        .is_some()
+            }) {
+                resolve(
+                    &mut validation.settings,
+                    Some(key.clone()),
+                    k.clone(),
+                    value.clone(),
+                    source.clone(),
+                );
+            }
+        }
+    }
+
+    validation
+        .settings
+        .sort_by(|a, b| a.section.cmp(&b.section));
+
+    // Check for settings that conflict or have no effect
+    let mut sections: Vec<Option<String>> = vec![None];
+    for setting in validation.settings.iter() {
+        if !sections.contains(&setting.section) {
+            sections.push(setting.section.clone());
+        }
+    }
+    let mut global_problems = vec![];
+    for section in sections {
+        let effective = |key: &str| {
+            validation.settings.iter().rev().find(|setting| {
+                setting.key.eq(key) && (setting.section.is_none() || setting.section.eq(&section))
+            })
+        };
+        let mut problems = vec![];
+        if effective("dynamic_fee_strategy").is_some() && effective("dynamic_fee_url").is_none() {
+            problems.push((
+                false,
+                "dynamic_fee_strategy has no effect without dynamic_fee_url".to_string(),
+            ));
+        }
+        if effective("dynamic_fee_url").is_some() && effective("priority_fee").is_some() {
+            problems.push((
+                false,
+                "priority_fee is ignored when dynamic_fee_url is set".to_string(),
+            ));
+        }
+        for key in ["log_max_size", "log_rotation", "log_retention"] {
+            if effective(key).is_some() && effective("log_file").is_none() {
+                problems.push((false, format!("{} has no effect without log_file", key)));
+            }
+        }
+        let enabled = |key: &str| {
+            effective(key).is_some_and(|setting| {
+                !matches!(setting.value, Value::Boolean(false) | Value::Integer(0))
+            })
+        };
+        if enabled("quiet") && enabled("verbose") {
+            problems.push((true, "quiet conflicts with verbose".to_string()));
+        }
+        for (is_error, problem) in problems {
+            // Sections inherit the problems of the top level, which are reported once
+            if global_problems.contains(&problem) {
+                continue;
+            }
+            if section.is_none() {
+                global_problems.push(problem.clone());
+            }
+            let problem = match &section {
+                Some(section) => format!("{} (in [{}])", problem, section),
+                None => problem,
+            };
+            let list = match is_error {
+                true => &mut validation.errors,
+                false => &mut validation.warnings,
+            };
+            if !list.contains(&problem) {
+                list.push(problem);
+            }
+        }
+    }
+    validation
+}
+
+/// Returns false for the keys that select profiles rather than set flags.
+fn is_setting(key: &str) -> bool {
+    key.ne("profiles") && key.ne("default_profile")
+}
+
+/// Records a setting, replacing the one it overrides.
+fn resolve(
+    settings: &mut Vec<Setting>,
+    section: Option<String>,
+    key: String,
+    value: Value,
+    source: String,
+) {
+    settings.retain(|setting| setting.section.ne(&section) || setting.key.ne(&key));
+    settings.push(Setting {
+        section,
+        key,
+        value,
+        source,
+    });
+}
+
+/// Checks the keys of the top level or a profile, and the command sections in it.
+fn check_layer(command: &Command, layer: &Table, prefix: &str, errors: &mut Vec<String>) {
+    for (key, value) in layer.iter() {
+        if !is_setting(key) {
+            if !prefix.is_empty() {
+                errors.push(format!(
+                    "{} is not allowed in [{}]",
+                    key,
+                    prefix.trim_end_matches('.')
+                ));
+            }
+            continue;
+        }
+        match value {
+            Value::Table(section) => match command.find_subcommand(key) {
+                Some(subcommand) => {
+                    let location = format!("[{}{}]", prefix, key);
+                    for (k, value) in section {
+                        let arg = find_arg(command.get_arguments(), k)
+                            .or_else(|| find_arg(subcommand.get_arguments(), k));
+                        check_setting(arg, k, value, &location, errors);
+                    }
+                }
+                None => errors.push(format!("Unknown section [{}{}]", prefix, key)),
+            },
+            value => {
+                let location = match prefix {
+                    "" => "the top level".to_string(),
+                    prefix => format!("[{}]", prefix.trim_end_matches('.')),
+                };
+                // Top-level settings may be meant for any command
+                let arg = find_arg(command.get_arguments(), key).or_else(|| {
+                    command
+                        .get_subcommands()
+                        .find_map(|subcommand| find_arg(subcommand.get_arguments(), key))
+                });
+                check_setting(arg, key, value, &location, errors);
+            }
+        }
+    }
+}
+
+fn find_arg<'a>(mut args: impl Iterator<Item = &'a Arg>, key: &str) -> Option<&'a Arg> {
+    let long = key.replace('_', "-");
+    args.find(|arg| arg.get_long().eq(&Some(long.as_str())))
+}
+
+/// Checks that a setting names a flag and has a value the flag accepts.
+fn check_setting(
+    arg: Option<&Arg>,
+    key: &str,
+    value: &Value,
+    location: &str,
+    errors: &mut Vec<String>,
+) {
+    let Some(arg) = arg else {
+        errors.push(format!("Unknown setting {:?} in {}", key, location));
+        return;
+    };
+    let result = match (arg.get_action(), value) {
+        (ArgAction::SetTrue, Value::Boolean(_)) => Ok(()),
+        (ArgAction::SetTrue, _) => Err("expected true or false".to_string()),
+        (ArgAction::Count, Value::Integer(count)) if count.ge(&0) => Ok(()),
+        (ArgAction::Count, _) => Err("expected a count".to_string()),
+        (_, Value::Table(_)) => Err("expected a value, not a table".to_string()),
+        (_, Value::Array(values)) => values.iter().try_for_each(|value| parse_value(arg, value)),
+        (_, value) => parse_value(arg, value),
+    };
+    if let Err(err) = result {
+        errors.push(format!(
+            "Invalid setting {:?} in {}: {}",
+            key, location, err
+        ));
+    }
+}
+
+fn parse_value(arg: &Arg, value: &Value) -> Result<(), String> {
+    // Parse the value alone, without the other flags it may require or conflict with
+    let long = arg.get_long().unwrap_or_default();
+    Command::new("ore")
+        .no_binary_name(true)
+        .arg(
+            Arg::new("value")
+                .long("value")
+                .action(ArgAction::Set)
+                .value_parser(arg.get_value_parser().clone()),
+        )
+        .try_get_matches_from([OsString::from("--value"), to_arg(value)])
+        .map(|_| ())
+        .map_err(|err| {
+            let err = err.to_string();
+            let line = err.lines().next().unwrap_or_default();
+            line.trim_start_matches("error: ")
+                .replace("--value <value>", &format!("--{}", long))
+        })
+}
@@ -0,0 +1,169 @@
+use std::{path::Path, sync::Arc};
+
+use colored::*;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::native_token::lamports_to_sol;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{write_keypair_file, Keypair, Signer},
+};
+use tracing::error;
+
+use crate::{
+    args::SetupArgs,
+    settings, signer,
+    timeout::rpc_timeout,
+    utils::{ask, ask_yes_no, assume_yes},
+    Miner,
+};
+
+const DEFAULT_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+
+impl Miner {
+    pub async fn setup(&self, _args: SetupArgs) {
+        let path = settings::path();
+        println!("{}", "ORE setup".bold());
+        println!("This writes your settings to {}\n", path.display());
+
+        // RPC
+        let mut rpc = self.rpc_client.url();
+        if rpc.is_empty() {
+            rpc = DEFAULT_RPC_URL.to_string();
+        }
+        let rpc_client = loop {
+            rpc = ask("RPC URL", &rpc);
+            let rpc_client = Arc::new(RpcClient::new_with_timeout_and_commitment(
+                rpc.clone(),
+                rpc_timeout(),
+                CommitmentConfig::confirmed(),
+            ));
+            match rpc_client.get_version().await {
+                Ok(version) => {
+                    println!("  {} Solana {}", "OK".bold().green(), version.solana_core);
+                    break rpc_client;
+                }
+                Err(err) => {
+                    println!("  {} {}", "ERROR".bold().red(), err);
+                    if ask_yes_no("  Use this RPC anyway?", false) {
+                        break rpc_client;
+                    }
+                    if assume_yes() {
+                        error!("Could not connect to {}", rpc);
+                        return;
+                    }
+                }
+            }
+        };
+
+        // Keypair
+        let default_keypair = self
+            .keypair_filepath
+            .clone()
+            .or_else(|| solana_cli_config::Config::default().keypair_path.into())
+            .unwrap_or_default();
+        let (keypair, pubkey) = loop {
+            let keypair = ask("Keypair path", &default_keypair);
+            if Path::new(&keypair).exists() || keypair.contains("://") {
+                match signer::from_uri(&keypair) {
+                    Ok(signer) => {
+                        println!("  {} {}", "OK".bold().green(), signer.pubkey());
+                        break (keypair, signer.pubkey());
+                    }
+                    Err(err) => println!("  {} {}", "ERROR".bold().red(), err),
+                }
+                if assume_yes() {
+                    error!("Could not read keypair {}", keypair);
+                    return;
+                }
+            } else if ask_yes_no("  No keypair found. Generate a new one?", true) {
+                let signer = Keypair::new();
+                if let Some(dir) = Path::new(&keypair).parent() {
+                    std::fs::create_dir_all(dir).ok();
+                }
+                match write_keypair_file(&signer, &keypair) {
+                    Ok(_) => {
+                        println!("  {} Wrote {}", "OK".bold().green(), signer.pubkey());
+                        break (keypair, signer.pubkey());
+                    }
+                    Err(err) => {
+                        println!("  {} {}", "ERROR".bold().red(), err);
+                        if assume_yes() {
+                            error!("Could not write keypair {}", keypair);
+                            return;
+                        }
+                    }
+                }
+            }
+        };
+        match rpc_client.get_balance(&pubkey).await {
+            Ok(0) => println!(
+                "  {} The keypair has no SOL. Fund it to pay transaction fees.",
+                "WARNING".bold().yellow()
+            ),
+            Ok(balance) => println!("  Balance: {} SOL", lamports_to_sol(balance)),
+            Err(_) => {}
+        }
+
+        // Fees
+        let dynamic_fee_url = if ask_yes_no("Use dynamic priority fees?", false) {
+            Some(ask("Dynamic fee RPC URL", &rpc))
+        } else {
+            None
+        };
+        let (priority_fee, dynamic_fee_max) = match dynamic_fee_url {
+            Some(_) => (
+                None,
+                Some(ask_number("Maximum priority fee (microlamports)", 500000)),
+            ),
+            None => (Some(ask_number("Priority fee (microlamports)", 0)), None),
+        };
+
+        // Threads
+        let threads = ask_number("Mining threads", num_cpus::get() as u64);
+
+        // Write config
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        let mut document = match contents.parse::<toml_edit::DocumentMut>() {
+            Ok(document) => document,
+            Err(err) => {
+                error!("Could not parse {}: {}", path.display(), err);
+                return;
+            }
+        };
+        document["rpc"] = toml_edit::value(rpc);
+        document["keypair"] = toml_edit::value(keypair);
+        if let Some(url) = dynamic_fee_url {
+            document["dynamic_fee_url"] = toml_edit::value(url);
+            document.remove("priority_fee");
+        } else {
+            document.remove("dynamic_fee_url");
+        }
+        if let Some(fee) = priority_fee {
+            document["priority_fee"] = toml_edit::value(fee as i64);
+        }
+        if let Some(fee) = dynamic_fee_max {
+            document["dynamic_fee_max"] = toml_edit::value(fee as i64);
+        }
+        if !document.contains_table("mine") {
+            document["mine"] = toml_edit::table();
+        }
+        document["mine"]["threads"] = toml_edit::value(threads as i64);
+        match std::fs::write(&path, document.to_string()) {
+            Ok(_) => println!(
+                "\n{} Saved {}. Run `ore mine` to start mining.",
+                "OK".bold().green(),
+                path.display()
+            ),
+            Err(err) => error!("Failed to write {}: {}", path.display(), err),
+        }
+    }
+}
+
+fn ask_number(question: &str, default: u64) -> u64 {
+    loop {
+        match ask(question, &default.to_string()).parse() {
+            Ok(value) => return value,
+            Err(_) => println!("  Please enter a whole number."),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    future::Future,
+    io::{BufRead, Write},
+    panic::AssertUnwindSafe,
+    pin::Pin,
+};
+
+use clap::{CommandFactory, Parser};
+use colored::*;
+use futures::FutureExt;
+
+use tracing::error;
+
+use crate::{args::ShellArgs, cli::Commands, error::OreError, exit, settings, Miner};
+
+/// A line entered in the shell, parsed as a subcommand.
+#[derive(Parser, Debug)]
+#[command(
+    name = "ore",
+    about = "Type a subcommand to run it, or exit to quit",
+    disable_version_flag = true
+)]
+struct ShellLine {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+impl Miner {
+    pub async fn shell(&self, _args: ShellArgs) {
+        println!("ORE shell. Type `help` to list commands, or `exit` to quit.");
+        let stdin = std::io::stdin();
+        loop {
+            // Read a line
+            print!("{} ", "ore>".bold());
+            std::io::stdout().flush().ok();
+            let mut line = String::new();
+            match stdin.lock().read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let mut words = match shell_words::split(&line) {
+                Ok(words) => words,
+                Err(err) => {
+                    println!("{} {}", "ERROR".bold().red(), err);
+                    continue;
+                }
+            };
+            match words.first().map(|word| word.as_str()) {
+                None => continue,
+                Some("exit") | Some("quit") => break,
+                _ => {}
+            }
+
+            // Parse it as a subcommand, with defaults from the config file
+            words.insert(0, "ore".to_string());
+            let args = settings::with_defaults(
+                &ShellLine::command(),
+                words.into_iter().map(Into::into).collect(),
+            );
+            let command = match ShellLine::try_parse_from(args) {
+                Ok(line) => line.command,
+                Err(err) => {
+                    err.print().ok();
+                    continue;
+                }
+            };
+            if let Commands::Shell(_) = command {
+                println!("Already in the shell");
+                continue;
+            }
+
+            // Run it, keeping the shell open if it fails
+            exit::reset();
+            let run: Pin<Box<dyn Future<Output = Result<(), OreError>> + '_>> =
+                Box::pin(self.run(command));
+            match AssertUnwindSafe(run).catch_unwind().await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => {
+                    error!("{}", err);
+                    exit::set(err.exit_code());
+                }
+                Err(_) => {
+                    exit::set(exit::ERROR);
+                    println!("{} Command failed", "ERROR".bold().red());
+                }
+            }
+        }
+    }
+}
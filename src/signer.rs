@@ -0,0 +1,263 @@
+use std::{
+    net::IpAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use reqwest::Url;
+use serde_json::{json, Value};
+use solana_remote_wallet::{
+    locator::Locator,
+    remote_keypair::generate_remote_keypair,
+    remote_wallet::{initialize_wallet_manager, RemoteWalletError},
+};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    pubkey::Pubkey,
+    signature::{Signature, Signer, SignerError},
+};
+use tokio::runtime::Handle;
+
+use crate::{keypair, timeout::http_timeout};
+
+/// The environment variable with a bearer token for remote signers.
+pub const SIGNER_TOKEN_VAR: &str = "ORE_SIGNER_TOKEN";
+
+/// A signer that can be shared across tasks.
+pub type BoxedSigner = Box<dyn Signer + Send + Sync>;
+
+/// Creates signers for keypair URIs, e.g. a file path or `usb://ledger`.
+pub trait SignerProvider: Send + Sync {
+    fn signer(&self, uri: &str) -> Result<BoxedSigner, String>;
+}
+
+/// Returns the provider for the scheme of the keypair URI.
+pub fn provider(uri: &str) -> &'static dyn SignerProvider {
+    if uri.starts_with("usb://") {
+        &LedgerProvider
+    } else if uri.starts_with("http://") || uri.starts_with("https://") {
+        &RemoteProvider
+    } else {
+        &KeypairProvider
+    }
+}
+
+/// Signers resolved from keypair URIs, kept so devices and remote signers are only
+/// queried for their pubkey once.
+static LOADED_SIGNERS: Mutex<Vec<(String, SharedSigner)>> = Mutex::new(vec![]);
+
+/// Creates the signer for a keypair URI with the provider for its scheme, or returns the
+/// one already created for it.
+pub fn from_uri(uri: &str) -> Result<BoxedSigner, String> {
+    let mut signers = LOADED_SIGNERS.lock().unwrap();
+    if let Some((_, signer)) = signers.iter().find(|(loaded, _)| loaded.eq(uri)) {
+        return Ok(Box::new(signer.clone()));
+    }
+    let signer = SharedSigner(Arc::from(provider(uri).signer(uri)?));
+    signers.push((uri.to_string(), signer.clone()));
+    Ok(Box::new(signer))
+}
+
+/// A signer shared by every caller that resolved the same keypair URI.
+#[derive(Clone)]
+struct SharedSigner(Arc<dyn Signer + Send + Sync>);
+
+impl Signer for SharedSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.0.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.0.try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.0.is_interactive()
+    }
+}
+
+/// Reads keypairs from files, environment variables, stdin, the OS keyring, or a seed
+/// phrase. See [`keypair::read_keypair`].
+pub struct KeypairProvider;
+
+impl SignerProvider for KeypairProvider {
+    fn signer(&self, uri: &str) -> Result<BoxedSigner, String> {
+        Ok(Box::new(keypair::read_keypair(uri)?))
+    }
+}
+
+/// Signs with a Ledger, e.g. `usb://ledger?key=0/0`. The derivation path defaults to
+/// `--derivation-path`, then to `m/44'/501'`.
+pub struct LedgerProvider;
+
+impl SignerProvider for LedgerProvider {
+    fn signer(&self, uri: &str) -> Result<BoxedSigner, String> {
+        if !cfg!(feature = "ledger") {
+            return Err("Ledger support is disabled. Build ore with --features ledger.".into());
+        }
+        let (path, query) = uri.split_once('?').unwrap_or((uri, ""));
+        let locator = Locator::new_from_path(path).map_err(|err| err.to_string())?;
+        let derivation_path = match query.strip_prefix("key=") {
+            Some(key) => DerivationPath::from_key_str(key).map_err(|err| err.to_string())?,
+            None => keypair::derivation_path().unwrap_or_default(),
+        };
+        let signer = LedgerSigner {
+            locator,
+            derivation_path,
+            pubkey: Pubkey::default(),
+        };
+        let pubkey = signer.connect().map_err(|err| err.to_string())?.pubkey;
+        Ok(Box::new(LedgerSigner { pubkey, ..signer }))
+    }
+}
+
+/// A Ledger key. The device is opened again for each signature, so the signer holds no
+/// device handle and can move between threads.
+struct LedgerSigner {
+    locator: Locator,
+    derivation_path: DerivationPath,
+    pubkey: Pubkey,
+}
+
+impl LedgerSigner {
+    fn connect(
+        &self,
+    ) -> Result<solana_remote_wallet::remote_keypair::RemoteKeypair, RemoteWalletError> {
+        let wallet_manager = initialize_wallet_manager()?;
+        wallet_manager.update_devices()?;
+        generate_remote_keypair(
+            self.locator.clone(),
+            self.derivation_path.clone(),
+            &wallet_manager,
+            false,
+            "keypair",
+        )
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.connect()?.try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+/// Signs with a remote HTTP signer. `GET <url>/pubkey` must return `{"pubkey": ...}`,
+/// and `POST <url>/sign` with `{"message": <base64>}` must return
+/// `{"signature": <base58>}`. Requests carry `ORE_SIGNER_TOKEN` as a bearer token, if set.
+/// Plain `http://` is only accepted for signers on a loopback address, so the token and
+/// messages never cross the network unencrypted.
+pub struct RemoteProvider;
+
+impl SignerProvider for RemoteProvider {
+    fn signer(&self, uri: &str) -> Result<BoxedSigner, String> {
+        let url = uri.trim_end_matches('/').to_string();
+        let parsed = Url::parse(&url).map_err(|err| err.to_string())?;
+        if parsed.scheme().eq("http") && !is_loopback(&parsed) {
+            return Err(format!(
+                "Refusing to use a remote signer over plain http:// on {}. Use https://, or a loopback address.",
+                parsed.host_str().unwrap_or_default()
+            ));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(http_timeout())
+            .build()
+            .map_err(|err| err.to_string())?;
+        let response = request(&client, &format!("{}/pubkey", url), None)?;
+        let pubkey = response["pubkey"]
+            .as_str()
+            .and_then(|pubkey| Pubkey::from_str(pubkey).ok())
+            .ok_or_else(|| format!("Invalid pubkey response: {}", response))?;
+        Ok(Box::new(RemoteSigner {
+            client,
+            url,
+            pubkey,
+        }))
+    }
+}
+
+fn is_loopback(url: &Url) -> bool {
+    match url.host_str() {
+        Some(host) => {
+            host.eq_ignore_ascii_case("localhost")
+                || host
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse::<IpAddr>()
+                    .is_ok_and(|ip| ip.is_loopback())
+        }
+        None => false,
+    }
+}
+
+struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    pubkey: Pubkey,
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let body = json!({
+            "pubkey": self.pubkey.to_string(),
+            "message": BASE64_STANDARD.encode(message),
+        });
+        let response = request(&self.client, &format!("{}/sign", self.url), Some(body))
+            .map_err(SignerError::Connection)?;
+        let signature = response["signature"]
+            .as_str()
+            .and_then(|signature| Signature::from_str(signature).ok())
+            .ok_or_else(|| SignerError::Custom(format!("Invalid sign response: {}", response)))?;
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Custom(
+                "Remote signer returned an invalid signature".into(),
+            ));
+        }
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Sends a request to a remote signer. Signing is synchronous, so the request blocks the
+/// calling thread, and the runtime moves its other tasks off it while it waits.
+fn request(client: &reqwest::Client, url: &str, body: Option<Value>) -> Result<Value, String> {
+    let mut request = match &body {
+        Some(body) => client.post(url).json(body),
+        None => client.get(url),
+    };
+    if let Ok(token) = std::env::var(SIGNER_TOKEN_VAR) {
+        request = request.bearer_auth(token);
+    }
+    let send = async {
+        request
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await
+    };
+    let result = match Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(send)),
+        Err(_) => tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| err.to_string())?
+            .block_on(send),
+    };
+    result.map_err(|err| format!("Remote signer request failed: {}", err))
+}
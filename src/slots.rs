@@ -0,0 +1,129 @@
+//! Times mine transactions to slot boundaries. A websocket slot subscription tracks when
+//! each slot starts, and submissions wait for the start of one of the first slots of a
+//! leader's window, so the transaction reaches the leader with the most of its window left
+//! to land in.
+
+use std::{
+    sync::{atomic::Ordering, Mutex, OnceLock},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_sdk::clock::NUM_CONSECUTIVE_LEADER_SLOTS;
+use tokio::{sync::watch, time::Instant};
+use tracing::{debug, info, warn};
+
+use crate::metrics::metrics;
+
+/// How long into a slot a transaction still counts as sent at its start.
+const SEND_WINDOW: Duration = Duration::from_millis(150);
+
+/// The slots of a leader's window worth sending in, counted from its first slot.
+const SEND_SLOTS: u64 = 2;
+
+/// The longest a submission waits for a good slot, about one leader window.
+const MAX_WAIT: Duration = Duration::from_millis(1600);
+
+/// How long without a slot update before the subscription counts as stalled, and
+/// submissions stop waiting on it.
+const STALE_AFTER: Duration = Duration::from_secs(2);
+
+/// How long to wait before reconnecting a dropped subscription.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// The current slot and when it started.
+#[derive(Clone, Copy)]
+struct SlotStart {
+    slot: u64,
+    started_at: Instant,
+}
+
+fn slots() -> &'static OnceLock<watch::Receiver<Option<SlotStart>>> {
+    static SLOTS: OnceLock<watch::Receiver<Option<SlotStart>>> = OnceLock::new();
+    &SLOTS
+}
+
+/// The slot the last confirmed transaction landed in.
+static LANDED_SLOT: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Subscribes to slot updates over the websocket, reconnecting whenever it drops.
+pub fn start(ws_url: String) {
+    let (tx, rx) = watch::channel(None);
+    if slots().set(rx).is_err() {
+        return;
+    }
+    info!("Timing submissions to slots from {}", ws_url);
+    tokio::spawn(async move {
+        loop {
+            match PubsubClient::new(&ws_url).await {
+                Ok(client) => match client.slot_subscribe().await {
+                    Ok((mut stream, _unsubscribe)) => {
+                        while let Some(info) = stream.next().await {
+                            tx.send_replace(Some(SlotStart {
+                                slot: info.slot,
+                                started_at: Instant::now(),
+                            }));
+                        }
+                        warn!("Slot subscription closed, reconnecting");
+                    }
+                    Err(err) => warn!("Failed to subscribe to slots: {}", err),
+                },
+                Err(err) => warn!("Failed to connect to {}: {}", ws_url, err),
+            }
+            tx.send_replace(None);
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+/// Waits for the start of one of the first slots of a leader's window, for at most about
+/// one window, and returns the slot to send in. Returns at once without slot updates.
+pub async fn wait_for_send_window() -> Option<u64> {
+    let mut rx = slots().get().cloned()?;
+    LANDED_SLOT.lock().unwrap().take();
+    let deadline = Instant::now() + MAX_WAIT;
+    loop {
+        let current = (*rx.borrow_and_update())?;
+        let elapsed = current.started_at.elapsed();
+        if elapsed.gt(&STALE_AFTER) {
+            return None;
+        }
+        if (current.slot % NUM_CONSECUTIVE_LEADER_SLOTS).lt(&SEND_SLOTS) && elapsed.lt(&SEND_WINDOW)
+        {
+            debug!("Sending in slot {}", current.slot);
+            return Some(current.slot);
+        }
+        tokio::select! {
+            result = rx.changed() => {
+                result.ok()?;
+            }
+            _ = tokio::time::sleep_until(deadline) => {
+                return Some(current.slot);
+            }
+        }
+    }
+}
+
+/// Records the slot a transaction was confirmed in.
+pub fn record_landing(slot: u64) {
+    *LANDED_SLOT.lock().unwrap() = Some(slot);
+}
+
+/// Records how many slots the transaction sent in the slot took to land.
+pub fn observe_landing(sent_slot: u64) {
+    let Some(landed_slot) = LANDED_SLOT.lock().unwrap().take() else {
+        return;
+    };
+    let delay = landed_slot.saturating_sub(sent_slot);
+    debug!(
+        "Landed in slot {}, {} slots after sending in slot {}",
+        landed_slot, delay, sent_slot
+    );
+    metrics()
+        .land_slot_delay_sum
+        .fetch_add(delay, Ordering::Relaxed);
+    metrics()
+        .land_slot_delay_count
+        .fetch_add(1, Ordering::Relaxed);
+}
@@ -0,0 +1,185 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use native_tls::{TlsConnector, TlsStream};
+
+/// The port for SMTP over implicit TLS. Other ports upgrade with STARTTLS.
+const SMTPS_PORT: u16 = 465;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An SMTP server and the addresses to send alerts between.
+#[derive(Clone, Debug)]
+pub struct Smtp {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(TlsStream<TcpStream>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+struct Session {
+    stream: BufReader<Stream>,
+}
+
+impl Session {
+    /// Reads a reply and returns its lines, failing unless the code matches.
+    fn expect(&mut self, code: u16) -> Result<Vec<String>, String> {
+        let mut lines = vec![];
+        loop {
+            let mut line = String::new();
+            self.stream
+                .read_line(&mut line)
+                .map_err(|err| err.to_string())?;
+            if line.is_empty() {
+                return Err("Connection closed".to_string());
+            }
+            let line = line.trim_end().to_string();
+            let done = line.as_bytes().get(3).ne(&Some(&b'-'));
+            lines.push(line);
+            if done {
+                break;
+            }
+        }
+        let last = lines.last().cloned().unwrap_or_default();
+        if last
+            .get(..3)
+            .and_then(|c| c.parse::<u16>().ok())
+            .ne(&Some(code))
+        {
+            return Err(format!("Unexpected reply: {}", last));
+        }
+        Ok(lines)
+    }
+
+    fn command(&mut self, command: &str, code: u16) -> Result<Vec<String>, String> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .and_then(|_| stream.flush())
+            .map_err(|err| err.to_string())?;
+        self.expect(code)
+    }
+}
+
+/// Returns whether the EHLO reply lists the extension.
+fn supports(features: &[String], extension: &str) -> bool {
+    features
+        .iter()
+        .any(|line| line.get(4..).unwrap_or_default().starts_with(extension))
+}
+
+impl Smtp {
+    /// Sends a plain text email to every recipient. Blocks until the server accepts it.
+    pub fn send(&self, subject: &str, body: &str) -> Result<(), String> {
+        let tcp = TcpStream::connect((self.host.as_str(), self.port))
+            .map_err(|err| format!("Failed to connect to {}: {}", self.host, err))?;
+        tcp.set_read_timeout(Some(TIMEOUT)).ok();
+        tcp.set_write_timeout(Some(TIMEOUT)).ok();
+        let connector = TlsConnector::new().map_err(|err| err.to_string())?;
+
+        // Connect, upgrading to TLS when the server supports it
+        let mut session = if self.port.eq(&SMTPS_PORT) {
+            let tls = connector
+                .connect(&self.host, tcp)
+                .map_err(|err| err.to_string())?;
+            Session {
+                stream: BufReader::new(Stream::Tls(tls)),
+            }
+        } else {
+            Session {
+                stream: BufReader::new(Stream::Plain(tcp)),
+            }
+        };
+        session.expect(220)?;
+        let mut features = session.command("EHLO ore-cli", 250)?;
+        if let Stream::Plain(_) = session.stream.get_ref() {
+            if supports(&features, "STARTTLS") {
+                session.command("STARTTLS", 220)?;
+                let Stream::Plain(tcp) = session.stream.into_inner() else {
+                    unreachable!()
+                };
+                let tls = connector
+                    .connect(&self.host, tcp)
+                    .map_err(|err| err.to_string())?;
+                session = Session {
+                    stream: BufReader::new(Stream::Tls(tls)),
+                };
+                features = session.command("EHLO ore-cli", 250)?;
+            } else if self.username.is_some() {
+                return Err("Server does not support STARTTLS".to_string());
+            }
+        }
+
+        // Authenticate
+        if let Some(username) = &self.username {
+            if !supports(&features, "AUTH") {
+                return Err("Server does not support authentication".to_string());
+            }
+            let password = self.password.clone().unwrap_or_default();
+            let credentials = BASE64_STANDARD.encode(format!("\0{}\0{}", username, password));
+            session.command(&format!("AUTH PLAIN {}", credentials), 235)?;
+        }
+
+        // Send message
+        session.command(&format!("MAIL FROM:<{}>", self.from), 250)?;
+        for to in &self.to {
+            session.command(&format!("RCPT TO:<{}>", to), 250)?;
+        }
+        session.command("DATA", 354)?;
+        let body = body
+            .lines()
+            .map(|line| match line.starts_with('.') {
+                true => format!(".{}", line),
+                false => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        let message = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n.",
+            self.from,
+            self.to.join(", "),
+            subject,
+            chrono::Utc::now().to_rfc2822(),
+            body
+        );
+        session.command(&message, 250)?;
+        session.command("QUIT", 221).ok();
+        Ok(())
+    }
+}
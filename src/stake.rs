@@ -1,43 +1,121 @@
 use std::str::FromStr;
 
+use colored::*;
+use ore_api::consts::EPOCH_DURATION;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
 
 use crate::{
-    args::StakeArgs, cu_limits::CU_LIMIT_CLAIM, send_and_confirm::ComputeBudget,
-    utils::amount_f64_to_u64, Miner,
+    args::StakeArgs,
+    cu_limits::CU_LIMIT_CLAIM,
+    error::OreError,
+    mine::estimate_reward,
+    preview::Preview,
+    program,
+    send_and_confirm::ComputeBudget,
+    utils::{
+        amount_f64_to_u64, amount_u64_to_string, calculate_multiplier, get_config,
+        get_proof_with_authority, proof_pubkey,
+    },
+    Miner,
 };
 
 impl Miner {
-    pub async fn stake(&self, args: StakeArgs) {
+    pub async fn stake(&self, args: StakeArgs) -> Result<(), OreError> {
+        // Simulate stake
+        if let Some(amount) = args.simulate {
+            return self
+                .simulate_stake(amount_f64_to_u64(amount), args.difficulty)
+                .await;
+        }
+
         // Get signer
-        let signer = self.signer();
+        let signer = self.signer()?;
         let sender = match args.sender {
-            Some(sender) => Pubkey::from_str(&sender).expect("Failed to parse sender address"),
+            Some(sender) => {
+                Pubkey::from_str(&sender).map_err(|_| OreError::InvalidAddress(sender))?
+            }
             None => spl_associated_token_account::get_associated_token_address(
                 &signer.pubkey(),
-                &ore_api::consts::MINT_ADDRESS,
+                &program::mint(),
             ),
         };
 
         // Get token account
-        let Ok(Some(token_account)) = self.rpc_client.get_token_account(&sender).await else {
-            println!("Failed to fetch token account");
-            return;
-        };
+        let token_account = self
+            .rpc_client
+            .get_token_account(&sender)
+            .await
+            .map_err(|err| OreError::fetch("token", err))?
+            .ok_or(OreError::AccountNotFound("token"))?;
 
         // Parse amount
         let amount: u64 = if let Some(amount) = args.amount {
             amount_f64_to_u64(amount)
         } else {
             u64::from_str(token_account.token_amount.amount.as_str())
-                .expect("Failed to parse token balance")
+                .map_err(|_| OreError::InvalidAccount("token"))?
         };
 
+        // Preview stake and confirm
+        let fee = self
+            .estimate_fee(&ComputeBudget::Fixed(CU_LIMIT_CLAIM))
+            .await?;
+        if !Preview::new("Stake")
+            .row("Amount", format!("{} ORE", amount_u64_to_string(amount)))
+            .row("From", sender)
+            .row("Proof", proof_pubkey(signer.pubkey()))
+            .fee(fee)
+            .confirm()
+        {
+            return Ok(());
+        }
+
         // Send tx
         let ix = ore_api::instruction::stake(signer.pubkey(), sender, amount);
         self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await
-            .ok();
+            .await?;
+        Ok(())
+    }
+
+    async fn simulate_stake(&self, amount: u64, difficulty: Option<u32>) -> Result<(), OreError> {
+        let signer = self.signer()?;
+        let config = get_config(&self.rpc_client).await?;
+        let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await?;
+        let mut simulated = proof;
+        simulated.balance = proof.balance.saturating_add(amount);
+
+        // Estimate rewards assuming one submission per epoch
+        let difficulty = difficulty.unwrap_or(config.min_difficulty as u32);
+        let submissions_per_day = (24 * 60 * 60 / EPOCH_DURATION) as u64;
+        let current_per_day =
+            estimate_reward(config, proof, difficulty).saturating_mul(submissions_per_day);
+        let simulated_per_day =
+            estimate_reward(config, simulated, difficulty).saturating_mul(submissions_per_day);
+
+        println!(
+            "{}: {} ORE",
+            "Top stake".bold(),
+            amount_u64_to_string(config.top_balance)
+        );
+        println!(
+            "{}: {} ORE ({:.12}x)",
+            "Current stake".bold(),
+            amount_u64_to_string(proof.balance),
+            calculate_multiplier(proof.balance, config.top_balance)
+        );
+        println!(
+            "{}: {} ORE ({:.12}x)",
+            "Simulated stake".bold(),
+            amount_u64_to_string(simulated.balance),
+            calculate_multiplier(simulated.balance, config.top_balance)
+        );
+        println!(
+            "\nAt difficulty {} with one landed hash per epoch:\n  Current: {} ORE/day\n  Simulated: {} ORE/day\n  Difference: +{} ORE/day",
+            difficulty,
+            amount_u64_to_string(current_per_day),
+            amount_u64_to_string(simulated_per_day),
+            amount_u64_to_string(simulated_per_day.saturating_sub(current_per_day))
+        );
+        Ok(())
     }
 }
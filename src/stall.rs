@@ -0,0 +1,86 @@
+//! Alarms when the miner looks busy but nothing lands. The proof's `last_hash_at` only
+//! moves when a mine transaction lands, so it catches stalls anywhere in the pipeline,
+//! from hashing to submission.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::{
+    clock_drift::cluster_now,
+    notify::{notify, Event},
+    utils::get_proof_with_authority,
+};
+
+/// How often to check the proof.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Whether mining is paused on purpose, through the control API or the profit guard, so
+/// no transaction is expected to land.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// The token of the round being hashed, which a restart cancels.
+static ROUND: Mutex<Option<CancellationToken>> = Mutex::new(None);
+
+/// Records whether mining is paused on purpose. The alarm counts from the end of a pause.
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::Relaxed);
+}
+
+/// Starts hashing a round, and returns its token. It is cancelled with the miner, or when
+/// the stall alarm restarts the mining loop, which abandons the round before it submits.
+pub fn start_round(miner: &CancellationToken) -> CancellationToken {
+    let token = miner.child_token();
+    *ROUND.lock().unwrap() = Some(token.clone());
+    token
+}
+
+/// Watches the proof of the authority, and notifies when no transaction has landed for
+/// `alarm` seconds, not counting pauses. With `restart`, also restarts the mining loop, and
+/// again after each further `alarm` seconds without a landed transaction.
+pub fn start(client: Arc<RpcClient>, authority: Pubkey, alarm: u64, restart_loop: bool) {
+    tokio::spawn(async move {
+        // Count from the start, since the proof may have been idle before it
+        let mut since = cluster_now();
+        let mut alarmed = false;
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if PAUSED.load(Ordering::Relaxed) {
+                since = cluster_now();
+                alarmed = false;
+                continue;
+            }
+            let Ok(proof) = get_proof_with_authority(&client, authority).await else {
+                continue;
+            };
+            let age = cluster_now().saturating_sub(proof.last_hash_at.max(since));
+            if age.lt(&(alarm as i64)) {
+                alarmed = false;
+                continue;
+            }
+            if !alarmed {
+                warn!("No transaction has landed for {} min", age / 60);
+                notify(Event::NoLanding {
+                    minutes: age as u64 / 60,
+                });
+                alarmed = true;
+            }
+            if restart_loop {
+                if let Some(round) = ROUND.lock().unwrap().as_ref() {
+                    warn!("Restarting the mining loop");
+                    round.cancel();
+                }
+                since = cluster_now();
+            }
+        }
+    });
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use colored::*;
+use solana_program::native_token::lamports_to_sol;
+
+use crate::{
+    args::StatsArgs,
+    ledger::{self, LedgerEntry, LedgerKind},
+    output::print_json,
+    schema::{
+        DailyStatsDocument, DifficultyCountDocument, StatsDocument, StatsSummaryDocument,
+        WalletStatsDocument,
+    },
+    utils::amount_u64_to_string,
+    Miner,
+};
+
+/// The width of the longest bar in the difficulty histogram.
+const HISTOGRAM_WIDTH: u64 = 40;
+
+#[derive(Default)]
+struct Summary {
+    submissions: u64,
+    landed: u64,
+    mined: u64,
+    claimed: u64,
+    fees: u64,
+    difficulty_sum: u64,
+}
+
+impl Summary {
+    fn add(&mut self, entry: &LedgerEntry) {
+        self.fees += entry.fee;
+        match entry.kind {
+            LedgerKind::Mine => {
+                self.submissions += 1;
+                if entry.landed {
+                    self.landed += 1;
+                    self.mined += entry.amount;
+                    self.difficulty_sum += entry.difficulty.unwrap_or(0);
+                }
+            }
+            LedgerKind::Claim => {
+                if entry.landed {
+                    self.claimed += entry.amount;
+                }
+            }
+            LedgerKind::Swap => {}
+        }
+    }
+
+    fn average_difficulty(&self) -> f64 {
+        if self.landed.eq(&0) {
+            return 0.0;
+        }
+        self.difficulty_sum as f64 / self.landed as f64
+    }
+
+    fn landing_rate(&self) -> f64 {
+        if self.submissions.eq(&0) {
+            return 0.0;
+        }
+        self.landed as f64 / self.submissions as f64 * 100.0
+    }
+}
+
+impl Miner {
+    pub async fn stats(&self, args: StatsArgs) {
+        let entries: Vec<LedgerEntry> = ledger::load()
+            .into_iter()
+            .filter(|entry| args.wallet.as_ref().map_or(true, |w| entry.authority.eq(w)))
+            .collect();
+        if entries.is_empty() {
+            println!("No mining activity has been recorded yet");
+            return;
+        }
+
+        // Lifetime summary
+        let mut lifetime = Summary::default();
+        let mut daily: BTreeMap<String, Summary> = BTreeMap::new();
+        let mut wallets: BTreeMap<String, Summary> = BTreeMap::new();
+        for entry in entries.iter() {
+            lifetime.add(entry);
+            wallets
+                .entry(entry.authority.clone())
+                .or_default()
+                .add(entry);
+            let date = DateTime::<Utc>::from_timestamp(entry.timestamp, 0)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            daily.entry(date).or_default().add(entry);
+        }
+        let (rounds, average_hashrate) = ledger::round_summary(args.wallet.as_deref());
+        let histogram = ledger::difficulty_histogram(args.wallet.as_deref());
+        if self.is_json() {
+            let summarize = |summary: &Summary| StatsSummaryDocument {
+                submissions: summary.submissions,
+                landed: summary.landed,
+                mined: summary.mined,
+                claimed: summary.claimed,
+                fees: summary.fees,
+                average_difficulty: summary.average_difficulty(),
+            };
+            print_json(&StatsDocument {
+                lifetime: summarize(&lifetime),
+                rounds,
+                average_hashrate,
+                difficulty_histogram: histogram
+                    .iter()
+                    .map(|(difficulty, rounds)| DifficultyCountDocument {
+                        difficulty: *difficulty,
+                        rounds: *rounds,
+                    })
+                    .collect(),
+                daily: daily
+                    .iter()
+                    .rev()
+                    .take(args.days)
+                    .map(|(date, summary)| DailyStatsDocument {
+                        date: date.clone(),
+                        summary: summarize(summary),
+                    })
+                    .collect(),
+                wallets: wallets
+                    .iter()
+                    .map(|(authority, summary)| WalletStatsDocument {
+                        authority: authority.clone(),
+                        summary: summarize(summary),
+                    })
+                    .collect(),
+            });
+            return;
+        }
+        println!("{}", "Lifetime".bold());
+        println!("  Mined: {} ORE", amount_u64_to_string(lifetime.mined));
+        println!("  Claimed: {} ORE", amount_u64_to_string(lifetime.claimed));
+        println!("  Fees: {} SOL", lamports_to_sol(lifetime.fees));
+        println!(
+            "  Landed: {} / {} ({:.1}%)",
+            lifetime.landed,
+            lifetime.submissions,
+            lifetime.landing_rate()
+        );
+        println!("  Average difficulty: {:.2}", lifetime.average_difficulty());
+        if rounds.gt(&0) {
+            println!("  Rounds: {} ({} H/sec average)", rounds, average_hashrate);
+        }
+
+        // Difficulty distribution
+        if !histogram.is_empty() {
+            println!("\n{}", "Rounds by best difficulty".bold());
+            let max = histogram.iter().map(|(_, n)| *n).max().unwrap_or(1);
+            for (difficulty, count) in histogram.iter() {
+                println!(
+                    "  {:>3}: {:<width$} {} ({:.1}%)",
+                    difficulty,
+                    "#".repeat((count * HISTOGRAM_WIDTH / max) as usize),
+                    count,
+                    *count as f64 / rounds as f64 * 100.0,
+                    width = HISTOGRAM_WIDTH as usize
+                );
+            }
+        }
+
+        // Daily summary
+        println!("\n{}", "Daily".bold());
+        for (date, summary) in daily.iter().rev().take(args.days) {
+            println!(
+                "  {}: {} ORE, {} SOL fees, {} / {} landed, {:.2} avg difficulty",
+                date,
+                amount_u64_to_string(summary.mined),
+                lamports_to_sol(summary.fees),
+                summary.landed,
+                summary.submissions,
+                summary.average_difficulty()
+            );
+        }
+
+        // Wallet summary
+        if wallets.len().gt(&1) {
+            println!("\n{}", "Wallets".bold());
+            for (authority, summary) in wallets.iter() {
+                println!(
+                    "  {}: {} ORE, {} SOL fees, {} / {} landed, {:.2} avg difficulty",
+                    authority,
+                    amount_u64_to_string(summary.mined),
+                    lamports_to_sol(summary.fees),
+                    summary.landed,
+                    summary.submissions,
+                    summary.average_difficulty()
+                );
+            }
+        }
+    }
+}
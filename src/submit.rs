@@ -0,0 +1,148 @@
+use std::{io::Read, time::Duration};
+
+use colored::*;
+use drillx::Solution;
+use ore_api::consts::{ONE_MINUTE, TOLERANCE};
+use serde::Deserialize;
+use tokio::sync::oneshot;
+use tracing::info;
+
+use crate::{
+    args::SubmitArgs,
+    clock_drift,
+    cu_limits::{CU_LIMIT_MINE_TX, CU_LIMIT_RESET_TX},
+    error::OreError,
+    ledger::LedgerKind,
+    mine::{estimate_reward, find_bus, should_reset},
+    output::print_json,
+    schema::SubmitDocument,
+    send_and_confirm::ComputeBudget,
+    utils::{get_mining_state, get_mining_state_with_proof, parse_hex, proof_pubkey},
+    Miner,
+};
+
+/// A solution found by external hashing software.
+#[derive(Deserialize)]
+struct ExternalSolution {
+    /// The equix digest, hex encoded.
+    digest: String,
+    nonce: u64,
+    /// The hex encoded challenge the solution was computed for, to reject stale work.
+    #[serde(default)]
+    challenge: Option<String>,
+}
+
+impl Miner {
+    pub async fn submit(&self, args: SubmitArgs) -> Result<(), OreError> {
+        // Read the solution
+        let input = match &args.file {
+            Some(path) if path.as_os_str().ne("-") => {
+                std::fs::read_to_string(path).map_err(|err| {
+                    OreError::InvalidSolution(format!("Failed to read {}: {}", path.display(), err))
+                })?
+            }
+            _ => {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut input)
+                    .map_err(|err| OreError::InvalidSolution(err.to_string()))?;
+                input
+            }
+        };
+        let external: ExternalSolution = serde_json::from_str(&input)
+            .map_err(|err| OreError::InvalidSolution(format!("Failed to parse: {}", err)))?;
+        let digest = parse_hex::<16>(&external.digest)
+            .map_err(|err| OreError::InvalidSolution(format!("Invalid digest: {}", err)))?;
+        let solution = Solution::new(digest, external.nonce.to_le_bytes());
+
+        // Validate it against the current challenge
+        let signer = self.signer()?;
+        let (state, proof) = get_mining_state_with_proof(&self.rpc_client, signer.pubkey()).await?;
+        clock_drift::observe(&state.clock);
+        if let Some(challenge) = &external.challenge {
+            if challenge.to_lowercase().ne(&hex::encode(proof.challenge)) {
+                return Err(OreError::InvalidSolution(
+                    "The challenge has changed since the solution was computed".to_string(),
+                ));
+            }
+        }
+        if !solution.is_valid(&proof.challenge) {
+            return Err(OreError::InvalidSolution(
+                "The digest does not match the nonce and the current challenge".to_string(),
+            ));
+        }
+        let difficulty = solution.to_hash().difficulty();
+        if (difficulty as u64).lt(&state.config.min_difficulty) {
+            return Err(OreError::InvalidSolution(format!(
+                "Difficulty {} is below the minimum of {}",
+                difficulty, state.config.min_difficulty
+            )));
+        }
+        if !self.is_json() {
+            println!("{}: {}", "Difficulty".bold(), difficulty);
+        }
+
+        // Wait until the program accepts a hash for the proof. Sent earlier, the
+        // transaction fails as spam and still pays its fees.
+        let wait = proof
+            .last_hash_at
+            .saturating_add(ONE_MINUTE)
+            .saturating_sub(TOLERANCE)
+            .saturating_sub(clock_drift::cluster_now());
+        if wait.gt(&0) {
+            info!("Waiting {}s until the proof accepts a hash", wait);
+            self.sleep(Duration::from_secs(wait as u64)).await?;
+        }
+
+        // Build the mine transaction
+        let mut state = state;
+        if self.wait_out_reset(&state, args.reset_guard).await? {
+            state = get_mining_state(&self.rpc_client).await?;
+        }
+        let config = state.config;
+        let mut compute_budget = CU_LIMIT_MINE_TX;
+        let mut ixs = vec![ore_api::instruction::auth(proof_pubkey(signer.pubkey()))];
+        if should_reset(config) {
+            compute_budget += CU_LIMIT_RESET_TX;
+            ixs.push(ore_api::instruction::reset(signer.pubkey()));
+        }
+        let reward = estimate_reward(config, proof, difficulty);
+        let bus = find_bus(args.bus_strategy, &state.busses, reward, 0);
+        ixs.push(ore_api::instruction::mine(
+            signer.pubkey(),
+            signer.pubkey(),
+            bus,
+            solution,
+        ));
+
+        // Send and confirm
+        let result = self
+            .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
+            .await;
+        let sig = result.as_ref().ok().copied();
+        let (settled_tx, settled_rx) = oneshot::channel();
+        let entry = self.record_transaction_then(
+            LedgerKind::Mine,
+            signer.pubkey(),
+            sig,
+            0,
+            Some(difficulty as u64),
+            move |entry| {
+                settled_tx.send(entry.clone()).ok();
+            },
+        );
+        let entry = settled_rx.await.unwrap_or(entry);
+        if self.is_json() {
+            print_json(&SubmitDocument {
+                authority: signer.pubkey().to_string(),
+                difficulty,
+                bus: bus.to_string(),
+                signature: sig.map(|sig| sig.to_string()),
+                landed: entry.landed,
+                reward: entry.amount,
+            });
+        }
+        result?;
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use base64::Engine;
+use colored::*;
+use ore_api::consts::TOKEN_DECIMALS;
+use serde_json::{json, Value};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_sdk::{compute_budget, signature::Signature};
+use spl_token::amount_to_ui_amount;
+
+use crate::{
+    args::SwapArgs,
+    error::OreError,
+    ledger::LedgerKind,
+    output::print_json,
+    preview::Preview,
+    program,
+    schema::SwapDocument,
+    send_and_confirm::{ComputeBudget, OnSigned},
+    timeout::http_client,
+    utils::{amount_f64_to_u64, get_mint_decimals},
+    Miner,
+};
+
+/// The Jupiter swap API, unless `--jupiter-url` is given.
+pub const DEFAULT_JUPITER_URL: &str = "https://lite-api.jup.ag/swap/v1";
+
+/// The highest slippage accepted without `--allow-high-slippage`, in basis points.
+pub const MAX_SLIPPAGE_BPS: u16 = 1000;
+
+/// The compute unit limit of a swap, if Jupiter doesn't estimate one.
+const SWAP_CU_LIMIT: u32 = 400_000;
+
+static JUPITER_URL: OnceLock<String> = OnceLock::new();
+
+/// Sets the Jupiter swap API to quote and build swaps with.
+pub fn set_jupiter_url(url: String) {
+    JUPITER_URL.set(url.trim_end_matches('/').to_string()).ok();
+}
+
+fn jupiter_url() -> &'static str {
+    JUPITER_URL
+        .get()
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_JUPITER_URL)
+}
+
+/// Rejects a slippage above [`MAX_SLIPPAGE_BPS`], unless it is explicitly allowed.
+pub fn check_slippage(slippage_bps: u16, allow_high: bool) -> Result<(), OreError> {
+    if slippage_bps.gt(&MAX_SLIPPAGE_BPS) && !allow_high {
+        return Err(OreError::Swap(format!(
+            "A slippage of {} bps is above {} bps. Pass --allow-high-slippage to use it anyway.",
+            slippage_bps, MAX_SLIPPAGE_BPS
+        )));
+    }
+    Ok(())
+}
+
+/// Tokens that can be swapped to by symbol. Any other token is given by its mint address.
+const TOKENS: &[(&str, &str)] = &[
+    ("USDC", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+    ("USDT", "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"),
+    ("SOL", "So11111111111111111111111111111111111111112"),
+];
+
+/// How a sale is carried out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapMode {
+    /// Print the quote without swapping.
+    Quote,
+    /// Swap once the quote is confirmed.
+    Confirm,
+    /// Swap without asking, for scheduled sales.
+    Unattended,
+}
+
+/// Resolves a token symbol like `USDC`, or a mint address.
+pub fn parse_token(token: &str) -> Result<Pubkey, String> {
+    match TOKENS
+        .iter()
+        .find(|(symbol, _)| symbol.eq_ignore_ascii_case(token))
+    {
+        Some((_, mint)) => Pubkey::from_str(mint).map_err(|err| err.to_string()),
+        None => Pubkey::from_str(token).map_err(|_| {
+            format!(
+                "Unknown token {:?}. Use USDC, USDT, SOL, or a mint address.",
+                token
+            )
+        }),
+    }
+}
+
+impl Miner {
+    pub async fn swap(&self, args: SwapArgs) -> Result<(), OreError> {
+        check_slippage(args.slippage_bps, args.allow_high_slippage)?;
+        self.sell_ore(
+            amount_f64_to_u64(args.amount),
+            args.to,
+            args.slippage_bps,
+            match args.dry_run {
+                true => SwapMode::Quote,
+                false => SwapMode::Confirm,
+            },
+            &|_, _| Ok(()),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Sells ORE from the signer's wallet through Jupiter. The swap is built from Jupiter's
+    /// instructions, checked, and sent like any other transaction. Returns the signature of
+    /// the swap, if one was sent. Each signature is handed to `on_signed` before it is sent.
+    pub(crate) async fn sell_ore(
+        &self,
+        amount: u64,
+        to: Pubkey,
+        slippage_bps: u16,
+        mode: SwapMode,
+        on_signed: OnSigned<'_>,
+    ) -> Result<Option<Signature>, OreError> {
+        let signer = self.signer()?;
+
+        // Fetch a quote
+        let quote: Value = http_client()
+            .get(format!("{}/quote", jupiter_url()))
+            .query(&[
+                ("inputMint", program::mint().to_string()),
+                ("outputMint", to.to_string()),
+                ("amount", amount.to_string()),
+                ("slippageBps", slippage_bps.to_string()),
+                ("asLegacyTransaction", "true".to_string()),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| OreError::Swap(format!("Failed to fetch a quote: {}", err)))?
+            .json()
+            .await
+            .map_err(|err| OreError::Swap(format!("Invalid quote: {}", err)))?;
+        let amount_field = |field: &str| {
+            quote[field]
+                .as_str()
+                .and_then(|amount| amount.parse::<u64>().ok())
+                .ok_or_else(|| OreError::Swap(format!("Quote has no {}", field)))
+        };
+        let out_amount = amount_field("outAmount")?;
+        let min_out_amount = amount_field("otherAmountThreshold")?;
+        let price_impact_pct = quote["priceImpactPct"]
+            .as_str()
+            .and_then(|pct| pct.parse::<f64>().ok())
+            .unwrap_or(0.0)
+            * 100.0;
+        let route = quote["routePlan"]
+            .as_array()
+            .map(|steps| {
+                steps
+                    .iter()
+                    .filter_map(|step| step["swapInfo"]["label"].as_str())
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            })
+            .unwrap_or_default();
+        let decimals = get_mint_decimals(&self.rpc_client, &to).await?;
+        let token = TOKENS
+            .iter()
+            .find(|(_, mint)| mint.eq(&to.to_string()))
+            .map(|(symbol, _)| symbol.to_string())
+            .unwrap_or_else(|| to.to_string());
+        let mut document = SwapDocument {
+            input_mint: program::mint().to_string(),
+            output_mint: to.to_string(),
+            in_amount: amount,
+            out_amount,
+            min_out_amount,
+            price_impact_pct,
+            signature: None,
+        };
+
+        // Preview the quote
+        let preview = Preview::new("Swap")
+            .row(
+                "Sell",
+                format!("{} ORE", amount_to_ui_amount(amount, TOKEN_DECIMALS)),
+            )
+            .row(
+                "Receive",
+                format!("{} {}", amount_to_ui_amount(out_amount, decimals), token),
+            )
+            .row(
+                "Minimum received",
+                format!(
+                    "{} {}",
+                    amount_to_ui_amount(min_out_amount, decimals),
+                    token
+                ),
+            )
+            .row("Price impact", format!("{:.2}%", price_impact_pct))
+            .row("Route", route);
+        match mode {
+            SwapMode::Quote => {
+                match self.is_json() {
+                    true => print_json(&document),
+                    false => preview.print(),
+                }
+                return Ok(None);
+            }
+            SwapMode::Confirm if !preview.confirm() => return Ok(None),
+            SwapMode::Unattended if !self.is_json() => preview.print(),
+            _ => {}
+        }
+
+        // Build the swap instructions
+        let response: Value = http_client()
+            .post(format!("{}/swap-instructions", jupiter_url()))
+            .json(&json!({
+                "quoteResponse": quote,
+                "userPublicKey": signer.pubkey().to_string(),
+                "wrapAndUnwrapSol": true,
+                "dynamicComputeUnitLimit": true,
+                "asLegacyTransaction": true,
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| OreError::Swap(format!("Failed to build the swap: {}", err)))?
+            .json()
+            .await
+            .map_err(|err| OreError::Swap(format!("Invalid swap: {}", err)))?;
+        let (ixs, compute_units) = swap_instructions(&response, signer.pubkey())?;
+
+        // Send it through the transaction pipeline, with our own priority fee
+        let sig = self
+            .send_and_confirm_tracked(&ixs, ComputeBudget::Fixed(compute_units), false, on_signed)
+            .await?;
+        self.record_transaction(LedgerKind::Swap, signer.pubkey(), Some(sig), amount, None);
+        document.signature = Some(sig.to_string());
+        match self.is_json() {
+            true => print_json(&document),
+            false => println!("{} Swapped. Signature: {}", "OK".bold().green(), sig),
+        }
+        Ok(Some(sig))
+    }
+}
+
+/// Decodes the instructions of a swap built by Jupiter, and the compute unit limit it
+/// estimated. Rejects swaps that need another signer than the user, or that don't sell from
+/// the user's ORE token account, so a bad response can't spend anything else.
+fn swap_instructions(response: &Value, user: Pubkey) -> Result<(Vec<Instruction>, u32), OreError> {
+    let invalid = |reason: &str| OreError::Swap(format!("Invalid swap: {}", reason));
+    if response["addressLookupTableAddresses"]
+        .as_array()
+        .is_some_and(|tables| !tables.is_empty())
+    {
+        return Err(invalid("the route needs address lookup tables"));
+    }
+
+    // The compute budget is set by the pipeline, so only keep Jupiter's estimate
+    let compute_units = response["computeBudgetInstructions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|ix| decode_instruction(ix).ok())
+        .filter(|ix| ix.program_id.eq(&compute_budget::id()))
+        .find_map(|ix| match ix.data.as_slice() {
+            [2, limit @ ..] if limit.len().eq(&4) => {
+                Some(u32::from_le_bytes(limit.try_into().ok()?))
+            }
+            _ => None,
+        })
+        .unwrap_or(SWAP_CU_LIMIT);
+
+    // Setup, swap, and cleanup, in order
+    let swap_ix = decode_instruction(&response["swapInstruction"])?;
+    let mut ixs = vec![];
+    for ix in response["setupInstructions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        ixs.push(decode_instruction(ix)?);
+    }
+    ixs.push(swap_ix.clone());
+    if !response["cleanupInstruction"].is_null() {
+        ixs.push(decode_instruction(&response["cleanupInstruction"])?);
+    }
+    for ix in response["otherInstructions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        ixs.push(decode_instruction(ix)?);
+    }
+
+    // Check the user is the only signer, and sells from their own ORE account
+    if let Some(meta) = ixs
+        .iter()
+        .flat_map(|ix| ix.accounts.iter())
+        .find(|meta| meta.is_signer && meta.pubkey.ne(&user))
+    {
+        return Err(invalid(&format!(
+            "it needs a signature from {}",
+            meta.pubkey
+        )));
+    }
+    let ore_tokens =
+        spl_associated_token_account::get_associated_token_address(&user, &program::mint());
+    if !swap_ix
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey.eq(&ore_tokens) && meta.is_writable)
+    {
+        return Err(invalid(&format!(
+            "it doesn't sell from your ORE token account {}",
+            ore_tokens
+        )));
+    }
+    Ok((ixs, compute_units))
+}
+
+/// Decodes an instruction in Jupiter's JSON format.
+fn decode_instruction(ix: &Value) -> Result<Instruction, OreError> {
+    let invalid = || OreError::Swap("Invalid swap instruction".to_string());
+    let program_id = ix["programId"]
+        .as_str()
+        .and_then(|id| Pubkey::from_str(id).ok())
+        .ok_or_else(invalid)?;
+    let accounts = ix["accounts"]
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|meta| {
+            Some(AccountMeta {
+                pubkey: Pubkey::from_str(meta["pubkey"].as_str()?).ok()?,
+                is_signer: meta["isSigner"].as_bool()?,
+                is_writable: meta["isWritable"].as_bool()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    let data = ix["data"]
+        .as_str()
+        .and_then(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+        .ok_or_else(invalid)?;
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
@@ -0,0 +1,273 @@
+//! Tells systemd when the miner is ready and still making progress, and installs a unit
+//! file that runs it.
+
+use std::{
+    ffi::CString,
+    os::{linux::net::SocketAddrExt, unix::net::UnixDatagram},
+    path::PathBuf,
+    process::Command,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use tracing::{debug, warn};
+
+use crate::{
+    args::SystemdInstallArgs,
+    error::OreError,
+    healthcheck, logging,
+    metrics::{metrics, status},
+    utils::ask_confirm,
+    Miner,
+};
+
+/// Sends a state like `READY=1` to the service manager, if it started this process.
+fn notify(state: &str) {
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let path = path.to_string_lossy().to_string();
+    let result = UnixDatagram::unbound().and_then(|socket| match path.strip_prefix('@') {
+        Some(name) => {
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)
+        }
+        None => socket.send_to(state.as_bytes(), &path),
+    });
+    if let Err(err) = result {
+        debug!("Failed to notify systemd: {}", err);
+    }
+}
+
+/// Reports that the miner started, and pings the watchdog while mining makes progress or
+/// is paused on purpose, so systemd restarts a miner that hangs.
+pub fn ready() {
+    notify("READY=1");
+    let Some(interval) = std::env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|usec| usec.parse::<u64>().ok())
+        .map(Duration::from_micros)
+    else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let progress = || {
+            (
+                metrics().hashes.load(Ordering::Relaxed),
+                status().round,
+                healthcheck::last_beat_at(),
+            )
+        };
+        let mut last = progress();
+        let mut last_progress_at = Instant::now();
+        loop {
+            std::thread::sleep(interval / 2);
+            let current = progress();
+            if current.ne(&last) {
+                last = current;
+                last_progress_at = Instant::now();
+            }
+            if last_progress_at.elapsed().lt(&interval) {
+                notify("WATCHDOG=1");
+            }
+        }
+    });
+}
+
+/// Reports that the miner is shutting down.
+pub fn stopping() {
+    notify("STOPPING=1");
+}
+
+/// How often systemd expects a watchdog ping. Longer than a round and its submission.
+const WATCHDOG_SEC: u64 = 300;
+
+impl Miner {
+    pub async fn systemd_install(&self, args: SystemdInstallArgs) -> Result<(), OreError> {
+        // Run the command line as given, with mine in place of this command. The parsed
+        // arguments include the config file defaults, which the service reads itself.
+        let exe = std::env::current_exe()
+            .and_then(|exe| exe.canonicalize())
+            .map_err(|err| OreError::Systemd(format!("Could not find the ore binary: {}", err)))?;
+        let argv: Vec<String> = std::env::args().skip(1).collect();
+        let command = argv
+            .iter()
+            .position(|arg| arg.eq("systemd-install"))
+            .unwrap_or_default();
+        let mine_args = argv[command..]
+            .iter()
+            .position(|arg| arg.eq("--"))
+            .map(|i| &argv[command + i + 1..])
+            .unwrap_or_default();
+        let exec_start = std::iter::once(exe.display().to_string())
+            .chain(argv[..command].iter().cloned())
+            .chain(std::iter::once("mine".to_string()))
+            .chain(mine_args.iter().cloned())
+            .map(|arg| quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // Generate the unit
+        // Under sudo, run as the user who invoked it
+        let (user, home) = match std::env::var("SUDO_USER") {
+            Ok(user) => {
+                let home = home_dir(&user).ok_or_else(|| {
+                    OreError::Systemd(format!("Could not find the home directory of {}", user))
+                })?;
+                (user, home)
+            }
+            Err(_) => (
+                std::env::var("USER").unwrap_or_default(),
+                PathBuf::from(std::env::var("HOME").unwrap_or_default()),
+            ),
+        };
+        let unit = unit_file(&exec_start, &args, &user, &home);
+        if args.print {
+            print!("{}", unit);
+            return Ok(());
+        }
+
+        // Install and enable it
+        let path = match args.user {
+            true => PathBuf::from(&home)
+                .join(".config/systemd/user")
+                .join(format!("{}.service", args.name)),
+            false => PathBuf::from("/etc/systemd/system").join(format!("{}.service", args.name)),
+        };
+        println!("{}\n", unit);
+        if !ask_confirm(&format!("Install this unit to {}? [Y/n]", path.display())) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        std::fs::write(&path, &unit).map_err(|err| {
+            OreError::Systemd(format!(
+                "Failed to write {}: {}. Installing a system unit requires root; run with sudo or pass --user.",
+                path.display(),
+                err
+            ))
+        })?;
+        println!("{} Wrote {}", "OK".bold().green(), path.display());
+        systemctl(args.user, &["daemon-reload"])?;
+        systemctl(
+            args.user,
+            &["enable", "--now", &format!("{}.service", args.name)],
+        )?;
+        println!(
+            "{} Enabled and started {}. Follow its logs with `journalctl {}-u {} -f`.",
+            "OK".bold().green(),
+            args.name,
+            if args.user { "--user " } else { "" },
+            args.name
+        );
+        if args.user {
+            warn!(
+                "User units only start at boot with lingering enabled: loginctl enable-linger {}",
+                user
+            );
+        }
+        Ok(())
+    }
+}
+
+fn unit_file(
+    exec_start: &str,
+    args: &SystemdInstallArgs,
+    user: &str,
+    home: &std::path::Path,
+) -> String {
+    let mut unit = String::new();
+    unit.push_str("[Unit]\n");
+    unit.push_str("Description=ORE miner\n");
+    unit.push_str("After=network-online.target\n");
+    unit.push_str("Wants=network-online.target\n\n");
+    unit.push_str("[Service]\n");
+    unit.push_str("Type=notify\n");
+    unit.push_str("NotifyAccess=main\n");
+    unit.push_str(&format!("ExecStart={}\n", exec_start));
+    unit.push_str("Restart=always\n");
+    unit.push_str("RestartSec=10\n");
+    unit.push_str("TimeoutStartSec=300\n");
+    unit.push_str(&format!("WatchdogSec={}\n", WATCHDOG_SEC));
+    if !args.user && !user.is_empty() {
+        unit.push_str(&format!("User={}\n", user));
+    }
+    unit.push_str(&format!(
+        "Environment=HOME={}\n",
+        quote(&home.display().to_string())
+    ));
+    unit.push_str("NoNewPrivileges=true\n");
+    unit.push_str("PrivateTmp=true\n");
+    unit.push_str("PrivateDevices=true\n");
+    unit.push_str("ProtectSystem=strict\n");
+    unit.push_str("ProtectHome=read-only\n");
+    let mut writable = vec![home.join(".config/ore")];
+    if let Some(dir) = logging::log_file_path()
+        .and_then(|path| std::env::current_dir().ok().map(|dir| dir.join(path)))
+        .and_then(|path| path.parent().map(PathBuf::from))
+    {
+        writable.push(dir);
+    }
+    unit.push_str(&format!(
+        "ReadWritePaths={}\n",
+        writable
+            .iter()
+            .map(|path| quote(&path.display().to_string()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+    unit.push_str("ProtectKernelTunables=true\n");
+    unit.push_str("ProtectKernelModules=true\n");
+    unit.push_str("ProtectControlGroups=true\n");
+    unit.push_str("RestrictSUIDSGID=true\n");
+    unit.push_str("RestrictNamespaces=true\n");
+    unit.push_str("LockPersonality=true\n\n");
+    unit.push_str("[Install]\n");
+    unit.push_str(match args.user {
+        true => "WantedBy=default.target\n",
+        false => "WantedBy=multi-user.target\n",
+    });
+    unit
+}
+
+/// Quotes an argument for a unit file, escaping the characters systemd expands.
+fn quote(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    if !escaped.is_empty() && !escaped.contains(|c: char| c.is_whitespace() || "\"'\\;".contains(c))
+    {
+        return escaped;
+    }
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Returns the home directory of the user from the password database.
+fn home_dir(user: &str) -> Option<PathBuf> {
+    let name = CString::new(user).ok()?;
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+    if passwd.is_null() {
+        return None;
+    }
+    let dir = unsafe { std::ffi::CStr::from_ptr((*passwd).pw_dir) };
+    Some(PathBuf::from(dir.to_string_lossy().to_string()))
+}
+
+fn systemctl(user: bool, args: &[&str]) -> Result<(), OreError> {
+    let mut command = Command::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    let status = command
+        .args(args)
+        .status()
+        .map_err(|err| OreError::Systemd(format!("Failed to run systemctl: {}", err)))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(OreError::Systemd(format!(
+            "systemctl {} exited with {}",
+            args.join(" "),
+            status
+        ))),
+    }
+}
@@ -0,0 +1,134 @@
+use std::net::UdpSocket;
+
+use opentelemetry::{trace::TraceError, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::warn;
+
+use crate::timeout::http_timeout;
+
+/// Flushes buffered spans to the OTLP collector when dropped.
+pub struct TracingGuard(pub trace::TracerProvider);
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        for result in self.0.force_flush() {
+            result.ok();
+        }
+        self.0.shutdown().ok();
+    }
+}
+
+/// Builds a provider that exports spans from the transaction pipeline to an OTLP/HTTP
+/// collector.
+pub fn otlp_provider(endpoint: &str) -> Result<trace::TracerProvider, TraceError> {
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(trace::Config::default().with_resource(Resource::new(vec![
+            KeyValue::new("service.name", "ore-cli"),
+        ])))
+        .install_batch(runtime::Tokio)
+}
+
+/// Metrics collected for a single mining round.
+pub struct RoundReport {
+    pub authority: Pubkey,
+    pub hashrate: u64,
+    pub difficulty: u32,
+    pub reward: u64,
+    pub fee: u64,
+    pub landed: bool,
+}
+
+/// How many round reports can wait for a slow InfluxDB endpoint before new ones are dropped.
+const INFLUX_QUEUE: usize = 64;
+
+/// Pushes round reports to an InfluxDB endpoint from a background task, so a slow or
+/// unreachable endpoint never holds up mining.
+#[derive(Clone)]
+pub struct InfluxSink(mpsc::Sender<RoundReport>);
+
+impl InfluxSink {
+    pub fn spawn(url: String, token: Option<String>) -> Self {
+        let (tx, mut rx) = mpsc::channel(INFLUX_QUEUE);
+        tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            while let Some(report) = rx.recv().await {
+                push_influx(&client, &url, token.as_deref(), &report).await;
+            }
+        });
+        Self(tx)
+    }
+
+    /// Queues a report, dropping it if the endpoint is too far behind.
+    pub fn push(&self, report: RoundReport) {
+        if let Err(TrySendError::Full(_)) = self.0.try_send(report) {
+            warn!("Telemetry sink is falling behind, dropping a round report");
+        }
+    }
+}
+
+/// Pushes a round report to an InfluxDB (or any line-protocol) write endpoint.
+async fn push_influx(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+    report: &RoundReport,
+) {
+    let line = format!(
+        "ore_round,authority={} hashrate={}i,difficulty={}i,reward={}i,fee={}i,landed={} {}",
+        report.authority,
+        report.hashrate,
+        report.difficulty,
+        report.reward,
+        report.fee,
+        report.landed,
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+    let mut request = client.post(url).timeout(http_timeout()).body(line);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Token {}", token));
+    }
+    match request.send().await {
+        Ok(response) if !response.status().is_success() => {
+            warn!("Telemetry sink returned {}", response.status());
+        }
+        Err(err) => {
+            warn!("Failed to push telemetry: {}", err);
+        }
+        _ => {}
+    }
+}
+
+/// Emits a round report as StatsD gauges and counters over UDP. DogStatsD mode tags
+/// each metric with the miner authority.
+pub fn push_statsd(addr: &str, dogstatsd: bool, report: &RoundReport) {
+    let outcome = if report.landed { "landed" } else { "expired" };
+    let tags = if dogstatsd {
+        format!("|#authority:{}", report.authority)
+    } else {
+        String::new()
+    };
+    let payload = [
+        format!("ore.hashrate:{}|g", report.hashrate),
+        format!("ore.difficulty:{}|g", report.difficulty),
+        "ore.rounds:1|c".to_string(),
+        format!("ore.transactions.{}:1|c", outcome),
+        format!("ore.rewards:{}|c", report.reward),
+        format!("ore.fees:{}|c", report.fee),
+    ]
+    .map(|metric| metric + &tags)
+    .join("\n");
+    let result =
+        UdpSocket::bind("0.0.0.0:0").and_then(|socket| socket.send_to(payload.as_bytes(), addr));
+    if let Err(err) = result {
+        warn!("Failed to emit StatsD metrics: {}", err);
+    }
+}
@@ -0,0 +1,44 @@
+use std::{sync::OnceLock, time::Duration};
+
+/// How long to wait for an RPC response by default.
+const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait for other HTTP requests, like fee estimates and prices, by default.
+const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to keep submitting and confirming a transaction by default. This outlasts the
+/// ~150 blocks a blockhash stays valid for, so a transaction is never given up on while it
+/// can still land.
+const DEFAULT_CONFIRM_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// The timeout given with `--timeout`, which replaces every default but only ever extends
+/// the confirm timeout.
+static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+pub fn set_timeout(timeout: Duration) {
+    TIMEOUT.set(timeout).ok();
+}
+
+pub fn rpc_timeout() -> Duration {
+    TIMEOUT.get().copied().unwrap_or(DEFAULT_RPC_TIMEOUT)
+}
+
+pub fn http_timeout() -> Duration {
+    TIMEOUT.get().copied().unwrap_or(DEFAULT_HTTP_TIMEOUT)
+}
+
+pub fn confirm_timeout() -> Duration {
+    TIMEOUT
+        .get()
+        .copied()
+        .unwrap_or_default()
+        .max(DEFAULT_CONFIRM_TIMEOUT)
+}
+
+/// Returns an HTTP client that gives up after the HTTP timeout.
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(http_timeout())
+        .build()
+        .unwrap_or_default()
+}
@@ -0,0 +1,75 @@
+use clap::ValueEnum;
+use colored::*;
+
+use crate::{
+    args::TopArgs,
+    error::OreError,
+    output::print_json,
+    schema::{TopDocument, TopMinerDocument},
+    utils::{amount_u64_to_string, get_proofs},
+    Miner,
+};
+
+/// The ranking of `ore top`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TopOrder {
+    /// Lifetime rewards.
+    Rewards,
+    /// Current balance, including stake.
+    Balance,
+}
+
+impl Miner {
+    pub async fn top(&self, args: TopArgs) -> Result<(), OreError> {
+        // Fetch all proof accounts
+        let mut proofs = get_proofs(&self.rpc_client).await?;
+        let total = proofs.len();
+
+        // Rank
+        match args.by {
+            TopOrder::Rewards => {
+                proofs.sort_by(|(_, a), (_, b)| b.total_rewards.cmp(&a.total_rewards))
+            }
+            TopOrder::Balance => proofs.sort_by(|(_, a), (_, b)| b.balance.cmp(&a.balance)),
+        }
+        proofs.truncate(args.limit);
+
+        // Print as json
+        if args.json || self.is_json() {
+            print_json(&TopDocument {
+                miners: proofs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (address, proof))| TopMinerDocument {
+                        rank: i + 1,
+                        authority: proof.authority.to_string(),
+                        proof: address.to_string(),
+                        balance: proof.balance,
+                        total_rewards: proof.total_rewards,
+                        total_hashes: proof.total_hashes,
+                    })
+                    .collect(),
+                total_miners: total,
+            });
+            return Ok(());
+        }
+
+        // Print human readable
+        println!(
+            "{:>4}  {:<44} {:>18} {:>18} {:>12}",
+            "Rank", "Authority", "Rewards (ORE)", "Balance (ORE)", "Hashes"
+        );
+        for (i, (_, proof)) in proofs.iter().enumerate() {
+            println!(
+                "{:>4}  {:<44} {:>18} {:>18} {:>12}",
+                i + 1,
+                proof.authority.to_string(),
+                amount_u64_to_string(proof.total_rewards),
+                amount_u64_to_string(proof.balance),
+                proof.total_hashes
+            );
+        }
+        println!("\n{} {} miners", "Total:".bold(), total);
+        Ok(())
+    }
+}
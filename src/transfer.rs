@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use colored::*;
+use ore_api::consts::TOKEN_DECIMALS;
+use solana_program::pubkey::Pubkey;
+use spl_token::amount_to_ui_amount;
+
+use crate::{
+    args::TransferArgs,
+    error::OreError,
+    program,
+    send_and_confirm::ComputeBudget,
+    utils::{amount_f64_to_u64, ask_confirm},
+    Miner,
+};
+
+impl Miner {
+    pub async fn transfer(&self, args: TransferArgs) -> Result<(), OreError> {
+        let signer = self.signer()?;
+        let recipient =
+            Pubkey::from_str(&args.to).map_err(|_| OreError::InvalidAddress(args.to.clone()))?;
+
+        // Check sender balance
+        let sender_tokens = spl_associated_token_account::get_associated_token_address(
+            &signer.pubkey(),
+            &program::mint(),
+        );
+        let sender_account = self
+            .rpc_client
+            .get_token_account(&sender_tokens)
+            .await
+            .map_err(|err| OreError::fetch("token", err))?
+            .ok_or(OreError::AccountNotFound("token"))?;
+        let amount = amount_f64_to_u64(args.amount);
+        let balance = u64::from_str(sender_account.token_amount.amount.as_str())
+            .map_err(|_| OreError::InvalidAccount("token"))?;
+        if amount.gt(&balance) {
+            return Err(OreError::InsufficientFunds {
+                balance: amount_to_ui_amount(balance, TOKEN_DECIMALS),
+                required: amount_to_ui_amount(amount, TOKEN_DECIMALS),
+                token: "ORE",
+            });
+        }
+
+        // Create recipient token account, if needed
+        let mut ixs = vec![];
+        let recipient_tokens = spl_associated_token_account::get_associated_token_address(
+            &recipient,
+            &program::mint(),
+        );
+        if !matches!(
+            self.rpc_client.get_token_account(&recipient_tokens).await,
+            Ok(Some(_))
+        ) {
+            if !ask_confirm(
+                "\nThe recipient does not have an ORE token account. Creating one will cost rent from your wallet.\n\nAre you sure you want to continue? [Y/n]",
+            ) {
+                return Ok(());
+            }
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &signer.pubkey(),
+                    &recipient,
+                    &program::mint(),
+                    &spl_token::id(),
+                ),
+            );
+        }
+
+        // Confirm user wants to transfer
+        if !ask_confirm(
+            format!(
+                "\nYou are about to send {} to {}.\n\nAre you sure you want to continue? [Y/n]",
+                format!("{} ORE", amount_to_ui_amount(amount, TOKEN_DECIMALS)).bold(),
+                recipient
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+
+        // Send and confirm
+        ixs.push(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                &sender_tokens,
+                &program::mint(),
+                &recipient_tokens,
+                &signer.pubkey(),
+                &[&signer.pubkey()],
+                amount,
+                TOKEN_DECIMALS,
+            )
+            .unwrap(),
+        );
+        self.send_and_confirm(&ixs, ComputeBudget::Dynamic, false)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Stdout},
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{BarChart, Block, Borders, Paragraph, Row, Sparkline, Table},
+    Frame, Terminal,
+};
+use solana_program::native_token::lamports_to_sol;
+use tracing::error;
+
+use crate::{
+    exit,
+    metrics::{metrics, status, RECENT_TRANSACTIONS},
+    session,
+    utils::{amount_u64_to_string, format_timestamp},
+};
+
+/// The number of hashrate samples shown in the graph, one per second.
+const HASHRATE_HISTORY: usize = 300;
+
+/// Draws the mining dashboard on a background thread until the user quits.
+pub fn spawn() {
+    let mut terminal = match setup() {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            error!("Failed to start the dashboard: {}", err);
+            return;
+        }
+    };
+
+    // Restore the terminal before printing a panic
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        hook(info);
+    }));
+
+    let runtime = tokio::runtime::Handle::current();
+    std::thread::spawn(move || {
+        let mut history = VecDeque::with_capacity(HASHRATE_HISTORY);
+        let mut last_hashes = metrics().hashes.load(Ordering::Relaxed);
+        let mut last_sample = Instant::now();
+        loop {
+            // Sample the live hashrate
+            if last_sample.elapsed().ge(&Duration::from_secs(1)) {
+                let hashes = metrics().hashes.load(Ordering::Relaxed);
+                let hashrate = (hashes.saturating_sub(last_hashes) as f64
+                    / last_sample.elapsed().as_secs_f64()) as u64;
+                if history.len().eq(&HASHRATE_HISTORY) {
+                    history.pop_front();
+                }
+                history.push_back(hashrate);
+                last_hashes = hashes;
+                last_sample = Instant::now();
+            }
+
+            terminal.draw(|frame| draw(frame, &history)).ok();
+
+            // Quit on q, Esc, or Ctrl-C
+            if event::poll(Duration::from_millis(250)).unwrap_or(false) {
+                if let Ok(Event::Key(key)) = event::read() {
+                    let ctrl_c = key.code.eq(&KeyCode::Char('c'))
+                        && key.modifiers.contains(KeyModifiers::CONTROL);
+                    if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        restore();
+                        runtime.block_on(session::stop(
+                            "Quit from the dashboard".to_string(),
+                            exit::OK,
+                        ));
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn setup() -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(io::stdout()))
+}
+
+fn restore() {
+    disable_raw_mode().ok();
+    execute!(io::stdout(), LeaveAlternateScreen).ok();
+}
+
+fn draw(frame: &mut Frame, history: &VecDeque<u64>) {
+    let metrics = metrics();
+    let status = status();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(8),
+            Constraint::Min(6),
+            Constraint::Length(8),
+            Constraint::Length(RECENT_TRANSACTIONS as u16 + 3),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    // Session stats
+    let label = Style::default().add_modifier(Modifier::BOLD);
+    let stat = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<18}", name), label),
+            Span::raw(value),
+        ])
+    };
+    let remaining = status.cutoff_at.saturating_sub(Utc::now().timestamp());
+    let stats = vec![
+        stat(
+            "Round",
+            format!(
+                "{} (challenge {})",
+                status.round,
+                bs58::encode(status.challenge).into_string()
+            ),
+        ),
+        stat(
+            "Cutoff",
+            if remaining.gt(&0) {
+                format!("{} sec remaining", remaining)
+            } else {
+                "Submitting...".to_string()
+            },
+        ),
+        stat(
+            "Best difficulty",
+            format!(
+                "{} last round, {} this session",
+                metrics.best_difficulty.load(Ordering::Relaxed),
+                status.session_best_difficulty
+            ),
+        ),
+        stat(
+            "Next reset",
+            match status.reset_at.saturating_sub(Utc::now().timestamp()) {
+                secs if secs.gt(&0) => format!("{} sec", secs),
+                _ => "due".to_string(),
+            },
+        ),
+        stat(
+            "Best hash so far",
+            if status.round_expected_reward.gt(&0) {
+                format!(
+                    "difficulty {}, worth {} ORE",
+                    status.round_best_difficulty,
+                    amount_u64_to_string(status.round_expected_reward)
+                )
+            } else {
+                format!(
+                    "difficulty {}, below the min difficulty",
+                    status.round_best_difficulty
+                )
+            },
+        ),
+        stat(
+            "Transactions",
+            format!(
+                "{} landed, {} expired",
+                metrics.landed.load(Ordering::Relaxed),
+                metrics.expired.load(Ordering::Relaxed)
+            ),
+        ),
+        stat(
+            "Rewards",
+            format!(
+                "{} ORE mined, {} ORE unclaimed",
+                amount_u64_to_string(metrics.rewards.load(Ordering::Relaxed)),
+                amount_u64_to_string(metrics.unclaimed_balance.load(Ordering::Relaxed))
+            ),
+        ),
+        stat(
+            "Fees",
+            format!(
+                "{} SOL",
+                lamports_to_sol(metrics.fees.load(Ordering::Relaxed))
+            ),
+        ),
+    ];
+    frame.render_widget(
+        Paragraph::new(stats).block(Block::default().borders(Borders::ALL).title(" ORE ")),
+        chunks[0],
+    );
+
+    // Hashrate graph, newest samples on the right
+    let width = chunks[1].width.saturating_sub(2) as usize;
+    let samples: Vec<u64> = history
+        .iter()
+        .skip(history.len().saturating_sub(width))
+        .copied()
+        .collect();
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " Hashrate: {} H/sec ",
+                samples.last().copied().unwrap_or_default()
+            )))
+            .data(&samples)
+            .style(Style::default().fg(Color::Cyan)),
+        chunks[1],
+    );
+
+    // Best difficulty of each round
+    let labels: Vec<(String, u64)> = status
+        .difficulties
+        .iter()
+        .map(|(difficulty, rounds)| (difficulty.to_string(), *rounds))
+        .collect();
+    let bars: Vec<(&str, u64)> = labels
+        .iter()
+        .map(|(label, rounds)| (label.as_str(), *rounds))
+        .collect();
+    frame.render_widget(
+        BarChart::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Rounds by best difficulty "),
+            )
+            .data(&bars)
+            .bar_width(3)
+            .bar_style(Style::default().fg(Color::Yellow)),
+        chunks[2],
+    );
+
+    // Recent landed transactions
+    let rows: Vec<Row> = status
+        .recent
+        .iter()
+        .map(|tx| {
+            Row::new(vec![
+                format_timestamp(tx.timestamp),
+                tx.difficulty.to_string(),
+                amount_u64_to_string(tx.reward),
+                lamports_to_sol(tx.fee).to_string(),
+                tx.signature.clone(),
+            ])
+        })
+        .collect();
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(23),
+                Constraint::Length(10),
+                Constraint::Length(14),
+                Constraint::Length(12),
+                Constraint::Min(20),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "Time",
+                "Difficulty",
+                "Reward (ORE)",
+                "Fee (SOL)",
+                "Signature",
+            ])
+            .style(label),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Landed transactions "),
+        ),
+        chunks[3],
+    );
+
+    frame.render_widget(
+        Paragraph::new("Press q to quit").style(Style::default().fg(Color::DarkGray)),
+        chunks[4],
+    );
+}
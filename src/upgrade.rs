@@ -1,116 +1,188 @@
+use std::str::FromStr;
+
 use colored::*;
-use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use ore_api::consts::{MINT_V1_ADDRESS, TOKEN_DECIMALS_V1};
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::pubkey::Pubkey;
 use spl_token::amount_to_ui_amount;
 
 use crate::{
     cu_limits::CU_LIMIT_UPGRADE,
+    error::OreError,
+    exit,
+    output::{finish_batch, new_batch_progress, print_status},
+    preview::Preview,
+    program,
     send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64_v1, ask_confirm},
+    utils::amount_f64_to_u64_v1,
     Miner, UpgradeArgs,
 };
 
 impl Miner {
-    pub async fn upgrade(&self, args: UpgradeArgs) {
-        let signer = &self.signer();
-        let beneficiary = self.get_or_initialize_ata().await;
-        let (sender, sender_balance) = self.get_ata_v1().await;
+    pub async fn upgrade(&self, args: UpgradeArgs) -> Result<(), OreError> {
+        let signer = &self.signer()?;
 
-        let amount_f64 = match args.amount {
-            Some(f64) => f64,
-            None => {
-                println!(
-                    "Defaulting to max amount of v1 Ore token in wallet: {}",
-                    sender_balance
+        // Collect v1 token accounts
+        let senders = if args.accounts.is_empty() {
+            self.get_token_accounts_v1().await?
+        } else {
+            let mut senders = vec![];
+            for address in args.accounts.iter() {
+                senders.push(
+                    Pubkey::from_str(address)
+                        .map_err(|_| OreError::InvalidAddress(address.clone()))?,
                 );
-                sender_balance
             }
+            senders
         };
-        let amount = amount_f64_to_u64_v1(amount_f64);
-        let amount_ui = amount_to_ui_amount(amount, ore_api::consts::TOKEN_DECIMALS_V1);
 
-        if !ask_confirm(
-            format!(
-                "\n You are about to upgrade {}. \n\nAre you sure you want to continue? [Y/n]",
-                format!("{} ORE", amount_ui).bold(),
+        // Fetch balances and calculate upgrade amounts
+        let mut upgrades = vec![];
+        let progress_bar = new_batch_progress(senders.len());
+        progress_bar.set_message("Fetching balances...");
+        for sender in senders {
+            let balance = self.get_token_balance_v1(sender).await;
+            progress_bar.inc(1);
+            let balance = match balance {
+                Ok(balance) => balance,
+                Err(err) => {
+                    print_status(format!("{} {}: {}", "ERROR".bold().red(), sender, err));
+                    continue;
+                }
+            };
+            let amount = match args.amount {
+                Some(amount) => amount_f64_to_u64_v1(amount).min(balance),
+                None => balance,
+            };
+            if amount.eq(&0) {
+                print_status(format!("Skipping {}: no v1 ORE to upgrade", sender));
+                continue;
+            }
+            upgrades.push((sender, amount));
+        }
+        finish_batch(&progress_bar);
+        if upgrades.is_empty() {
+            print_status("No v1 ORE token accounts to upgrade".to_string());
+            return Ok(());
+        }
+
+        // Preview upgrades and confirm
+        let total = upgrades.iter().map(|(_, amount)| amount).sum::<u64>();
+        let fee = self
+            .estimate_fee(&ComputeBudget::Fixed(CU_LIMIT_UPGRADE))
+            .await?;
+        let mut preview = Preview::new("Upgrades");
+        for (sender, amount) in upgrades.iter() {
+            preview = preview.row(
+                &sender.to_string(),
+                format!("{} ORE", amount_to_ui_amount(*amount, TOKEN_DECIMALS_V1)),
+            );
+        }
+        let preview = preview
+            .row(
+                "Total",
+                format!("{} ORE", amount_to_ui_amount(total, TOKEN_DECIMALS_V1)),
             )
-            .as_str(),
-        ) {
-            return;
+            .fee(fee.saturating_mul(upgrades.len() as u64));
+        if args.dry_run {
+            preview.print();
+            return Ok(());
+        }
+        if !preview.confirm() {
+            return Ok(());
         }
 
-        let ix = ore_api::instruction::upgrade(signer.pubkey(), beneficiary, sender, amount);
-        match self
-            .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_UPGRADE), false)
-            .await
-        {
-            Ok(_sig) => {}
-            Err(err) => {
-                println!("error: {}", err);
+        // Upgrade each account in sequence
+        let beneficiary = self.get_or_initialize_ata().await?;
+        let count = upgrades.len();
+        let mut failures = 0;
+        let progress_bar = new_batch_progress(count);
+        for (sender, amount) in upgrades.into_iter() {
+            progress_bar.set_message(format!(
+                "Upgrading {} ORE from {}",
+                amount_to_ui_amount(amount, TOKEN_DECIMALS_V1),
+                sender
+            ));
+            let ix = ore_api::instruction::upgrade(signer.pubkey(), beneficiary, sender, amount);
+            if let Err(err) = self
+                .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_UPGRADE), false)
+                .await
+            {
+                print_status(format!("{} {}: {}", "ERROR".bold().red(), sender, err));
+                exit::set(err.exit_code());
+                failures += 1;
             }
+            progress_bar.inc(1);
         }
+        finish_batch(&progress_bar);
+        print_status(format!(
+            "Upgraded {} of {} token account(s)",
+            count - failures,
+            count
+        ));
+        Ok(())
     }
 
-    // asserts that token account exists and gets balance
-    async fn get_ata_v1(&self) -> (Pubkey, f64) {
-        // Initialize client.
-        let signer = self.signer();
-        let client = self.rpc_client.clone();
-
-        // Derive assoicated token address (for v1 account)
-        let token_account_pubkey_v1 = spl_associated_token_account::get_associated_token_address(
-            &signer.pubkey(),
-            &ore_api::consts::MINT_V1_ADDRESS,
-        );
+    // finds all v1 token accounts owned by the signer
+    async fn get_token_accounts_v1(&self) -> Result<Vec<Pubkey>, OreError> {
+        let signer = self.signer()?;
+        let accounts = self
+            .rpc_client
+            .get_token_accounts_by_owner(
+                &signer.pubkey(),
+                TokenAccountsFilter::Mint(MINT_V1_ADDRESS),
+            )
+            .await?;
+        Ok(accounts
+            .iter()
+            .filter_map(|account| Pubkey::from_str(&account.pubkey).ok())
+            .collect())
+    }
 
-        // Get token account balance
-        let balance = match client.get_token_account(&token_account_pubkey_v1).await {
-            Ok(None) => {
-                panic!("v1 token account doesn't exist")
-            }
-            Ok(Some(token_account)) => match token_account.token_amount.ui_amount {
-                Some(ui_amount) => ui_amount,
-                None => {
-                    panic!(
-                        "Error parsing token account UI amount: {}",
+    // asserts that token account exists and gets balance
+    async fn get_token_balance_v1(&self, address: Pubkey) -> Result<u64, String> {
+        match self.rpc_client.get_token_account(&address).await {
+            Ok(None) => Err("v1 token account doesn't exist".to_string()),
+            Ok(Some(token_account)) => {
+                if token_account.mint.ne(&MINT_V1_ADDRESS.to_string()) {
+                    return Err("not a v1 ORE token account".to_string());
+                }
+                u64::from_str(token_account.token_amount.amount.as_str()).map_err(|_| {
+                    format!(
+                        "Error parsing token account amount: {}",
                         token_account.token_amount.amount
                     )
-                }
-            },
-            Err(err) => {
-                panic!("Error fetching token account: {}", err)
+                })
             }
-        };
-
-        // Return v1 token account address
-        (token_account_pubkey_v1, balance)
+            Err(err) => Err(format!("Error fetching token account: {}", err)),
+        }
     }
 
-    async fn get_or_initialize_ata(&self) -> Pubkey {
+    async fn get_or_initialize_ata(&self) -> Result<Pubkey, OreError> {
         // Initialize client
-        let signer = self.signer();
+        let signer = self.signer()?;
         let client = self.rpc_client.clone();
 
         // Derive assoicated token address (ata)
         let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
             &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
+            &program::mint(),
         );
 
         // Check if ata already exists or init
         if let Err(_err) = client.get_token_account(&token_account_pubkey).await {
-            println!("Initializing v2 token account...");
+            print_status("Initializing v2 token account...".to_string());
             let ix = spl_associated_token_account::instruction::create_associated_token_account(
                 &signer.pubkey(),
                 &signer.pubkey(),
-                &ore_api::consts::MINT_ADDRESS,
+                &program::mint(),
                 &spl_token::id(),
             );
             self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
-                .await
-                .ok();
+                .await?;
         }
 
         // Return token account address
-        token_account_pubkey
+        Ok(token_account_pubkey)
     }
 }
@@ -1,53 +1,266 @@
-use std::io::Read;
+use std::{
+    io::Read,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use cached::proc_macro::cached;
+use chrono::{DateTime, Utc};
 use ore_api::{
-    consts::{
-        CONFIG_ADDRESS, MINT_ADDRESS, PROOF, TOKEN_DECIMALS, TOKEN_DECIMALS_V1, TREASURY_ADDRESS,
-    },
-    state::{Config, Proof, Treasury},
+    consts::{EPOCH_DURATION, PROOF, TOKEN_DECIMALS, TOKEN_DECIMALS_V1},
+    state::{Bus, Config, Proof, Treasury},
+};
+use ore_utils::{AccountDeserialize, Discriminator};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
 };
-use ore_utils::AccountDeserialize;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_program::{pubkey::Pubkey, sysvar};
-use solana_sdk::clock::Clock;
-use spl_associated_token_account::get_associated_token_address;
+use solana_program::{program_pack::Pack, pubkey::Pubkey, sysvar};
+use solana_sdk::{clock::Clock, signature::read_keypair_file};
 
-pub async fn _get_treasury(client: &RpcClient) -> Treasury {
-    let data = client
-        .get_account_data(&TREASURY_ADDRESS)
-        .await
-        .expect("Failed to get treasury account");
-    *Treasury::try_from_bytes(&data).expect("Failed to parse treasury account")
+use crate::{cache, error::OreError, exit, program};
+
+/// Whether to answer prompts without waiting for input, set by `--yes`.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// How long the treasury is cached, in seconds.
+const TREASURY_TTL: i64 = 60 * 60;
+
+/// How long token mints are cached, in seconds. Their decimals never change.
+const MINT_TTL: i64 = 24 * 60 * 60;
+
+pub async fn _get_treasury(client: &RpcClient) -> Result<Treasury, OreError> {
+    let address = program::treasury_address();
+    let data = match cache::get(client, &address) {
+        Some(data) => data,
+        None => {
+            let data = client
+                .get_account_data(&address)
+                .await
+                .map_err(|err| OreError::fetch("treasury", err))?;
+            cache::insert(
+                client,
+                &address,
+                &data,
+                Utc::now().timestamp() + TREASURY_TTL,
+            );
+            data
+        }
+    };
+    Treasury::try_from_bytes(&data)
+        .copied()
+        .map_err(|_| OreError::InvalidAccount("treasury"))
 }
 
-pub async fn get_config(client: &RpcClient) -> Config {
+#[tracing::instrument(skip_all)]
+pub async fn get_config(client: &RpcClient) -> Result<Config, OreError> {
+    if let Some(config) = cached_config(client) {
+        return Ok(config);
+    }
     let data = client
-        .get_account_data(&CONFIG_ADDRESS)
+        .get_account_data(&program::config_address())
         .await
-        .expect("Failed to get config account");
-    *Config::try_from_bytes(&data).expect("Failed to parse config account")
+        .map_err(|err| OreError::fetch("config", err))?;
+    let config = Config::try_from_bytes(&data)
+        .copied()
+        .map_err(|_| OreError::InvalidAccount("config"))?;
+    cache_config(client, &config, &data);
+    Ok(config)
+}
+
+fn cached_config(client: &RpcClient) -> Option<Config> {
+    let data = cache::get(client, &program::config_address())?;
+    Config::try_from_bytes(&data).ok().copied()
+}
+
+/// Caches the config until the next epoch reset, the only time it changes.
+fn cache_config(client: &RpcClient, config: &Config, data: &[u8]) {
+    let expires_at = config.last_reset_at.saturating_add(EPOCH_DURATION);
+    cache::insert(client, &program::config_address(), data, expires_at);
+}
+
+/// Returns the decimals of a token mint.
+pub async fn get_mint_decimals(client: &RpcClient, mint: &Pubkey) -> Result<u8, OreError> {
+    let data = match cache::get(client, mint) {
+        Some(data) => data,
+        None => {
+            let data = client
+                .get_account_data(mint)
+                .await
+                .map_err(|err| OreError::fetch("mint", err))?;
+            cache::insert(client, mint, &data, Utc::now().timestamp() + MINT_TTL);
+            data
+        }
+    };
+    spl_token::state::Mint::unpack(&data)
+        .map(|mint| mint.decimals)
+        .map_err(|_| OreError::InvalidAccount("mint"))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_busses(client: &RpcClient) -> Result<Vec<Bus>, OreError> {
+    let data = get_accounts_data(client, "bus", &program::bus_addresses()).await?;
+    decode_busses(data)
+}
+
+/// The program accounts read together by a mining round, fetched in one request.
+pub struct MiningState {
+    pub config: Config,
+    pub clock: Clock,
+    pub busses: Vec<Bus>,
+}
+
+/// Fetches the config, clock, and busses with one `getMultipleAccounts` request.
+#[tracing::instrument(skip_all)]
+pub async fn get_mining_state(client: &RpcClient) -> Result<MiningState, OreError> {
+    let config = cached_config(client);
+    let addresses = mining_state_addresses(config.is_none());
+    let data = get_accounts_data(client, "mining state", &addresses).await?;
+    decode_mining_state(client, config, data)
+}
+
+/// Fetches the config, clock, busses, and the proof of the authority with one
+/// `getMultipleAccounts` request.
+#[tracing::instrument(skip_all)]
+pub async fn get_mining_state_with_proof(
+    client: &RpcClient,
+    authority: Pubkey,
+) -> Result<(MiningState, Proof), OreError> {
+    let config = cached_config(client);
+    let mut addresses = mining_state_addresses(config.is_none());
+    addresses.push(proof_pubkey(authority));
+    let mut data = get_accounts_data(client, "mining state", &addresses).await?;
+    let proof = decode_account::<Proof>("proof", data.pop().flatten())?;
+    Ok((decode_mining_state(client, config, data)?, proof))
+}
+
+/// Returns the addresses of the mining state, leaving out the config if it is cached.
+fn mining_state_addresses(with_config: bool) -> Vec<Pubkey> {
+    let mut addresses = vec![];
+    if with_config {
+        addresses.push(program::config_address());
+    }
+    addresses.push(sysvar::clock::ID);
+    addresses.extend(program::bus_addresses());
+    addresses
+}
+
+fn decode_mining_state(
+    client: &RpcClient,
+    config: Option<Config>,
+    data: Vec<Option<Vec<u8>>>,
+) -> Result<MiningState, OreError> {
+    let mut data = data.into_iter();
+    let config = match config {
+        Some(config) => config,
+        None => {
+            let bytes = data.next().flatten();
+            let config = decode_account::<Config>("config", bytes.clone())?;
+            cache_config(client, &config, &bytes.unwrap_or_default());
+            config
+        }
+    };
+    let clock = data
+        .next()
+        .flatten()
+        .ok_or(OreError::AccountNotFound("clock"))
+        .and_then(|data| {
+            bincode::deserialize::<Clock>(&data).map_err(|_| OreError::InvalidAccount("clock"))
+        })?;
+    let busses = decode_busses(data.collect())?;
+    Ok(MiningState {
+        config,
+        clock,
+        busses,
+    })
+}
+
+fn decode_busses(data: Vec<Option<Vec<u8>>>) -> Result<Vec<Bus>, OreError> {
+    data.into_iter()
+        .map(|data| decode_account::<Bus>("bus", data))
+        .collect()
+}
+
+fn decode_account<T: AccountDeserialize + Copy>(
+    name: &'static str,
+    data: Option<Vec<u8>>,
+) -> Result<T, OreError> {
+    let data = data.ok_or(OreError::AccountNotFound(name))?;
+    T::try_from_bytes(&data)
+        .copied()
+        .map_err(|_| OreError::InvalidAccount(name))
+}
+
+/// Fetches the data of several accounts with `getMultipleAccounts`, in as few requests as
+/// the RPC allows. Missing accounts are `None`.
+pub async fn get_accounts_data(
+    client: &RpcClient,
+    name: &'static str,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<Vec<u8>>>, OreError> {
+    let mut data = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client
+            .get_multiple_accounts(chunk)
+            .await
+            .map_err(|err| OreError::fetch(name, err))?;
+        data.extend(accounts.into_iter().map(|account| account.map(|a| a.data)));
+    }
+    Ok(data)
 }
 
-pub async fn get_proof_with_authority(client: &RpcClient, authority: Pubkey) -> Proof {
+pub async fn get_proof_with_authority(
+    client: &RpcClient,
+    authority: Pubkey,
+) -> Result<Proof, OreError> {
     let proof_address = proof_pubkey(authority);
     get_proof(client, proof_address).await
 }
 
-pub async fn get_proof(client: &RpcClient, address: Pubkey) -> Proof {
+#[tracing::instrument(skip_all)]
+pub async fn get_proof(client: &RpcClient, address: Pubkey) -> Result<Proof, OreError> {
     let data = client
         .get_account_data(&address)
         .await
-        .expect("Failed to get miner account");
-    *Proof::try_from_bytes(&data).expect("Failed to parse miner account")
+        .map_err(|err| OreError::fetch("proof", err))?;
+    Proof::try_from_bytes(&data)
+        .copied()
+        .map_err(|_| OreError::InvalidAccount("proof"))
 }
 
-pub async fn get_clock(client: &RpcClient) -> Clock {
-    let data = client
-        .get_account_data(&sysvar::clock::ID)
+/// Fetches every proof account of the program with a filtered `getProgramAccounts`.
+#[tracing::instrument(skip_all)]
+pub async fn get_proofs(client: &RpcClient) -> Result<Vec<(Pubkey, Proof)>, OreError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(8 + std::mem::size_of::<Proof>() as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![Proof::discriminator()])),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let accounts = client
+        .get_program_accounts_with_config(&program::program_id(), config)
         .await
-        .expect("Failed to get miner account");
-    bincode::deserialize::<Clock>(&data).expect("Failed to deserialize clock")
+        .map_err(|err| OreError::fetch("proof", err))?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            Proof::try_from_bytes(&account.data)
+                .ok()
+                .map(|proof| (address, *proof))
+        })
+        .collect())
+}
+
+pub fn calculate_multiplier(balance: u64, top_balance: u64) -> f64 {
+    1.0 + (balance as f64 / top_balance as f64).min(1.0f64)
 }
 
 pub fn amount_u64_to_string(amount: u64) -> String {
@@ -66,25 +279,159 @@ pub fn amount_f64_to_u64_v1(amount: f64) -> u64 {
     (amount * 10f64.powf(TOKEN_DECIMALS_V1 as f64)) as u64
 }
 
+pub fn format_timestamp(timestamp: i64) -> String {
+    match DateTime::from_timestamp(timestamp, 0) {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Parses exactly `N` hex encoded bytes.
+pub fn parse_hex<const N: usize>(s: &str) -> Result<[u8; N], String> {
+    hex::decode(s.trim())
+        .map_err(|err| err.to_string())?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("Expected {} bytes, got {}", N, bytes.len()))
+}
+
+/// Parses a duration in seconds, with an optional `s`, `m`, `h`, or `d` suffix.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value = value
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid duration: {:?}", s))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("Invalid duration unit: {:?}", unit)),
+    };
+    Ok(value.saturating_mul(multiplier))
+}
+
+/// Parses a size in bytes, with an optional `K`, `M`, or `G` suffix.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let value = value
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid size: {:?}", s))?;
+    let multiplier = match unit.to_ascii_uppercase().trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => return Err(format!("Invalid size unit: {:?}", unit)),
+    };
+    Ok(value.saturating_mul(multiplier))
+}
+
+pub fn ore_dir() -> PathBuf {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_else(|_| ".".to_string());
+    let dir = PathBuf::from(home).join(".config").join("ore");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Returns the keypair files in a directory, sorted by path.
+pub fn keypair_files(dir: &str) -> std::io::Result<Vec<String>> {
+    let mut filepaths: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && read_keypair_file(path).is_ok())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    filepaths.sort();
+    Ok(filepaths)
+}
+
+/// Answers yes to confirmation prompts, and the default to other questions, without
+/// waiting for input.
+pub fn set_assume_yes() {
+    ASSUME_YES.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether prompts are answered without waiting for input.
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
+
 pub fn ask_confirm(question: &str) -> bool {
-    println!("{}", question);
+    // Prompts go to stderr to keep stdout parseable
+    eprintln!("{}", question);
+    if assume_yes() {
+        return true;
+    }
     loop {
         let mut input = [0];
-        let _ = std::io::stdin().read(&mut input);
+        // Stdin is closed, e.g. when the keypair was piped in
+        if let Ok(0) | Err(_) = std::io::stdin().read(&mut input) {
+            exit::set(exit::DECLINED);
+            return false;
+        }
         match input[0] as char {
             'y' | 'Y' => return true,
-            'n' | 'N' => return false,
-            _ => println!("y/n only please."),
+            'n' | 'N' => {
+                exit::set(exit::DECLINED);
+                return false;
+            }
+            _ => eprintln!("y/n only please."),
+        }
+    }
+}
+
+/// Prompts for a line of input, returning the default if it is left empty.
+pub fn ask(question: &str, default: &str) -> String {
+    if assume_yes() {
+        return default.to_string();
+    }
+    if default.is_empty() {
+        eprint!("{}: ", question);
+    } else {
+        eprint!("{} [{}]: ", question, default);
+    }
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).ok();
+    match input.trim() {
+        "" => default.to_string(),
+        input => input.to_string(),
+    }
+}
+
+/// Prompts for a yes or no answer, returning the default if it is left empty.
+pub fn ask_yes_no(question: &str, default: bool) -> bool {
+    let hint = if default { "Y/n" } else { "y/N" };
+    if assume_yes() {
+        return default;
+    }
+    loop {
+        eprint!("{} [{}] ", question, hint);
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).ok();
+        match input.trim().to_lowercase().as_str() {
+            "" => return default,
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => eprintln!("y/n only please."),
         }
     }
 }
 
 #[cached]
 pub fn proof_pubkey(authority: Pubkey) -> Pubkey {
-    Pubkey::find_program_address(&[PROOF, authority.as_ref()], &ore_api::ID).0
+    Pubkey::find_program_address(&[PROOF, authority.as_ref()], &program::program_id()).0
 }
 
-#[cached]
 pub fn treasury_tokens_pubkey() -> Pubkey {
-    get_associated_token_address(&TREASURY_ADDRESS, &MINT_ADDRESS)
+    program::treasury_tokens_address()
 }
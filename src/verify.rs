@@ -0,0 +1,43 @@
+use colored::*;
+use drillx::Solution;
+
+use crate::{args::VerifyArgs, error::OreError, output::print_json, schema::VerifyDocument, Miner};
+
+impl Miner {
+    pub fn verify(&self, args: VerifyArgs) -> Result<(), OreError> {
+        // Recompute the hash
+        let solution = Solution::new(args.digest, args.nonce.to_le_bytes());
+        let hash = solution
+            .is_valid(&args.challenge)
+            .then(|| solution.to_hash());
+        if self.is_json() {
+            print_json(&VerifyDocument {
+                valid: hash.is_some(),
+                difficulty: hash.as_ref().map(|hash| hash.difficulty()),
+                hash: hash.as_ref().map(|hash| hex::encode(hash.h)),
+            });
+        }
+        let Some(hash) = hash else {
+            return Err(OreError::InvalidSolution(
+                "The digest does not match the challenge and nonce".to_string(),
+            ));
+        };
+
+        // Check the difficulty
+        let difficulty = hash.difficulty();
+        if !self.is_json() {
+            println!("{}: {}", "Hash".bold(), hex::encode(hash.h));
+            println!("{}: {}", "Difficulty".bold(), difficulty);
+        }
+        if difficulty.lt(&args.min_difficulty) {
+            return Err(OreError::InvalidSolution(format!(
+                "Difficulty {} is below the minimum of {}",
+                difficulty, args.min_difficulty
+            )));
+        }
+        if !self.is_json() {
+            println!("{} Valid solution", "OK".bold().green());
+        }
+        Ok(())
+    }
+}